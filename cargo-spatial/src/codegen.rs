@@ -1,7 +1,10 @@
 use crate::{config::Config, format_arg};
 use anyhow::{anyhow, Context, Result};
 use log::*;
-use spatialos_sdk_code_generator::{generator, schema_bundle};
+use spatialos_sdk_code_generator::{
+    generator::{self, CodegenOptions},
+    schema_bundle,
+};
 use std::{
     fmt::{Display, Formatter},
     fs::{self, File},
@@ -102,7 +105,11 @@ pub fn run_codegen(config: &Config) -> Result<()> {
     let bundle = schema_bundle::load_bundle(&contents)
         .with_context(|| format!("Failed to parse contents of {}", bundle_json_path.display()))?;
 
-    let generated_file = generator::generate_code(bundle);
+    let options = CodegenOptions {
+        serde: config.codegen_serde,
+        borrowed_views: config.codegen_borrowed_views,
+    };
+    let generated_file = generator::generate_code_with_options(bundle, &options);
 
     // Write the generated code to the output file.
     File::create(&config.codegen_out)
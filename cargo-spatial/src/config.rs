@@ -28,6 +28,16 @@ pub struct Config {
     /// Defaults to `src/generated.rs`.
     pub codegen_out: String,
 
+    /// Whether generated types should derive serde's `Serialize` and `Deserialize`.
+    ///
+    /// Requires the `serde` feature of `spatialos-sdk`. Defaults to `false`.
+    pub codegen_serde: bool,
+
+    /// Whether to generate borrowed `FooRef<'a>` views alongside the generated types.
+    ///
+    /// Defaults to `false`.
+    pub codegen_borrowed_views: bool,
+
     /// The directories containing schema files for the project.
     ///
     /// Defaults to `./schema`.
@@ -63,6 +73,8 @@ impl Default for Config {
             runtime_version: "14.5.4".into(),
             workers: vec![".".into()],
             codegen_out: "src/generated.rs".into(),
+            codegen_serde: false,
+            codegen_borrowed_views: false,
             schema_paths: vec![],
             build_dir: "./build".into(),
             schema_build_dir: None,
@@ -274,6 +274,12 @@ impl Component for EntityIdTest {
 
         self.merge_update(copy);
     }
+
+    fn diff(old: &Self, new: &Self) -> Self::Update {
+        EntityIdTestUpdate {
+            eid: if old.eid == new.eid { None } else { Some(new.eid) },
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -334,6 +340,12 @@ impl Component for EntityTest {
 
         self.merge_update(copy);
     }
+
+    fn diff(old: &Self, new: &Self) -> Self::Update {
+        EntityTestUpdate {
+            entity: if old.entity == new.entity { None } else { Some(new.entity.clone()) },
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -394,6 +406,12 @@ impl Component for EnumTestComponent {
 
         self.merge_update(copy);
     }
+
+    fn diff(old: &Self, new: &Self) -> Self::Update {
+        EnumTestComponentUpdate {
+            test: if old.test == new.test { None } else { Some(new.test) },
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -518,6 +536,12 @@ impl Component for Example {
 
         self.merge_update(copy);
     }
+
+    fn diff(old: &Self, new: &Self) -> Self::Update {
+        ExampleUpdate {
+            x: if old.x == new.x { None } else { Some(new.x) },
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -596,6 +620,14 @@ impl Component for Rotate {
 
         self.merge_update(copy);
     }
+
+    fn diff(old: &Self, new: &Self) -> Self::Update {
+        RotateUpdate {
+            angle: if old.angle == new.angle { None } else { Some(new.angle) },
+            center: if old.center == new.center { None } else { Some(new.center.clone()) },
+            radius: if old.radius == new.radius { None } else { Some(new.radius) },
+        }
+    }
 }
 
 
@@ -943,6 +975,13 @@ impl Component for EntityAcl {
 
         self.merge_update(copy);
     }
+
+    fn diff(old: &Self, new: &Self) -> Self::Update {
+        EntityAclUpdate {
+            read_acl: if old.read_acl == new.read_acl { None } else { Some(new.read_acl.clone()) },
+            component_write_acl: if old.component_write_acl == new.component_write_acl { None } else { Some(new.component_write_acl.clone()) },
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -1003,6 +1042,12 @@ impl Component for Interest {
 
         self.merge_update(copy);
     }
+
+    fn diff(old: &Self, new: &Self) -> Self::Update {
+        InterestUpdate {
+            component_interest: if old.component_interest == new.component_interest { None } else { Some(new.component_interest.clone()) },
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -1063,6 +1108,12 @@ impl Component for Metadata {
 
         self.merge_update(copy);
     }
+
+    fn diff(old: &Self, new: &Self) -> Self::Update {
+        MetadataUpdate {
+            entity_type: if old.entity_type == new.entity_type { None } else { Some(new.entity_type.clone()) },
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -1114,6 +1165,12 @@ impl Component for Persistence {
 
         self.merge_update(copy);
     }
+
+    fn diff(old: &Self, new: &Self) -> Self::Update {
+        PersistenceUpdate {
+
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -1174,6 +1231,12 @@ impl Component for Position {
 
         self.merge_update(copy);
     }
+
+    fn diff(old: &Self, new: &Self) -> Self::Update {
+        PositionUpdate {
+            coords: if old.coords == new.coords { None } else { Some(new.coords.clone()) },
+        }
+    }
 }
 
 
@@ -1362,6 +1425,12 @@ impl Component for PlayerClient {
 
         self.merge_update(copy);
     }
+
+    fn diff(old: &Self, new: &Self) -> Self::Update {
+        PlayerClientUpdate {
+            player_identity: if old.player_identity == new.player_identity { None } else { Some(new.player_identity.clone()) },
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -1413,6 +1482,12 @@ impl Component for System {
 
         self.merge_update(copy);
     }
+
+    fn diff(old: &Self, new: &Self) -> Self::Update {
+        SystemUpdate {
+
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -1555,6 +1630,14 @@ impl Component for Worker {
 
         self.merge_update(copy);
     }
+
+    fn diff(old: &Self, new: &Self) -> Self::Update {
+        WorkerUpdate {
+            worker_id: if old.worker_id == new.worker_id { None } else { Some(new.worker_id.clone()) },
+            worker_type: if old.worker_type == new.worker_type { None } else { Some(new.worker_type.clone()) },
+            connection: if old.connection == new.connection { None } else { Some(new.connection.clone()) },
+        }
+    }
 }
 
 
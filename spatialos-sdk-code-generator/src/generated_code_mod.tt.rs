@@ -9,7 +9,7 @@ use <#= vec!["super".to_string(); self.depth() + 1].join("::") #>::generated as
 let enum_def = self.get_enum_definition(enum_name);
 let enum_rust_name = self.rust_name(&enum_def.qualified_name);
 #>
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]<#= self.serde_attributes() #>
 pub enum <#= enum_rust_name #> {
 <# for enum_value in &enum_def.values { #>
     <#= enum_value.name #>,<# } #>
@@ -50,7 +50,7 @@ impl Into<u32> for <#= enum_rust_name #> {
 impl_field_for_enum_field!(<#= enum_rust_name #>);
 <# } #>
 /* Types. */<# for type_name in &self.types { let type_def = self.get_type_definition(type_name); #>
-#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord)]<#= self.serde_attributes() #>
 pub struct <#= self.rust_name(&type_def.qualified_name) #> {<#
     for field in &type_def.fields {
     #>
@@ -70,13 +70,33 @@ impl ObjectField for <#= self.rust_name(&type_def.qualified_name) #> {
         <#= self.serialize_field(field, "output") #>;<# } #>
     }
 }
-<# } #>
+<# if self.borrowed_views() { #>
+#[derive(Debug, Clone)]
+pub struct <#= self.rust_name(&type_def.qualified_name) #>Ref<'a> {<#
+    for field in &type_def.fields {
+    #>
+    pub <#= field.name #>: <#= self.generate_ref_field_type(field) #>,<# } #>
+    _marker: std::marker::PhantomData<&'a ()>,
+}
+impl<'a> ObjectFieldRef<'a> for <#= self.rust_name(&type_def.qualified_name) #> {
+    type Ref = <#= self.rust_name(&type_def.qualified_name) #>Ref<'a>;
+
+    fn from_object_ref(input: &'a SchemaObject) -> Result<Self::Ref> {
+        Ok(<#= self.rust_name(&type_def.qualified_name) #>Ref {<#
+            for field in &type_def.fields {
+            #>
+            <#= field.name #>: <#= self.deserialize_ref_field(field, "input") #>,<# } #>
+            _marker: std::marker::PhantomData,
+        })
+    }
+}
+<# } #><# } #>
 /* Components. */ <# for component_name in &self.components {
     let component = self.get_component_definition(component_name);
     let component_fields = self.get_component_fields(&component);
     let component_name = self.rust_name(&component.qualified_name);
     let update_name = format!("{}Update", component_name); #>
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default)]<#= self.serde_attributes() #>
 pub struct <#= component_name #> {<#
     for field in &component_fields {
     #>
@@ -97,8 +117,28 @@ impl ObjectField for <#= component_name #> {
         <#= self.serialize_field(field, "output") #>;<# } #>
     }
 }
+<# if self.borrowed_views() { #>
+#[derive(Debug, Clone)]
+pub struct <#= component_name #>Ref<'a> {<#
+    for field in &component_fields {
+    #>
+    pub <#= field.name #>: <#= self.generate_ref_field_type(field) #>,<# } #>
+    _marker: std::marker::PhantomData<&'a ()>,
+}
 
-#[derive(Debug, Clone, Default)]
+impl<'a> ObjectFieldRef<'a> for <#= component_name #> {
+    type Ref = <#= component_name #>Ref<'a>;
+
+    fn from_object_ref(input: &'a SchemaObject) -> Result<Self::Ref> {
+        Ok(<#= component_name #>Ref {<#
+            for field in &component_fields {#>
+            <#= field.name #>: <#= self.deserialize_ref_field(field, "input") #>,<# } #>
+            _marker: std::marker::PhantomData,
+        })
+    }
+}
+<# } #>
+#[derive(Debug, Clone, Default)]<#= self.serde_attributes() #>
 pub struct <#= update_name #> {<#
     for field in &component_fields {
     #>
@@ -134,10 +174,34 @@ impl Update for <#= update_name #> {
         self.<#= event.name #>.append(&mut update.<#= event.name #>);<# } #>
     }
 }
+<# if self.borrowed_views() { #>
+#[derive(Debug, Clone)]
+pub struct <#= update_name #>Ref<'a> {<#
+    for field in &component_fields {
+    #>
+    pub <#= field.name #>: Option<<#= self.generate_ref_field_type(field) #>>,<# } #><#
+    for event in &component.events { #>
+    pub <#= event.name #>: ListRef<'a, <#= self.rust_fqname(&event.type_reference) #>>,<# } #>
+    _marker: std::marker::PhantomData<&'a ()>,
+}
+
+impl<'a> UpdateRef<'a> for <#= update_name #> {
+    type Ref = <#= update_name #>Ref<'a>;
 
+    fn from_schema_ref(update: &'a SchemaComponentUpdate) -> Result<Self::Ref> {
+        Ok(<#= update_name #>Ref {<#
+            for field in &component_fields {#>
+            <#= field.name #>: <#= self.deserialize_update_ref_field(field, "update") #>,<# } #><#
+            for event in &component.events { #>
+            <#= event.name #>: <#= self.deserialize_update_ref_event(event, "update") #>,<# } #>
+            _marker: std::marker::PhantomData,
+        })
+    }
+}
+<# } #>
 <# if (!&component.commands.is_empty()) { #>
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone)]<#= self.serde_attributes() #>
 pub enum <#= component_name #>CommandRequest {<#
     for command in &component.commands {
     #>
@@ -171,7 +235,7 @@ impl Request for <#= component_name #>CommandRequest {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone)]<#= self.serde_attributes() #>
 pub enum <#= component_name #>CommandResponse {<#
     for command in &component.commands {
     #>
@@ -233,5 +297,13 @@ impl Component for <#= component_name #> {
 
         self.merge_update(copy);
     }
+
+    fn diff(old: &Self, new: &Self) -> Self::Update {
+        <#= update_name #> {<# for field in &component_fields { #>
+            <#= field.name #>: if old.<#= field.name #> == new.<#= field.name #> { None } else { Some(new.<#= field.name #><#= if self.field_needs_clone(&field) { ".clone()" } else { "" } #>) },<# } #><#
+            for event in &component.events { #>
+            <#= event.name #>: Vec::new(),<# } #>
+        }
+    }
 }
 <# } #>
@@ -62,6 +62,18 @@ impl Package {
         self.path.len()
     }
 
+    fn serde_attributes(&self) -> &'static str {
+        if self.generated_code.borrow().options.serde {
+            "\n#[derive(spatialos_sdk::serde::Serialize, spatialos_sdk::serde::Deserialize)]\n#[serde(crate = \"spatialos_sdk::serde\")]"
+        } else {
+            ""
+        }
+    }
+
+    fn borrowed_views(&self) -> bool {
+        self.generated_code.borrow().options.borrowed_views
+    }
+
     fn rust_name(&self, qualified_name: &str) -> String {
         let tokens: Vec<&str> = qualified_name.split('.').collect();
         tokens[self.path.len()..].join("_")
@@ -219,6 +231,47 @@ impl Package {
         }
     }
 
+    fn generate_rust_ref_type_name(&self, value_type: &TypeReference) -> String {
+        match value_type {
+            TypeReference::Primitive(PrimitiveType::String) => "&'a str".to_string(),
+            TypeReference::Primitive(PrimitiveType::Bytes) => "&'a [u8]".to_string(),
+            TypeReference::Type(ref type_ref) => format!(
+                "{}Ref<'a>",
+                self.rust_fqname(&self.resolve_type_reference(&type_ref).qualified_name)
+            ),
+            _ => self.generate_rust_type_name(value_type),
+        }
+    }
+
+    fn generate_ref_field_type(&self, field: &FieldDefinition) -> String {
+        match field.field_type {
+            FieldDefinition_FieldType::Singular { ref type_reference } => {
+                self.generate_rust_ref_type_name(type_reference)
+            }
+            FieldDefinition_FieldType::Option { ref inner_type } => {
+                if self.is_type_recursive(inner_type) {
+                    format!(
+                        "Option<Box<{}>>",
+                        self.generate_rust_ref_type_name(inner_type)
+                    )
+                } else {
+                    format!("Option<{}>", self.generate_rust_ref_type_name(inner_type))
+                }
+            }
+            FieldDefinition_FieldType::List { ref inner_type } => {
+                format!("ListRef<'a, {}>", self.schema_type_name(inner_type))
+            }
+            FieldDefinition_FieldType::Map {
+                ref key_type,
+                ref value_type,
+            } => format!(
+                "MapRef<'a, {}, {}>",
+                self.schema_type_name(key_type),
+                self.schema_type_name(value_type)
+            ),
+        }
+    }
+
     fn is_type_recursive(&self, type_ref: &TypeReference) -> bool {
         fn is_recursive(
             gen_code: Rc<RefCell<GeneratedCode>>,
@@ -339,6 +392,25 @@ impl Package {
         )
     }
 
+    // Generates an expression which reads a borrowed view of a field from a schema field 'schema_field'.
+    fn deserialize_ref_field(&self, field: &FieldDefinition, schema_field: &str) -> String {
+        format!(
+            "{}.get_ref::<{}>({field}).map_err(Error::at_field::<Self>({field}))?",
+            schema_field,
+            self.field_type_name(&field.field_type),
+            field = field.field_id,
+        )
+    }
+
+    fn deserialize_update_ref_field(&self, field: &FieldDefinition, update: &str) -> String {
+        format!(
+            "{}.get_field_ref::<{}>({field}).map_err(Error::at_field::<Self>({field}))?",
+            update,
+            self.field_type_name(&field.field_type),
+            field = field.field_id,
+        )
+    }
+
     fn deserialize_update_event(
         &self,
         event: &ComponentDefinition_EventDefinition,
@@ -352,6 +424,14 @@ impl Package {
         )
     }
 
+    fn deserialize_update_ref_event(
+        &self,
+        event: &ComponentDefinition_EventDefinition,
+        update: &str,
+    ) -> String {
+        format!("ListRef::new({}.events(), {})", update, event.event_index)
+    }
+
     fn serialize_update_field(&self, field: &FieldDefinition, update: &str) -> String {
         format!(
             "{}.add_field::<{}>({}, &self.{})",
@@ -377,8 +457,27 @@ impl Package {
     }
 }
 
+/// Options that control the code generated for a schema bundle.
+#[derive(Debug, Clone, Default)]
+pub struct CodegenOptions {
+    /// Derive serde's `Serialize` and `Deserialize` traits for every generated type.
+    ///
+    /// The generated code refers to serde through `spatialos_sdk::serde`, so the `serde`
+    /// feature of `spatialos-sdk` must be enabled.
+    pub serde: bool,
+
+    /// Generate a borrowed `FooRef<'a>` view struct alongside each type and component,
+    /// and a `FooUpdateRef<'a>` view alongside each component update.
+    ///
+    /// Views read strings, bytes and collections directly from the underlying schema
+    /// data instead of copying them, which avoids allocating when only part of the data
+    /// in an op is needed.
+    pub borrowed_views: bool,
+}
+
 #[derive(Debug)]
 struct GeneratedCode {
+    options: CodegenOptions,
     root_package: Option<Package>,
     packages: BTreeSet<String>,
     enums: BTreeMap<String, EnumDefinition>,
@@ -476,8 +575,13 @@ fn generate_module(package: &Package) -> String {
 }
 
 pub fn generate_code(bundle: SchemaBundle) -> String {
+    generate_code_with_options(bundle, &CodegenOptions::default())
+}
+
+pub fn generate_code_with_options(bundle: SchemaBundle, options: &CodegenOptions) -> String {
     // Set up the root package.
     let generated_code = Rc::new(RefCell::new(GeneratedCode {
+        options: options.clone(),
         root_package: None,
         packages: BTreeSet::new(),
         enums: BTreeMap::new(),
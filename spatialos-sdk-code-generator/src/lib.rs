@@ -4,16 +4,19 @@ extern crate serde_derive;
 extern crate serde;
 extern crate serde_json;
 
+#[cfg(feature = "generator")]
 #[macro_use]
 extern crate t4rust_derive;
 
+#[cfg(feature = "generator")]
 extern crate heck;
 
+#[cfg(feature = "generator")]
 pub mod generator;
 #[allow(non_camel_case_types)]
 pub mod schema_bundle;
 
-#[cfg(test)]
+#[cfg(all(test, feature = "generator"))]
 mod tests {
     use crate::generator;
     use crate::schema_bundle;
@@ -40,4 +43,39 @@ mod tests {
             generator::generate_code(bundle.unwrap())
         );
     }
+
+    #[test]
+    fn generate_code_with_serde_derives() {
+        let mut file =
+            File::open("data/test.sb.json").expect("Unable to open the test schema bundle.");
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)
+            .expect("Unable to read the test schema bundle");
+
+        let bundle = schema_bundle::load_bundle(&contents).unwrap();
+        let options = generator::CodegenOptions {
+            serde: true,
+            ..Default::default()
+        };
+        let generated = generator::generate_code_with_options(bundle, &options);
+        assert!(generated.contains("#[serde(crate = \"spatialos_sdk::serde\")]"));
+    }
+
+    #[test]
+    fn generate_code_with_borrowed_views() {
+        let mut file =
+            File::open("data/test.sb.json").expect("Unable to open the test schema bundle.");
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)
+            .expect("Unable to read the test schema bundle");
+
+        let bundle = schema_bundle::load_bundle(&contents).unwrap();
+        let options = generator::CodegenOptions {
+            borrowed_views: true,
+            ..Default::default()
+        };
+        let generated = generator::generate_code_with_options(bundle, &options);
+        assert!(generated.contains("impl<'a> ObjectFieldRef<'a> for"));
+        assert!(generated.contains("impl<'a> UpdateRef<'a> for"));
+    }
 }
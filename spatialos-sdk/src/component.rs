@@ -2,6 +2,10 @@ use crate::schema::{self, *};
 use spatialos_sdk_sys::worker::*;
 use std::ops::DerefMut;
 
+mod buffer;
+
+pub use self::buffer::*;
+
 pub type ComponentId = Worker_ComponentId;
 
 // A trait that's implemented by a component to convert to/from schema handle types.
@@ -12,6 +16,13 @@ pub trait Component: ObjectField {
 
     fn merge_update(&mut self, update: Self::Update);
     fn merge_update_ref(&mut self, update: &Self::Update);
+
+    /// Returns the update that turns `old` into `new`.
+    ///
+    /// Only fields that differ between `old` and `new` are set in the update. Collection
+    /// fields that became empty are sent as cleared fields, so that applying the update
+    /// to `old` results in a value equal to `new`. The update has no events.
+    fn diff(old: &Self, new: &Self) -> Self::Update;
 }
 
 pub trait Update: Sized + Clone {
@@ -22,6 +33,19 @@ pub trait Update: Sized + Clone {
     fn merge(&mut self, other: Self);
 }
 
+/// An [`Update`] that can be read as a borrowed view of a [`SchemaComponentUpdate`].
+///
+/// This is implemented by the code generator when borrowed views are enabled, with `Ref`
+/// being the generated `FooUpdateRef<'a>` struct.
+///
+/// [`Update`]: trait.Update.html
+/// [`SchemaComponentUpdate`]: ../schema/struct.SchemaComponentUpdate.html
+pub trait UpdateRef<'a>: Update {
+    type Ref;
+
+    fn from_schema_ref(update: &'a SchemaComponentUpdate) -> schema::Result<Self::Ref>;
+}
+
 pub struct ComponentUpdate {
     pub schema_data: Owned<SchemaComponentUpdate>,
     pub component_id: ComponentId,
@@ -41,9 +65,10 @@ impl<U: Update> From<&U> for ComponentUpdate {
 
 /// Additional parameters for sending component updates.
 ///
-/// Additional parameters passed to [`Connection::send_component_update`]. Note that
-/// all parameters are kept private and the struct can only be initialized with
-/// default values in order to make it possible to add new parameters without a
+/// Additional parameters passed to [`Connection::send_component_update`],
+/// [`Connection::send_add_component`] and [`Connection::send_remove_component`].
+/// Note that all parameters are kept private and the struct can only be initialized
+/// with default values in order to make it possible to add new parameters without a
 /// breaking change.
 ///
 /// If you would like to use a method-chaining style when initializing the parameters,
@@ -101,6 +126,104 @@ impl UpdateParameters {
     }
 }
 
+/// Overrides whether the worker is interested in a single component type.
+///
+/// See [`ComponentInterest`] for more information.
+///
+/// [`ComponentInterest`]: struct.ComponentInterest.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InterestOverride {
+    pub component_id: ComponentId,
+    pub is_interested: bool,
+}
+
+impl InterestOverride {
+    pub fn new(component_id: ComponentId, is_interested: bool) -> Self {
+        InterestOverride {
+            component_id,
+            is_interested,
+        }
+    }
+
+    pub fn interested<C: Component>() -> Self {
+        InterestOverride::new(C::ID, true)
+    }
+
+    pub fn not_interested<C: Component>() -> Self {
+        InterestOverride::new(C::ID, false)
+    }
+
+    pub(crate) fn to_worker_sdk(self) -> Worker_InterestOverride {
+        Worker_InterestOverride {
+            component_id: self.component_id,
+            is_interested: self.is_interested as u8,
+        }
+    }
+}
+
+/// A set of component interest overrides for an entity.
+///
+/// Passed to [`Connection::send_component_interest`] in order to force the data for
+/// specific components on an entity to either always or never be sent to the
+/// worker, regardless of the worker's configured interest. Overrides do not apply
+/// to components that the worker is authoritative over, which are always sent.
+///
+/// # Examples
+///
+/// ```
+/// use spatialos_sdk::component::{ComponentInterest, InterestOverride};
+///
+/// let interest = ComponentInterest::new()
+///     .with_override(InterestOverride::new(1000, true))
+///     .with_override(InterestOverride::new(1001, false));
+///
+/// assert_eq!(2, interest.overrides().len());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ComponentInterest {
+    overrides: Vec<InterestOverride>,
+}
+
+impl ComponentInterest {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Opts in to receiving data for the component `C`.
+    pub fn with_interest<C: Component>(self) -> Self {
+        self.with_override(InterestOverride::interested::<C>())
+    }
+
+    /// Opts out of receiving data for the component `C`.
+    pub fn without_interest<C: Component>(self) -> Self {
+        self.with_override(InterestOverride::not_interested::<C>())
+    }
+
+    pub fn with_override(mut self, interest_override: InterestOverride) -> Self {
+        self.add_override(interest_override);
+        self
+    }
+
+    /// Adds an override to the set, replacing any existing override for the same
+    /// component.
+    pub fn add_override(&mut self, interest_override: InterestOverride) {
+        self.overrides
+            .retain(|existing| existing.component_id != interest_override.component_id);
+        self.overrides.push(interest_override);
+    }
+
+    pub fn overrides(&self) -> &[InterestOverride] {
+        &self.overrides
+    }
+
+    pub(crate) fn to_worker_sdk(&self) -> Vec<Worker_InterestOverride> {
+        self.overrides
+            .iter()
+            .map(|interest_override| interest_override.to_worker_sdk())
+            .collect()
+    }
+}
+
 #[derive(Debug)]
 pub struct ComponentDataRef<'a> {
     pub component_id: ComponentId,
@@ -122,6 +245,17 @@ impl<'a> ComponentDataRef<'a> {
 
         Some(self.schema_type.deserialize())
     }
+
+    pub fn get_ref<C>(&self) -> Option<schema::Result<<C as ObjectFieldRef<'a>>::Ref>>
+    where
+        C: Component + ObjectFieldRef<'a>,
+    {
+        if C::ID != self.component_id {
+            return None;
+        }
+
+        Some(C::from_object_ref(self.schema_type.fields()))
+    }
 }
 
 #[derive(Debug)]
@@ -145,4 +279,16 @@ impl<'a> ComponentUpdateRef<'a> {
 
         Some(self.schema_type.deserialize())
     }
+
+    pub(crate) fn get_ref<C>(&self) -> Option<schema::Result<<C::Update as UpdateRef<'a>>::Ref>>
+    where
+        C: Component,
+        C::Update: UpdateRef<'a>,
+    {
+        if C::ID != self.component_id {
+            return None;
+        }
+
+        Some(C::Update::from_schema_ref(self.schema_type))
+    }
 }
@@ -0,0 +1,224 @@
+use crate::{
+    component::{ComponentId, ComponentUpdate, UpdateParameters},
+    connection::Connection,
+    schema, EntityId,
+};
+use std::collections::HashMap;
+
+struct BufferedUpdate {
+    entity_id: EntityId,
+    update: ComponentUpdate,
+    parameters: UpdateParameters,
+}
+
+/// Buffers outgoing component updates, coalescing them into a single update per
+/// entity and component.
+///
+/// Workers often update the same component several times within a single frame. Adding
+/// each of those updates to an `UpdateBuffer` instead of sending them directly merges
+/// them at the schema level, so that only one update per `(EntityId, ComponentId)` pair
+/// is sent when the buffer is [flushed]. Fields set by later updates take precedence,
+/// and events from every update are kept in the order they were added.
+///
+/// # Examples
+///
+/// ```no_run
+/// use spatialos_sdk::{
+///     component::{ComponentUpdate, UpdateBuffer, UpdateParameters},
+///     connection::{Connection, WorkerConnection},
+///     EntityId,
+/// };
+///
+/// # let mut connection: WorkerConnection = unimplemented!();
+/// # let first: ComponentUpdate = unimplemented!();
+/// # let second: ComponentUpdate = unimplemented!();
+/// let mut buffer = UpdateBuffer::new();
+/// buffer.add(EntityId::new(1), first, UpdateParameters::new()).unwrap();
+/// buffer.add(EntityId::new(1), second, UpdateParameters::new()).unwrap();
+///
+/// // Sends a single update containing the changes from both updates.
+/// buffer.flush(&mut connection);
+/// ```
+///
+/// [flushed]: #method.flush
+#[derive(Default)]
+pub struct UpdateBuffer {
+    updates: Vec<BufferedUpdate>,
+    indices: HashMap<(EntityId, ComponentId), usize>,
+}
+
+impl UpdateBuffer {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the number of updates that will be sent when the buffer is flushed.
+    pub fn len(&self) -> usize {
+        self.updates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.updates.is_empty()
+    }
+
+    /// Adds `update` to the buffer, merging it into any update already buffered for the
+    /// same entity and component.
+    ///
+    /// The merged update allows loopback if any of the updates merged into it did, so
+    /// that none of their changes are missing from the worker's own view. If merging
+    /// fails, the buffer is left unchanged.
+    pub fn add<T: Into<ComponentUpdate>>(
+        &mut self,
+        entity_id: EntityId,
+        update: T,
+        parameters: UpdateParameters,
+    ) -> schema::Result<()> {
+        let update = update.into();
+        let key = (entity_id, update.component_id);
+
+        if let Some(&index) = self.indices.get(&key) {
+            let buffered = &mut self.updates[index];
+            buffered.update.schema_data.merge(&update.schema_data)?;
+            buffered.parameters.loopback |= parameters.loopback;
+            return Ok(());
+        }
+
+        self.indices.insert(key, self.updates.len());
+        self.updates.push(BufferedUpdate {
+            entity_id,
+            update,
+            parameters,
+        });
+
+        Ok(())
+    }
+
+    /// Sends every buffered update on `connection`, leaving the buffer empty. Returns
+    /// the number of updates sent.
+    ///
+    /// Updates are sent in the order in which their entity and component were first
+    /// added to the buffer. This doesn't flush the connection itself.
+    pub fn flush<C: Connection>(&mut self, connection: &mut C) -> usize {
+        self.indices.clear();
+
+        let count = self.updates.len();
+        for buffered in self.updates.drain(..) {
+            connection.send_component_update(
+                buffered.entity_id,
+                buffered.update,
+                buffered.parameters,
+            );
+        }
+
+        count
+    }
+
+    /// Discards every buffered update without sending it.
+    pub fn clear(&mut self) {
+        self.updates.clear();
+        self.indices.clear();
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod test {
+    use super::*;
+    use crate::{
+        schema::{SchemaComponentUpdate, SchemaInt32},
+        testing::{MockConnection, SentMessage},
+    };
+
+    fn update(component_id: ComponentId, value: i32) -> ComponentUpdate {
+        let mut schema_data = SchemaComponentUpdate::new();
+        schema_data.fields_mut().add::<SchemaInt32>(1, &value);
+        schema_data
+            .events_mut()
+            .add_object(1)
+            .add::<SchemaInt32>(1, &value);
+
+        ComponentUpdate {
+            schema_data,
+            component_id,
+        }
+    }
+
+    #[test]
+    fn coalesces_updates_for_the_same_component() {
+        let mut buffer = UpdateBuffer::new();
+        buffer
+            .add(EntityId::new(1), update(1000, 5), UpdateParameters::new())
+            .unwrap();
+        buffer
+            .add(EntityId::new(2), update(1000, 6), UpdateParameters::new())
+            .unwrap();
+        buffer
+            .add(EntityId::new(1), update(1000, 7), UpdateParameters::new())
+            .unwrap();
+        assert_eq!(2, buffer.len());
+
+        let mut connection = MockConnection::new("test_worker");
+        assert_eq!(2, buffer.flush(&mut connection));
+        assert!(buffer.is_empty());
+
+        let sent = connection.take_sent();
+        match &sent[..] {
+            [SentMessage::ComponentUpdate {
+                entity_id: first_id,
+                update: first,
+                ..
+            }, SentMessage::ComponentUpdate {
+                entity_id: second_id,
+                ..
+            }] => {
+                assert_eq!(EntityId::new(1), *first_id);
+                assert_eq!(EntityId::new(2), *second_id);
+
+                let first = &first.schema_data;
+                assert_eq!(7, first.fields().get::<SchemaInt32>(1).unwrap());
+
+                let events = first.events();
+                assert_eq!(2, events.object_count(1));
+                assert_eq!(5, events.index_object(1, 0).get::<SchemaInt32>(1).unwrap());
+                assert_eq!(7, events.index_object(1, 1).get::<SchemaInt32>(1).unwrap());
+            }
+            _ => panic!("Expected two component updates"),
+        }
+    }
+
+    #[test]
+    fn updates_for_different_components_are_not_merged() {
+        let mut buffer = UpdateBuffer::new();
+        buffer
+            .add(EntityId::new(1), update(1000, 5), UpdateParameters::new())
+            .unwrap();
+        buffer
+            .add(EntityId::new(1), update(1001, 6), UpdateParameters::new())
+            .unwrap();
+
+        let mut connection = MockConnection::new("test_worker");
+        assert_eq!(2, buffer.flush(&mut connection));
+        assert_eq!(2, connection.sent().len());
+    }
+
+    #[test]
+    fn merged_update_allows_loopback_if_any_update_did() {
+        let mut loopback = UpdateParameters::new();
+        loopback.allow_loopback();
+
+        let mut buffer = UpdateBuffer::new();
+        buffer
+            .add(EntityId::new(1), update(1000, 5), loopback)
+            .unwrap();
+        buffer
+            .add(EntityId::new(1), update(1000, 6), UpdateParameters::new())
+            .unwrap();
+
+        let mut connection = MockConnection::new("test_worker");
+        buffer.flush(&mut connection);
+
+        match connection.sent() {
+            [SentMessage::ComponentUpdate { parameters, .. }] => assert!(parameters.loopback),
+            _ => panic!("Expected a single component update"),
+        }
+    }
+}
@@ -5,11 +5,12 @@ use crate::{
     locator::*,
     logging::{LogLevel, ReleaseCallbackHandle},
     metrics::Metrics,
-    op::OpList,
+    op::{AuthorityChangeOp, OpList},
     parameters::ConnectionParameters,
+    schema::SchemaComponentData,
     utils::cstr_to_string,
     worker_future::{WorkerFuture, WorkerSdkFuture},
-    {EntityId, RequestId},
+    {Authority, EntityId, RequestId},
 };
 use spatialos_sdk_sys::worker::*;
 use std::{
@@ -20,6 +21,12 @@ use std::{
     result::Result,
 };
 
+mod handle;
+mod reconnect;
+mod stream;
+
+pub use self::{handle::*, reconnect::*, stream::*};
+
 pub type ConnectionStatus = Result<(), ConnectionStatusError>;
 
 #[derive(Clone, PartialOrd, PartialEq, Eq, Debug)]
@@ -44,6 +51,20 @@ pub enum ConnectionStatusErrorCode {
     Unknown,
 }
 
+impl ConnectionStatusErrorCode {
+    /// Returns `true` if the error is likely to be temporary, such that retrying the
+    /// connection may succeed.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            ConnectionStatusErrorCode::NetworkError
+            | ConnectionStatusErrorCode::Timeout
+            | ConnectionStatusErrorCode::CapacityExceeded
+            | ConnectionStatusErrorCode::RateExceeded => true,
+            _ => false,
+        }
+    }
+}
+
 impl From<i32> for ConnectionStatusErrorCode {
     fn from(code: i32) -> Self {
         match code {
@@ -190,6 +211,40 @@ pub trait Connection {
         parameters: UpdateParameters,
     );
 
+    /// Adds a component to an entity.
+    ///
+    /// The worker must have write access to the entity's `EntityAcl` component for
+    /// the request to succeed.
+    fn send_add_component<C: Component>(
+        &mut self,
+        entity_id: EntityId,
+        component: &C,
+        parameters: UpdateParameters,
+    );
+
+    /// Removes a component from an entity.
+    ///
+    /// The worker must be authoritative over the component for the request to succeed.
+    fn send_remove_component<C: Component>(
+        &mut self,
+        entity_id: EntityId,
+        parameters: UpdateParameters,
+    );
+
+    /// Acknowledges an `AuthorityLossImminent` authority change for a component.
+    ///
+    /// Sending the acknowledgement signals that the worker is ready to lose authority
+    /// over the component, allowing the handoff to complete without waiting for the
+    /// full authority loss timeout.
+    fn send_authority_loss_imminent_acknowledgement(
+        &mut self,
+        entity_id: EntityId,
+        component_id: ComponentId,
+    );
+
+    /// Overrides the worker's interest in components on the given entity.
+    fn send_component_interest(&mut self, entity_id: EntityId, interest: &ComponentInterest);
+
     fn flush(&mut self);
 
     fn enable_logging(&mut self);
@@ -207,6 +262,64 @@ pub trait Connection {
     fn get_worker_attributes(&self) -> &[String];
 }
 
+/// Automatically acknowledges imminent authority loss once final state has been sent.
+///
+/// When the worker receives an `AuthorityLossImminent` authority change, the handoff
+/// callback is invoked so that the worker can send any final component updates for
+/// the component. Once the callback returns, the acknowledgement is sent on the same
+/// connection, ensuring that it is ordered after the final state.
+///
+/// # Examples
+///
+/// ```no_run
+/// use spatialos_sdk::{component::ComponentId, connection::*, op::WorkerOp, EntityId};
+///
+/// fn send_final_state(
+///     connection: &mut WorkerConnection,
+///     entity_id: EntityId,
+///     component_id: ComponentId,
+/// ) {
+///     // Send final updates for `component_id` on `entity_id`.
+/// }
+///
+/// # let mut connection: WorkerConnection = unimplemented!();
+/// let mut handoff = AuthorityHandoff::new(send_final_state);
+///
+/// let ops = connection.get_op_list(0);
+/// for op in &ops {
+///     if let WorkerOp::AuthorityChange(authority_change) = op {
+///         handoff.handle(&mut connection, &authority_change);
+///     }
+/// }
+/// ```
+pub struct AuthorityHandoff<F> {
+    on_handoff: F,
+}
+
+impl<F> AuthorityHandoff<F> {
+    pub fn new(on_handoff: F) -> Self {
+        AuthorityHandoff { on_handoff }
+    }
+
+    /// Handles an authority change, running the handoff callback and sending the
+    /// acknowledgement if the worker is about to lose authority.
+    ///
+    /// Returns `true` if an acknowledgement was sent.
+    pub fn handle<C>(&mut self, connection: &mut C, op: &AuthorityChangeOp) -> bool
+    where
+        C: Connection,
+        F: FnMut(&mut C, EntityId, ComponentId),
+    {
+        if op.authority != Authority::AuthorityLossImminent {
+            return false;
+        }
+
+        (self.on_handoff)(connection, op.entity_id, op.component_id);
+        connection.send_authority_loss_imminent_acknowledgement(op.entity_id, op.component_id);
+        true
+    }
+}
+
 pub struct WorkerConnection {
     // NOTE: The `Worker_Connection` pointer is wrapped in a `MutPtr` to ensure
     // that we only attempt to use the connection pointer in methods that take
@@ -286,6 +399,34 @@ impl WorkerConnection {
     ) -> WorkerFuture<WorkerConnectionFuture> {
         WorkerFuture::new(WorkerConnectionFuture::Locator(locator, params, Vec::new()))
     }
+
+    /// Connects to the named deployment via the locator, queueing if the deployment is
+    /// at capacity.
+    ///
+    /// `on_queue_status` is called with each queue status update, and can return `false`
+    /// to cancel queueing. The deployment name should be obtained from
+    /// `Locator::get_deployment_list`.
+    pub fn connect_locator_and_queue<F>(
+        locator: Locator,
+        deployment_name: &str,
+        params: ConnectionParameters,
+        on_queue_status: F,
+    ) -> WorkerFuture<WorkerConnectionFuture>
+    where
+        F: FnMut(Result<u32, String>) -> bool + Send + 'static,
+    {
+        let deployment_name =
+            CString::new(deployment_name).expect("Received 0 byte in supplied deployment name.");
+        let on_queue_status: QueueStatusCallback = Box::new(on_queue_status);
+
+        WorkerFuture::new(WorkerConnectionFuture::LocatorQueue(
+            locator,
+            deployment_name,
+            params,
+            Box::new(on_queue_status),
+            Vec::new(),
+        ))
+    }
 }
 
 impl Connection for WorkerConnection {
@@ -502,6 +643,72 @@ impl Connection for WorkerConnection {
         }
     }
 
+    fn send_add_component<C: Component>(
+        &mut self,
+        entity_id: EntityId,
+        component: &C,
+        parameters: UpdateParameters,
+    ) {
+        let mut component_data = Worker_ComponentData {
+            reserved: ptr::null_mut(),
+            component_id: C::ID,
+            schema_type: SchemaComponentData::from_component(component).into_raw(),
+            user_handle: ptr::null_mut(),
+        };
+
+        let params = parameters.to_worker_sdk();
+        unsafe {
+            Worker_Connection_SendAddComponent(
+                self.connection_ptr.get(),
+                entity_id.id,
+                &mut component_data,
+                &params,
+            );
+        }
+    }
+
+    fn send_remove_component<C: Component>(
+        &mut self,
+        entity_id: EntityId,
+        parameters: UpdateParameters,
+    ) {
+        let params = parameters.to_worker_sdk();
+        unsafe {
+            Worker_Connection_SendRemoveComponent(
+                self.connection_ptr.get(),
+                entity_id.id,
+                C::ID,
+                &params,
+            );
+        }
+    }
+
+    fn send_authority_loss_imminent_acknowledgement(
+        &mut self,
+        entity_id: EntityId,
+        component_id: ComponentId,
+    ) {
+        unsafe {
+            Worker_Connection_SendAuthorityLossImminentAcknowledgement(
+                self.connection_ptr.get(),
+                entity_id.id,
+                component_id,
+            );
+        }
+    }
+
+    fn send_component_interest(&mut self, entity_id: EntityId, interest: &ComponentInterest) {
+        let overrides = interest.to_worker_sdk();
+        unsafe {
+            Worker_Connection_SendComponentInterest(
+                self.connection_ptr.get(),
+                entity_id.id,
+                overrides.as_ptr(),
+                overrides.len() as u32,
+            );
+        }
+    }
+
     fn flush(&mut self) {
         assert!(!self.connection_ptr.is_null());
         unsafe { Worker_Connection_Alpha_Flush(self.connection_ptr.get()) }
@@ -612,6 +819,13 @@ pub enum WorkerConnectionFuture {
         Vec<ReleaseCallbackHandle>,
     ),
     Locator(Locator, ConnectionParameters, Vec<ReleaseCallbackHandle>),
+    LocatorQueue(
+        Locator,
+        CString,
+        ConnectionParameters,
+        Box<QueueStatusCallback>,
+        Vec<ReleaseCallbackHandle>,
+    ),
 }
 
 unsafe impl Send for WorkerConnectionFuture {}
@@ -644,9 +858,28 @@ impl WorkerSdkFuture for WorkerConnectionFuture {
             WorkerConnectionFuture::Locator(locator, params, release_callback_handles) => {
                 let (params, mut callbacks) = params.flatten();
                 release_callback_handles.append(&mut callbacks);
-                unsafe {
-                    Worker_Locator_ConnectAsync(locator.locator, &params.as_raw(&default_vtable))
-                }
+                locator.with_ptr(|locator| unsafe {
+                    Worker_Locator_ConnectAsync(locator, &params.as_raw(&default_vtable))
+                })
+            }
+            WorkerConnectionFuture::LocatorQueue(
+                locator,
+                deployment_name,
+                params,
+                on_queue_status,
+                release_callback_handles,
+            ) => {
+                let (params, mut callbacks) = params.flatten();
+                release_callback_handles.append(&mut callbacks);
+                locator.with_ptr(|locator| unsafe {
+                    Worker_Locator_ConnectAndQueueAsync(
+                        locator,
+                        deployment_name.as_ptr(),
+                        &params.as_raw(&default_vtable),
+                        &mut **on_queue_status as *mut QueueStatusCallback as *mut _,
+                        Some(queue_status_handler),
+                    )
+                })
             }
         }
     }
@@ -661,6 +894,7 @@ impl WorkerSdkFuture for WorkerConnectionFuture {
         let callbacks = match self {
             WorkerConnectionFuture::Receptionist(_, _, _, _, callbacks) => callbacks,
             WorkerConnectionFuture::Locator(_, _, callbacks) => callbacks,
+            WorkerConnectionFuture::LocatorQueue(_, _, _, _, callbacks) => callbacks,
         };
 
         let mut connection = WorkerConnection::new(connection_ptr, callbacks.drain(..).collect());
@@ -674,6 +908,7 @@ impl WorkerSdkFuture for WorkerConnectionFuture {
         let callbacks = match self {
             WorkerConnectionFuture::Receptionist(_, _, _, _, callbacks) => callbacks,
             WorkerConnectionFuture::Locator(_, _, callbacks) => callbacks,
+            WorkerConnectionFuture::LocatorQueue(_, _, _, _, callbacks) => callbacks,
         };
 
         for cb in callbacks.drain(..) {
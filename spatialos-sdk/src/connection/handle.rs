@@ -0,0 +1,427 @@
+use crate::{
+    commands::CommandResponse,
+    component::{Component, ComponentUpdate, UpdateParameters},
+    connection::{Connection, OpStream},
+    logging::LogLevel,
+    op::OwnedWorkerOp,
+    {EntityId, RequestId},
+};
+use futures::{
+    channel::{
+        mpsc::{self, UnboundedReceiver, UnboundedSender},
+        oneshot,
+    },
+    FutureExt, StreamExt,
+};
+use std::{thread, time::Duration};
+
+/// The default interval at which the network thread polls for new ops when idle.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The maximum number of ops that a [`ConnectionHandle`] buffers for its first
+/// subscriber.
+///
+/// [`ConnectionHandle`]: struct.ConnectionHandle.html
+pub const MAX_BUFFERED_OPS: usize = 10_000;
+
+type Call<C> = Box<dyn FnOnce(&mut C) + Send>;
+
+enum Message<C> {
+    Call(Call<C>),
+    Subscribe(UnboundedSender<OwnedWorkerOp>),
+}
+
+/// A thread-safe handle to a connection that is owned by a background thread.
+///
+/// The background thread (the "network thread") is the only thread that accesses the
+/// connection. Messages sent through a handle are pushed onto a lock-free queue, which
+/// the network thread drains before polling the connection for new ops. Ops are then
+/// fanned out to every subscriber created with [`subscribe`]. Ops received before the
+/// first subscription are buffered and delivered to the first subscriber, so that the
+/// initial ops sent by SpatialOS when a worker connects aren't lost. At most
+/// [`MAX_BUFFERED_OPS`] ops are buffered: if more arrive before anyone subscribes, the
+/// buffer is discarded and the first subscriber only receives ops from the point at
+/// which it subscribed, like every other subscriber.
+///
+/// Handles can be cloned freely and used from any number of threads. Messages sent
+/// through a single handle are sent on the connection in the order they were queued.
+/// The network thread shuts down, and the connection is dropped, once every handle
+/// has been dropped.
+///
+/// # Examples
+///
+/// ```no_run
+/// use futures::executor::block_on_stream;
+/// use spatialos_sdk::{
+///     component::UpdateParameters,
+///     connection::{ConnectionHandle, WorkerConnection},
+///     op::OwnedWorkerOp,
+/// };
+/// use std::thread;
+///
+/// # let connection: WorkerConnection = unimplemented!();
+/// let handle = ConnectionHandle::new(connection);
+///
+/// let ops = handle.subscribe();
+/// let worker = handle.clone();
+/// thread::spawn(move || {
+///     for op in block_on_stream(ops) {
+///         if let OwnedWorkerOp::AddEntity(op) = op {
+///             println!("{} was added", op.entity_id);
+///             worker.flush();
+///         }
+///     }
+/// });
+/// ```
+///
+/// [`subscribe`]: #method.subscribe
+/// [`MAX_BUFFERED_OPS`]: constant.MAX_BUFFERED_OPS.html
+pub struct ConnectionHandle<C> {
+    sender: UnboundedSender<Message<C>>,
+    worker_id: String,
+    worker_attributes: Vec<String>,
+}
+
+impl<C> ConnectionHandle<C>
+where
+    C: Connection + Send + 'static,
+{
+    /// Moves `connection` onto a new network thread, which polls for new ops every
+    /// [`DEFAULT_POLL_INTERVAL`] when idle.
+    ///
+    /// [`DEFAULT_POLL_INTERVAL`]: constant.DEFAULT_POLL_INTERVAL.html
+    pub fn new(connection: C) -> Self {
+        Self::with_poll_interval(connection, DEFAULT_POLL_INTERVAL)
+    }
+
+    /// Moves `connection` onto a new network thread, which polls for new ops every
+    /// `poll_interval` when idle.
+    pub fn with_poll_interval(connection: C, poll_interval: Duration) -> Self {
+        let (sender, receiver) = mpsc::unbounded();
+        let handle = ConnectionHandle {
+            sender,
+            worker_id: connection.get_worker_id().to_owned(),
+            worker_attributes: connection.get_worker_attributes().to_vec(),
+        };
+
+        thread::spawn(move || run_network_thread(connection, receiver, poll_interval));
+
+        handle
+    }
+}
+
+impl<C> ConnectionHandle<C> {
+    pub fn get_worker_id(&self) -> &str {
+        &self.worker_id
+    }
+
+    pub fn get_worker_attributes(&self) -> &[String] {
+        &self.worker_attributes
+    }
+
+    /// Returns `true` if the network thread is still running.
+    pub fn is_running(&self) -> bool {
+        !self.sender.is_closed()
+    }
+
+    /// Subscribes to the ops received by the connection.
+    ///
+    /// The returned stream yields every op received after the subscription was
+    /// processed by the network thread. Since subscriptions are queued in the same way
+    /// as messages, this includes the responses to any requests sent through this
+    /// handle after calling `subscribe`.
+    ///
+    /// The first subscriber also receives every op that arrived before it subscribed,
+    /// unless more than [`MAX_BUFFERED_OPS`] ops arrived.
+    ///
+    /// [`MAX_BUFFERED_OPS`]: constant.MAX_BUFFERED_OPS.html
+    pub fn subscribe(&self) -> OpStream {
+        let (sender, receiver) = mpsc::unbounded();
+        self.queue(Message::Subscribe(sender));
+        OpStream::from_receiver(receiver)
+    }
+
+    /// Queues `f` to be run on the network thread with exclusive access to the
+    /// connection.
+    pub fn send<F>(&self, f: F)
+    where
+        F: FnOnce(&mut C) + Send + 'static,
+    {
+        self.queue(Message::Call(Box::new(f)));
+    }
+
+    /// Queues `f` to be run on the network thread, returning a future that resolves to
+    /// its result.
+    ///
+    /// This is used for operations that return a value, such as sending requests. The
+    /// future resolves to `Err(Canceled)` if the network thread has shut down.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use futures::executor::block_on;
+    /// use spatialos_sdk::{
+    ///     commands::ReserveEntityIdsRequest,
+    ///     connection::{Connection, ConnectionHandle, WorkerConnection},
+    /// };
+    ///
+    /// # let handle: ConnectionHandle<WorkerConnection> = unimplemented!();
+    /// let request_id = block_on(handle.call(|connection| {
+    ///     connection.send_reserve_entity_ids_request(ReserveEntityIdsRequest(1), None)
+    /// }));
+    /// ```
+    pub fn call<F, T>(&self, f: F) -> oneshot::Receiver<T>
+    where
+        F: FnOnce(&mut C) -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (sender, receiver) = oneshot::channel();
+        self.send(move |connection| {
+            let _ = sender.send(f(connection));
+        });
+        receiver
+    }
+
+    fn queue(&self, message: Message<C>) {
+        // Sending only fails if the network thread has shut down, in which case the
+        // message is dropped, the same as if it had been sent on a closed connection.
+        let _ = self.sender.unbounded_send(message);
+    }
+}
+
+impl<C> ConnectionHandle<C>
+where
+    C: Connection,
+{
+    pub fn send_log_message(
+        &self,
+        level: LogLevel,
+        logger_name: &str,
+        message: &str,
+        entity_id: Option<EntityId>,
+    ) {
+        let logger_name = logger_name.to_owned();
+        let message = message.to_owned();
+        self.send(move |connection| {
+            connection.send_log_message(level, &logger_name, &message, entity_id)
+        });
+    }
+
+    pub fn send_component_update<T: Into<ComponentUpdate>>(
+        &self,
+        entity_id: EntityId,
+        update: T,
+        parameters: UpdateParameters,
+    ) {
+        let update = update.into();
+        self.send(move |connection| {
+            connection.send_component_update(entity_id, update, parameters)
+        });
+    }
+
+    pub fn send_add_component<T>(
+        &self,
+        entity_id: EntityId,
+        component: T,
+        parameters: UpdateParameters,
+    ) where
+        T: Component + Send + 'static,
+    {
+        self.send(move |connection| {
+            connection.send_add_component(entity_id, &component, parameters)
+        });
+    }
+
+    pub fn send_remove_component<T: Component>(
+        &self,
+        entity_id: EntityId,
+        parameters: UpdateParameters,
+    ) {
+        self.send(move |connection| connection.send_remove_component::<T>(entity_id, parameters));
+    }
+
+    pub fn send_command_response<T: Into<CommandResponse>>(
+        &self,
+        request_id: RequestId,
+        response: T,
+    ) {
+        let response = response.into();
+        self.send(move |connection| connection.send_command_response(request_id, response));
+    }
+
+    pub fn flush(&self) {
+        self.send(|connection| connection.flush());
+    }
+}
+
+impl<C> Clone for ConnectionHandle<C> {
+    fn clone(&self) -> Self {
+        ConnectionHandle {
+            sender: self.sender.clone(),
+            worker_id: self.worker_id.clone(),
+            worker_attributes: self.worker_attributes.clone(),
+        }
+    }
+}
+
+fn run_network_thread<C: Connection>(
+    mut connection: C,
+    mut receiver: UnboundedReceiver<Message<C>>,
+    poll_interval: Duration,
+) {
+    let mut subscribers = Vec::<UnboundedSender<OwnedWorkerOp>>::new();
+
+    // Ops received before anyone has subscribed, which are replayed to the first
+    // subscriber. This is `None` once the first subscription has been processed, or
+    // once more than `MAX_BUFFERED_OPS` ops have been buffered.
+    let mut backlog = Some(Vec::new());
+
+    loop {
+        let mut is_idle = true;
+
+        // Drain the queue before polling for ops, so that subscriptions take effect
+        // before the responses to any requests queued after them can be received.
+        loop {
+            match receiver.next().now_or_never() {
+                Some(Some(Message::Call(call))) => call(&mut connection),
+                Some(Some(Message::Subscribe(subscriber))) => {
+                    for op in backlog.take().into_iter().flatten() {
+                        let _ = subscriber.unbounded_send(op);
+                    }
+                    subscribers.push(subscriber);
+                }
+
+                // Every handle has been dropped, so shut down.
+                Some(None) => return,
+
+                // The queue is empty.
+                None => break,
+            }
+
+            is_idle = false;
+        }
+
+        let mut ops = connection.get_op_list(0).to_owned_ops();
+        if let Some(buffered) = &mut backlog {
+            buffered.append(&mut ops);
+            if buffered.len() > MAX_BUFFERED_OPS {
+                backlog = None;
+            }
+        }
+
+        if !ops.is_empty() {
+            is_idle = false;
+            subscribers.retain(|subscriber| !subscriber.is_closed());
+
+            for op in ops {
+                for subscriber in &subscribers {
+                    let _ = subscriber.unbounded_send(op.clone());
+                }
+            }
+        }
+
+        if is_idle {
+            thread::sleep(poll_interval);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod test {
+    use super::*;
+    use crate::{
+        testing::{MockConnection, SentMessage},
+        Authority,
+    };
+    use futures::executor::block_on;
+    use std::sync::{Arc, Barrier};
+
+    #[test]
+    fn ops_are_fanned_out_to_every_subscriber() {
+        let handle = ConnectionHandle::new(MockConnection::new("test_worker"));
+        let first = handle.subscribe();
+        let second = handle.subscribe();
+
+        handle.send(|connection| connection.push_add_entity(EntityId::new(1)));
+
+        for ops in &mut [first, second] {
+            match block_on(ops.next()) {
+                Some(OwnedWorkerOp::AddEntity(op)) => assert_eq!(EntityId::new(1), op.entity_id),
+                _ => panic!("Expected an add entity op"),
+            }
+        }
+    }
+
+    #[test]
+    fn ops_received_before_subscribing_are_replayed_to_first_subscriber() {
+        let mut connection = MockConnection::new("test_worker");
+        connection.push_add_entity(EntityId::new(1));
+        connection.push_authority_change(EntityId::new(1), 54, Authority::Authoritative);
+        let handle = ConnectionHandle::new(connection);
+
+        // Wait until the network thread has taken the queued ops from the connection.
+        while block_on(handle.call(|connection| connection.pending_op_count())).unwrap() > 0 {
+            thread::yield_now();
+        }
+
+        let mut ops = handle.subscribe();
+        match block_on(ops.next()) {
+            Some(OwnedWorkerOp::AddEntity(op)) => assert_eq!(EntityId::new(1), op.entity_id),
+            _ => panic!("Expected an add entity op"),
+        }
+        match block_on(ops.next()) {
+            Some(OwnedWorkerOp::AuthorityChange(op)) => assert_eq!(54, op.component_id),
+            _ => panic!("Expected an authority change op"),
+        }
+    }
+
+    #[test]
+    fn backlog_is_discarded_once_it_exceeds_the_limit() {
+        let mut connection = MockConnection::new("test_worker");
+        for index in 0..=MAX_BUFFERED_OPS {
+            connection.push_add_entity(EntityId::new(index as i64 + 1));
+        }
+        let handle = ConnectionHandle::new(connection);
+
+        while block_on(handle.call(|connection| connection.pending_op_count())).unwrap() > 0 {
+            thread::yield_now();
+        }
+
+        let mut ops = handle.subscribe();
+        handle.send(|connection| connection.push_remove_entity(EntityId::new(1)));
+        match block_on(ops.next()) {
+            Some(OwnedWorkerOp::RemoveEntity(op)) => assert_eq!(EntityId::new(1), op.entity_id),
+            _ => panic!("Expected only ops received after subscribing"),
+        }
+    }
+
+    #[test]
+    fn messages_can_be_sent_from_multiple_threads() {
+        let handle = ConnectionHandle::new(MockConnection::new("test_worker"));
+        let barrier = Arc::new(Barrier::new(4));
+
+        let threads = (0..4)
+            .map(|index| {
+                let handle = handle.clone();
+                let barrier = barrier.clone();
+                thread::spawn(move || {
+                    barrier.wait();
+                    handle.send_log_message(LogLevel::Info, "test", &index.to_string(), None);
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let sent = block_on(handle.call(|connection| connection.take_sent())).unwrap();
+        assert_eq!(4, sent.len());
+        for message in sent {
+            if let SentMessage::LogMessage { .. } = message {
+                continue;
+            }
+            panic!("Expected only log messages to be sent");
+        }
+    }
+}
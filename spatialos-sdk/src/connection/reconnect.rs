@@ -0,0 +1,964 @@
+use crate::{
+    commands::*,
+    component::*,
+    connection::{Connection, ConnectionStatus, ConnectionStatusError, ConnectionStatusErrorCode},
+    logging::LogLevel,
+    metrics::Metrics,
+    op::{
+        CommandResponseError, CommandStatusCode, OpList, OpListBuilder, OwnedCommandResponseOp,
+        OwnedWorkerOp, WorkerOp,
+    },
+    {EntityId, RequestId},
+};
+use futures::{executor::block_on, future, task::noop_waker};
+use std::{
+    collections::{HashSet, VecDeque},
+    ffi::NulError,
+    future::Future,
+    mem,
+    pin::Pin,
+    task::{Context, Poll},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Configures how a [`ReconnectingConnection`] retries failed connection attempts.
+///
+/// The delay before each retry grows exponentially, starting at the initial delay and
+/// multiplying by the multiplier after every failed attempt, up to the maximum delay.
+/// Only transient failures are retried (see [`ConnectionStatusErrorCode::is_transient`]).
+///
+/// # Examples
+///
+/// ```
+/// use spatialos_sdk::connection::ReconnectPolicy;
+/// use std::time::Duration;
+///
+/// let policy = ReconnectPolicy::new()
+///     .with_initial_delay(Duration::from_millis(100))
+///     .with_max_delay(Duration::from_secs(5))
+///     .with_max_attempts(10);
+///
+/// assert_eq!(Duration::from_millis(100), policy.delay(1));
+/// assert_eq!(Duration::from_millis(400), policy.delay(3));
+/// assert_eq!(Duration::from_secs(5), policy.delay(10));
+/// ```
+///
+/// [`ReconnectingConnection`]: struct.ReconnectingConnection.html
+/// [`ConnectionStatusErrorCode::is_transient`]: enum.ConnectionStatusErrorCode.html#method.is_transient
+#[derive(Debug, Clone)]
+pub struct ReconnectPolicy {
+    initial_delay: Duration,
+    max_delay: Duration,
+    multiplier: f64,
+    max_attempts: Option<u32>,
+}
+
+impl ReconnectPolicy {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn with_initial_delay(mut self, delay: Duration) -> Self {
+        self.initial_delay = delay;
+        self
+    }
+
+    pub fn with_max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Sets the factor by which the delay grows after each failed attempt.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if `multiplier` is less than `1.0` or isn't finite, since the
+    /// delay must never shrink or become negative.
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        assert!(
+            multiplier.is_finite() && multiplier >= 1.0,
+            "Reconnect delay multiplier must be finite and at least 1.0, got {}",
+            multiplier
+        );
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Limits the number of consecutive connection attempts. By default, transient
+    /// failures are retried indefinitely.
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+
+    /// Returns the delay before the given retry, where the first retry is `1`.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let exponent = retry.saturating_sub(1) as i32;
+        let delay = self.initial_delay.as_secs_f64() * self.multiplier.powi(exponent);
+        if delay >= self.max_delay.as_secs_f64() {
+            self.max_delay
+        } else {
+            Duration::from_secs_f64(delay)
+        }
+    }
+
+    fn should_retry(&self, attempt: u32, error: &ConnectionStatusError) -> bool {
+        error.code.is_transient() && self.max_attempts.map_or(true, |max| attempt < max)
+    }
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        ReconnectPolicy {
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            multiplier: 2.0,
+            max_attempts: None,
+        }
+    }
+}
+
+/// A change in the state of a [`ReconnectingConnection`].
+///
+/// [`ReconnectingConnection`]: struct.ReconnectingConnection.html
+#[derive(Debug, Clone)]
+pub enum ConnectionEvent {
+    /// A connection was established after the given number of attempts.
+    Connected { attempts: u32 },
+
+    /// The connection was lost.
+    ///
+    /// All state received over the lost connection should be discarded, since the
+    /// worker will receive it again once it has reconnected.
+    Disconnected {
+        error: ConnectionStatusError,
+        invalidated: InvalidatedState,
+    },
+
+    /// A connection attempt failed, and will be retried after `delay`.
+    Reconnecting {
+        attempt: u32,
+        delay: Duration,
+        error: ConnectionStatusError,
+    },
+
+    /// A connection attempt failed, and will not be retried.
+    Failed { error: ConnectionStatusError },
+}
+
+/// The state that was invalidated when a connection was lost.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InvalidatedState {
+    /// The entities that were checked out, along with all of their component data and
+    /// authority. These will be added again after reconnecting if they are still in
+    /// the worker's view.
+    pub entities: Vec<EntityId>,
+
+    /// The requests that were awaiting a response, which will never be received.
+    pub requests: Vec<RequestId>,
+}
+
+/// A connection that automatically reconnects when it is lost.
+///
+/// A `ReconnectingConnection` wraps the connections created by a `connect` function,
+/// such as one calling [`WorkerConnection::connect_receptionist`]. The connection is
+/// considered lost when a `Disconnect` op is received, or when
+/// [`get_connection_status`] returns an error. The `Disconnect` op (if any) is still
+/// returned to the caller. If the [`ReconnectPolicy`] allows the error to be retried,
+/// later calls to [`get_op_list`] reconnect without blocking: each call starts a new
+/// connection attempt once the backoff delay has elapsed, or checks on the attempt
+/// that is already in progress, and returns an empty op list until a connection has
+/// been established.
+///
+/// Lifecycle events, including the state invalidated by each disconnect, are queued
+/// and can be retrieved with [`take_events`]. If the worker maintains a [`View`], it
+/// should be cleared when a [`ConnectionEvent::Disconnected`] event is received.
+///
+/// While there is no connection, messages are dropped. Requests are not sent, but
+/// still return a (negative) request ID, and a response op for that ID with a
+/// `Timeout` status is returned by the next call to [`get_op_list`]. This means that
+/// every request receives exactly one response, whether or not it could be sent.
+///
+/// # Examples
+///
+/// ```no_run
+/// use spatialos_sdk::{
+///     connection::*,
+///     parameters::ConnectionParameters,
+/// };
+///
+/// let mut connection = ReconnectingConnection::connect(
+///     || {
+///         WorkerConnection::connect_receptionist(
+///             "my_worker",
+///             "127.0.0.1",
+///             7777,
+///             ConnectionParameters::default(),
+///         )
+///     },
+///     ReconnectPolicy::new(),
+/// )
+/// .expect("Failed to connect");
+///
+/// while connection.is_connected() {
+///     let ops = connection.get_op_list(0);
+///     for event in connection.take_events() {
+///         println!("Connection event: {:?}", event);
+///     }
+/// }
+/// ```
+///
+/// [`WorkerConnection::connect_receptionist`]: struct.WorkerConnection.html#method.connect_receptionist
+/// [`get_connection_status`]: trait.Connection.html#tymethod.get_connection_status
+/// [`get_op_list`]: trait.Connection.html#tymethod.get_op_list
+/// [`ReconnectPolicy`]: struct.ReconnectPolicy.html
+/// [`take_events`]: #method.take_events
+/// [`View`]: ../view/struct.View.html
+/// [`ConnectionEvent::Disconnected`]: enum.ConnectionEvent.html#variant.Disconnected
+pub struct ReconnectingConnection<C, F, Fut> {
+    connect: F,
+    policy: ReconnectPolicy,
+    state: State<C, Fut>,
+    events: VecDeque<ConnectionEvent>,
+
+    // Cached from the most recent connection, so that they remain available while
+    // disconnected.
+    worker_id: String,
+    worker_attributes: Vec<String>,
+
+    // State received over the current connection, reported as invalidated if the
+    // connection is lost.
+    entities: HashSet<EntityId>,
+    requests: HashSet<RequestId>,
+
+    // Responses to requests that were made while disconnected, and the ID to give the
+    // next such request.
+    failed_requests: OpListBuilder,
+    next_local_request_id: i64,
+}
+
+enum State<C, Fut> {
+    Connected(C),
+    Reconnecting(Reconnect<Fut>),
+    Failed(ConnectionStatusError),
+}
+
+struct Reconnect<Fut> {
+    // The number of the next connection attempt, starting at 1.
+    attempt: u32,
+    retry_at: Instant,
+    error: ConnectionStatusError,
+    connecting: Option<Pin<Box<Fut>>>,
+}
+
+impl<Fut> Reconnect<Fut> {
+    fn new(attempt: u32, delay: Duration, error: ConnectionStatusError) -> Self {
+        Reconnect {
+            attempt,
+            retry_at: Instant::now() + delay,
+            error,
+            connecting: None,
+        }
+    }
+}
+
+impl<C, F, Fut> ReconnectingConnection<C, F, Fut>
+where
+    C: Connection,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<C, ConnectionStatusError>>,
+{
+    /// Connects using `connect`, retrying transient failures according to `policy`.
+    ///
+    /// Unlike reconnecting, this blocks until a connection is established, or returns
+    /// the last error if the policy gives up.
+    pub fn connect(connect: F, policy: ReconnectPolicy) -> Result<Self, ConnectionStatusError> {
+        let mut connection = ReconnectingConnection {
+            connect,
+            policy,
+            state: State::Reconnecting(Reconnect::new(
+                1,
+                Duration::from_secs(0),
+                ConnectionStatusError {
+                    code: ConnectionStatusErrorCode::Cancelled,
+                    detail: "Not yet connected".to_owned(),
+                },
+            )),
+            events: VecDeque::new(),
+            worker_id: String::new(),
+            worker_attributes: Vec::new(),
+            entities: HashSet::new(),
+            requests: HashSet::new(),
+            failed_requests: OpListBuilder::new(),
+            next_local_request_id: -1,
+        };
+
+        loop {
+            let retry_at = match &connection.state {
+                State::Connected(_) => return Ok(connection),
+                State::Failed(error) => return Err(error.clone()),
+                State::Reconnecting(reconnect) => reconnect.retry_at,
+            };
+
+            thread::sleep(retry_at.saturating_duration_since(Instant::now()));
+            block_on(future::poll_fn(|cx| connection.poll_reconnect(cx)));
+        }
+    }
+
+    /// Makes progress on reconnecting, starting a connection attempt if the backoff
+    /// delay has elapsed.
+    ///
+    /// Returns `Ready` once the current attempt has finished (or if there is nothing
+    /// to do), and `Pending` while waiting for either the delay or the attempt.
+    fn poll_reconnect(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        let reconnect = match &mut self.state {
+            State::Reconnecting(reconnect) => reconnect,
+            _ => return Poll::Ready(()),
+        };
+
+        if reconnect.connecting.is_none() {
+            if Instant::now() < reconnect.retry_at {
+                return Poll::Pending;
+            }
+            reconnect.connecting = Some(Box::pin((self.connect)()));
+        }
+
+        let result = match reconnect.connecting.as_mut().unwrap().as_mut().poll(cx) {
+            Poll::Ready(result) => result,
+            Poll::Pending => return Poll::Pending,
+        };
+
+        let attempt = reconnect.attempt;
+        match result {
+            Ok(connection) => {
+                self.worker_id = connection.get_worker_id().to_owned();
+                self.worker_attributes = connection.get_worker_attributes().to_vec();
+                self.state = State::Connected(connection);
+                self.events
+                    .push_back(ConnectionEvent::Connected { attempts: attempt });
+            }
+
+            Err(error) => {
+                if self.policy.should_retry(attempt, &error) {
+                    let delay = self.policy.delay(attempt);
+                    self.events.push_back(ConnectionEvent::Reconnecting {
+                        attempt,
+                        delay,
+                        error: error.clone(),
+                    });
+                    self.state = State::Reconnecting(Reconnect::new(attempt + 1, delay, error));
+                } else {
+                    self.fail(error);
+                }
+            }
+        }
+
+        Poll::Ready(())
+    }
+}
+
+impl<C, F, Fut> ReconnectingConnection<C, F, Fut> {
+    /// Returns `true` if there is a live connection, or the connection is being
+    /// re-established.
+    pub fn is_connected(&self) -> bool {
+        match self.state {
+            State::Failed(_) => false,
+            _ => true,
+        }
+    }
+
+    /// Returns the current connection, if connected.
+    pub fn connection(&mut self) -> Option<&mut C> {
+        match &mut self.state {
+            State::Connected(connection) => Some(connection),
+            _ => None,
+        }
+    }
+
+    /// Removes and returns all queued lifecycle events.
+    pub fn take_events(&mut self) -> Vec<ConnectionEvent> {
+        self.events.drain(..).collect()
+    }
+
+    fn track_request(&mut self, request_id: RequestId) -> RequestId {
+        self.requests.insert(request_id);
+        request_id
+    }
+
+    fn next_local_request_id(&mut self) -> RequestId {
+        let request_id = RequestId::new(self.next_local_request_id);
+        self.next_local_request_id -= 1;
+        request_id
+    }
+
+    fn track_op(&mut self, op: &WorkerOp<'_>) {
+        match op {
+            WorkerOp::AddEntity(op) => {
+                self.entities.insert(op.entity_id);
+            }
+            WorkerOp::RemoveEntity(op) => {
+                self.entities.remove(&op.entity_id);
+            }
+            WorkerOp::CommandResponse(op) => {
+                self.requests.remove(&op.request_id);
+            }
+            WorkerOp::ReserveEntityIdsResponse(op) => {
+                self.requests.remove(&op.request_id);
+            }
+            WorkerOp::CreateEntityResponse(op) => {
+                self.requests.remove(&op.request_id);
+            }
+            WorkerOp::DeleteEntityResponse(op) => {
+                self.requests.remove(&op.request_id);
+            }
+            WorkerOp::EntityQueryResponse(op) => {
+                self.requests.remove(&op.request_id);
+            }
+            _ => {}
+        }
+    }
+
+    fn disconnect(&mut self, error: ConnectionStatusError) {
+        let mut entities = mem::replace(&mut self.entities, HashSet::new())
+            .into_iter()
+            .collect::<Vec<_>>();
+        let mut requests = mem::replace(&mut self.requests, HashSet::new())
+            .into_iter()
+            .collect::<Vec<_>>();
+        entities.sort();
+        requests.sort();
+
+        self.events.push_back(ConnectionEvent::Disconnected {
+            error: error.clone(),
+            invalidated: InvalidatedState { entities, requests },
+        });
+
+        // Errors that won't go away by retrying, such as the worker being kicked by
+        // the runtime, aren't worth reconnecting for.
+        if self.policy.should_retry(0, &error) {
+            self.state = State::Reconnecting(Reconnect::new(1, Duration::from_secs(0), error));
+        } else {
+            self.fail(error);
+        }
+    }
+
+    fn fail(&mut self, error: ConnectionStatusError) {
+        self.events.push_back(ConnectionEvent::Failed {
+            error: error.clone(),
+        });
+        self.state = State::Failed(error);
+    }
+}
+
+fn not_connected() -> CommandResponseError {
+    CommandResponseError {
+        code: CommandStatusCode::Timeout,
+        detail: "The request was not sent because the worker is not connected".to_owned(),
+    }
+}
+
+// Forwards a message to the current connection, dropping it if disconnected.
+macro_rules! forward {
+    ($self:ident, |$connection:ident| $body:expr) => {
+        if let State::Connected($connection) = &mut $self.state {
+            $body;
+        }
+    };
+}
+
+// Forwards a request to the current connection and tracks its request ID. If
+// disconnected, allocates a local request ID and queues a failed response for it.
+macro_rules! forward_request {
+    ($self:ident, |$connection:ident| $body:expr, |$ops:ident, $request_id:ident| $failure:expr) => {{
+        if let State::Connected($connection) = &mut $self.state {
+            let request_id = $body;
+            return $self.track_request(request_id);
+        }
+
+        let $request_id = $self.next_local_request_id();
+        let $ops = &mut $self.failed_requests;
+        $failure;
+        $request_id
+    }};
+}
+
+impl<C, F, Fut> Connection for ReconnectingConnection<C, F, Fut>
+where
+    C: Connection,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<C, ConnectionStatusError>>,
+{
+    fn send_log_message(
+        &mut self,
+        level: LogLevel,
+        logger_name: &str,
+        message: &str,
+        entity_id: Option<EntityId>,
+    ) {
+        forward!(self, |c| c.send_log_message(
+            level,
+            logger_name,
+            message,
+            entity_id
+        ));
+    }
+
+    fn send_metrics(&mut self, metrics: &Metrics) {
+        forward!(self, |c| c.send_metrics(metrics));
+    }
+
+    fn send_reserve_entity_ids_request(
+        &mut self,
+        payload: ReserveEntityIdsRequest,
+        timeout_millis: Option<u32>,
+    ) -> RequestId {
+        forward_request!(
+            self,
+            |c| c.send_reserve_entity_ids_request(payload, timeout_millis),
+            |ops, request_id| ops.reserve_entity_ids_response(request_id, Err(not_connected()))
+        )
+    }
+
+    fn send_create_entity_request(
+        &mut self,
+        payload: CreateEntityRequest,
+        timeout_millis: Option<u32>,
+    ) -> RequestId {
+        forward_request!(
+            self,
+            |c| c.send_create_entity_request(payload, timeout_millis),
+            |ops, request_id| ops.create_entity_response(request_id, Err(not_connected()))
+        )
+    }
+
+    fn send_delete_entity_request(
+        &mut self,
+        payload: DeleteEntityRequest,
+        timeout_millis: Option<u32>,
+    ) -> RequestId {
+        let entity_id = payload.0;
+        forward_request!(
+            self,
+            |c| c.send_delete_entity_request(payload, timeout_millis),
+            |ops, request_id| ops.delete_entity_response(
+                request_id,
+                entity_id,
+                Err(not_connected())
+            )
+        )
+    }
+
+    fn send_entity_query_request(
+        &mut self,
+        payload: EntityQueryRequest,
+        timeout_millis: Option<u32>,
+    ) -> RequestId {
+        forward_request!(
+            self,
+            |c| c.send_entity_query_request(payload, timeout_millis),
+            |ops, request_id| ops.entity_query_response(request_id, Err(not_connected()))
+        )
+    }
+
+    fn send_command_request<T: Into<CommandRequest>>(
+        &mut self,
+        entity_id: EntityId,
+        request: T,
+        timeout_millis: Option<u32>,
+        params: CommandParameters,
+    ) -> RequestId {
+        let request = request.into();
+        let (component_id, command_index) = (request.component_id, request.command_index);
+        forward_request!(
+            self,
+            |c| c.send_command_request(entity_id, request, timeout_millis, params),
+            |ops, request_id| ops.op(OwnedWorkerOp::CommandResponse(OwnedCommandResponseOp {
+                request_id,
+                entity_id,
+                component_id,
+                command_index,
+                response: Err(not_connected()),
+            }))
+        )
+    }
+
+    fn send_command_response<T: Into<CommandResponse>>(
+        &mut self,
+        request_id: RequestId,
+        response: T,
+    ) {
+        forward!(self, |c| c.send_command_response(request_id, response));
+    }
+
+    fn send_command_failure(
+        &mut self,
+        request_id: RequestId,
+        message: &str,
+    ) -> Result<(), NulError> {
+        match &mut self.state {
+            State::Connected(connection) => connection.send_command_failure(request_id, message),
+            _ => Ok(()),
+        }
+    }
+
+    fn send_component_update<T: Into<ComponentUpdate>>(
+        &mut self,
+        entity_id: EntityId,
+        update: T,
+        parameters: UpdateParameters,
+    ) {
+        forward!(self, |c| c
+            .send_component_update(entity_id, update, parameters));
+    }
+
+    fn send_add_component<T: Component>(
+        &mut self,
+        entity_id: EntityId,
+        component: &T,
+        parameters: UpdateParameters,
+    ) {
+        forward!(self, |c| c
+            .send_add_component(entity_id, component, parameters));
+    }
+
+    fn send_remove_component<T: Component>(
+        &mut self,
+        entity_id: EntityId,
+        parameters: UpdateParameters,
+    ) {
+        forward!(self, |c| c
+            .send_remove_component::<T>(entity_id, parameters));
+    }
+
+    fn send_authority_loss_imminent_acknowledgement(
+        &mut self,
+        entity_id: EntityId,
+        component_id: ComponentId,
+    ) {
+        forward!(self, |c| c.send_authority_loss_imminent_acknowledgement(
+            entity_id,
+            component_id
+        ));
+    }
+
+    fn send_component_interest(&mut self, entity_id: EntityId, interest: &ComponentInterest) {
+        forward!(self, |c| c.send_component_interest(entity_id, interest));
+    }
+
+    fn flush(&mut self) {
+        forward!(self, |c| c.flush());
+    }
+
+    fn enable_logging(&mut self) {
+        forward!(self, |c| c.enable_logging());
+    }
+
+    fn disable_logging(&mut self) {
+        forward!(self, |c| c.disable_logging());
+    }
+
+    fn get_connection_status(&mut self) -> ConnectionStatus {
+        match &mut self.state {
+            State::Connected(connection) => connection.get_connection_status(),
+            State::Reconnecting(reconnect) => Err(reconnect.error.clone()),
+            State::Failed(error) => Err(error.clone()),
+        }
+    }
+
+    fn get_worker_flag(&mut self, name: &str) -> Option<String> {
+        match &mut self.state {
+            State::Connected(connection) => connection.get_worker_flag(name),
+            _ => None,
+        }
+    }
+
+    fn get_op_list(&mut self, timeout_millis: u32) -> OpList {
+        // Deliver the responses to requests made while disconnected before any new ops,
+        // since they were sent first.
+        if !self.failed_requests.is_empty() {
+            return self.failed_requests.build();
+        }
+
+        if let State::Reconnecting(_) = self.state {
+            let waker = noop_waker();
+            let _ = self.poll_reconnect(&mut Context::from_waker(&waker));
+        }
+
+        let connection = match &mut self.state {
+            State::Connected(connection) => connection,
+            _ => return OpList::from(Vec::new()),
+        };
+
+        let ops = connection.get_op_list(timeout_millis);
+        let status = connection.get_connection_status();
+
+        let mut disconnect_error = None;
+        for op in &ops {
+            if let WorkerOp::Disconnect(op) = &op {
+                disconnect_error = Some(ConnectionStatusError {
+                    code: op.code.clone(),
+                    detail: op.reason.clone(),
+                });
+            }
+            self.track_op(&op);
+        }
+
+        match (status, disconnect_error) {
+            (Err(error), _) | (Ok(()), Some(error)) => self.disconnect(error),
+            (Ok(()), None) => {}
+        }
+
+        ops
+    }
+
+    fn get_worker_id(&self) -> &str {
+        &self.worker_id
+    }
+
+    fn get_worker_attributes(&self) -> &[String] {
+        &self.worker_attributes
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod test {
+    use super::*;
+    use crate::testing::MockConnection;
+    use std::{cell::Cell, rc::Rc};
+
+    fn network_error() -> ConnectionStatusError {
+        ConnectionStatusError {
+            code: ConnectionStatusErrorCode::NetworkError,
+            detail: "Connection reset".to_owned(),
+        }
+    }
+
+    fn no_delay() -> ReconnectPolicy {
+        ReconnectPolicy::new().with_initial_delay(Duration::from_millis(0))
+    }
+
+    #[test]
+    fn transient_connection_failures_are_retried() {
+        let mut attempts = 0;
+        let connection = ReconnectingConnection::connect(
+            || {
+                attempts += 1;
+                if attempts < 3 {
+                    future::ready(Err(network_error()))
+                } else {
+                    future::ready(Ok(MockConnection::new("worker")))
+                }
+            },
+            no_delay(),
+        );
+
+        let events = connection.expect("Failed to connect").take_events();
+        assert_eq!(3, events.len());
+        match events[2] {
+            ConnectionEvent::Connected { attempts } => assert_eq!(3, attempts),
+            _ => panic!("Expected a connected event"),
+        }
+    }
+
+    #[test]
+    fn permanent_connection_failures_are_not_retried() {
+        let result = ReconnectingConnection::connect(
+            || {
+                future::ready(Err::<MockConnection, _>(ConnectionStatusError {
+                    code: ConnectionStatusErrorCode::Rejected,
+                    detail: "Bad credentials".to_owned(),
+                }))
+            },
+            no_delay(),
+        );
+
+        assert_eq!(
+            ConnectionStatusErrorCode::Rejected,
+            result.err().expect("Expected connecting to fail").code
+        );
+    }
+
+    #[test]
+    fn reconnects_after_disconnect_and_reports_invalidated_state() {
+        let mut connections = 0;
+        let mut connection = ReconnectingConnection::connect(
+            || {
+                connections += 1;
+                let mut connection = MockConnection::new(format!("worker{}", connections));
+                if connections == 1 {
+                    connection.push_add_entity(EntityId::new(1));
+                    connection
+                        .pending_ops_mut()
+                        .disconnect(ConnectionStatusErrorCode::NetworkError, "Connection lost");
+                }
+                future::ready(Ok(connection))
+            },
+            no_delay(),
+        )
+        .expect("Failed to connect");
+        connection.take_events();
+
+        let request_id =
+            connection.send_delete_entity_request(DeleteEntityRequest(EntityId::new(1)), None);
+        assert_eq!(2, connection.get_op_list(0).len());
+
+        match &connection.take_events()[..] {
+            [ConnectionEvent::Disconnected { error, invalidated }] => {
+                assert_eq!("Connection lost", error.detail);
+                assert_eq!(vec![EntityId::new(1)], invalidated.entities);
+                assert_eq!(vec![request_id], invalidated.requests);
+            }
+            _ => panic!("Expected a disconnected event"),
+        }
+
+        assert!(connection.get_op_list(0).is_empty());
+        assert_eq!("worker2", connection.get_worker_id());
+        match &connection.take_events()[..] {
+            [ConnectionEvent::Connected { attempts: 1 }] => {}
+            _ => panic!("Expected a connected event"),
+        }
+    }
+
+    #[test]
+    fn reconnecting_waits_for_the_delay_without_blocking() {
+        let connections = Rc::new(Cell::new(0));
+        let counter = connections.clone();
+        let mut connection = ReconnectingConnection::connect(
+            move || {
+                counter.set(counter.get() + 1);
+                if counter.get() > 1 {
+                    return future::ready(Err(network_error()));
+                }
+
+                let mut connection = MockConnection::new("worker");
+                connection
+                    .pending_ops_mut()
+                    .disconnect(ConnectionStatusErrorCode::NetworkError, "Connection lost");
+                future::ready(Ok(connection))
+            },
+            ReconnectPolicy::new().with_initial_delay(Duration::from_secs(60)),
+        )
+        .expect("Failed to connect");
+        connection.take_events();
+
+        // Receive the disconnect, then make the first reconnection attempt, which fails.
+        connection.get_op_list(0);
+        connection.get_op_list(0);
+        assert_eq!(2, connections.get());
+        match &connection.take_events()[..] {
+            [ConnectionEvent::Disconnected { .. }, ConnectionEvent::Reconnecting {
+                attempt: 1, delay, ..
+            }] => {
+                assert_eq!(Duration::from_secs(60), *delay)
+            }
+            events => panic!("Unexpected events: {:?}", events),
+        }
+
+        // The next attempt isn't made until the delay has elapsed.
+        assert!(connection.get_op_list(0).is_empty());
+        assert_eq!(2, connections.get());
+        assert!(connection.is_connected());
+        assert_eq!(
+            ConnectionStatusErrorCode::NetworkError,
+            connection.get_connection_status().unwrap_err().code
+        );
+    }
+
+    #[test]
+    fn permanent_disconnects_are_not_retried() {
+        let connections = Rc::new(Cell::new(0));
+        let counter = connections.clone();
+        let mut connection = ReconnectingConnection::connect(
+            move || {
+                counter.set(counter.get() + 1);
+                let mut connection = MockConnection::new("worker");
+                connection
+                    .pending_ops_mut()
+                    .disconnect(ConnectionStatusErrorCode::Rejected, "Kicked by the runtime");
+                future::ready(Ok(connection))
+            },
+            no_delay(),
+        )
+        .expect("Failed to connect");
+        connection.take_events();
+
+        connection.get_op_list(0);
+        connection.get_op_list(0);
+
+        assert_eq!(1, connections.get());
+        assert!(!connection.is_connected());
+        match &connection.take_events()[..] {
+            [ConnectionEvent::Disconnected { error, .. }, ConnectionEvent::Failed { .. }] => {
+                assert_eq!(ConnectionStatusErrorCode::Rejected, error.code)
+            }
+            events => panic!("Unexpected events: {:?}", events),
+        }
+    }
+
+    #[test]
+    fn requests_sent_while_disconnected_receive_failed_responses() {
+        let mut connection = ReconnectingConnection::connect(
+            || {
+                let mut connection = MockConnection::new("worker");
+                connection
+                    .pending_ops_mut()
+                    .disconnect(ConnectionStatusErrorCode::Rejected, "Kicked by the runtime");
+                future::ready(Ok(connection))
+            },
+            no_delay(),
+        )
+        .expect("Failed to connect");
+        connection.get_op_list(0);
+
+        let first =
+            connection.send_delete_entity_request(DeleteEntityRequest(EntityId::new(1)), None);
+        let second = connection.send_reserve_entity_ids_request(ReserveEntityIdsRequest(1), None);
+        assert_ne!(first, second);
+
+        let ops = connection.get_op_list(0);
+        let mut ops = ops.iter();
+        match ops.next() {
+            Some(WorkerOp::DeleteEntityResponse(op)) => {
+                assert_eq!(first, op.request_id);
+                match &op.response {
+                    Err(CommandResponseError {
+                        code: CommandStatusCode::Timeout,
+                        ..
+                    }) => {}
+                    response => panic!("Expected a timeout, got {:?}", response),
+                }
+            }
+            _ => panic!("Expected a delete entity response"),
+        }
+        match ops.next() {
+            Some(WorkerOp::ReserveEntityIdsResponse(op)) => assert_eq!(second, op.request_id),
+            _ => panic!("Expected a reserve entity IDs response"),
+        }
+        assert!(connection.get_op_list(0).is_empty());
+    }
+
+    #[test]
+    fn delay_grows_exponentially_up_to_the_maximum() {
+        let policy = ReconnectPolicy::new()
+            .with_initial_delay(Duration::from_secs(1))
+            .with_max_delay(Duration::from_secs(5));
+
+        assert_eq!(Duration::from_secs(1), policy.delay(1));
+        assert_eq!(Duration::from_secs(2), policy.delay(2));
+        assert_eq!(Duration::from_secs(4), policy.delay(3));
+        assert_eq!(Duration::from_secs(5), policy.delay(4));
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_multiplier_is_rejected() {
+        ReconnectPolicy::new().with_multiplier(-2.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn nan_multiplier_is_rejected() {
+        ReconnectPolicy::new().with_multiplier(std::f64::NAN);
+    }
+}
@@ -0,0 +1,386 @@
+use crate::{
+    commands::*,
+    component::*,
+    connection::{Connection, ConnectionStatus, DEFAULT_POLL_INTERVAL},
+    logging::LogLevel,
+    metrics::Metrics,
+    op::OwnedWorkerOp,
+    {EntityId, RequestId},
+};
+use futures::{
+    channel::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    Stream,
+};
+use std::{
+    ffi::NulError,
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard},
+    task::{Context, Poll},
+    thread,
+    time::Duration,
+};
+
+/// A stream of the ops received by a connection.
+///
+/// Creating an `OpStream` splits a connection into a stream of incoming ops and an
+/// [`OpSender`] for sending messages, so that a worker can be driven by an async
+/// executor. The connection is polled by a background thread, which calls
+/// `get_op_list(0)` and forwards any ops to the stream. When no ops are available, the
+/// thread sleeps for [`DEFAULT_POLL_INTERVAL`] (or the interval passed to
+/// [`with_poll_interval`]) before polling again, so an idle stream doesn't busy-wait.
+///
+/// The stream ends once a `Disconnect` op has been received, after yielding the
+/// `Disconnect` op itself. The polling thread stops at the same point, or as soon as
+/// the stream is dropped.
+///
+/// # Examples
+///
+/// ```no_run
+/// use futures::{executor::block_on, StreamExt};
+/// use spatialos_sdk::{
+///     connection::{OpStream, WorkerConnection},
+///     logging::LogLevel,
+///     op::OwnedWorkerOp,
+/// };
+///
+/// # let connection: WorkerConnection = unimplemented!();
+/// let (mut ops, sender) = OpStream::new(connection);
+/// block_on(async {
+///     while let Some(op) = ops.next().await {
+///         if let OwnedWorkerOp::AddEntity(op) = op {
+///             sender.send_log_message(LogLevel::Info, "main", "Entity added", Some(op.entity_id));
+///         }
+///     }
+/// });
+/// ```
+///
+/// [`OpSender`]: struct.OpSender.html
+/// [`DEFAULT_POLL_INTERVAL`]: constant.DEFAULT_POLL_INTERVAL.html
+/// [`with_poll_interval`]: #method.with_poll_interval
+#[derive(Debug)]
+pub struct OpStream {
+    receiver: UnboundedReceiver<OwnedWorkerOp>,
+}
+
+impl OpStream {
+    /// Splits `connection` into a stream of ops and a sender, polling for new ops every
+    /// [`DEFAULT_POLL_INTERVAL`] when idle.
+    ///
+    /// [`DEFAULT_POLL_INTERVAL`]: constant.DEFAULT_POLL_INTERVAL.html
+    pub fn new<C>(connection: C) -> (Self, OpSender<C>)
+    where
+        C: Connection + Send + 'static,
+    {
+        Self::with_poll_interval(connection, DEFAULT_POLL_INTERVAL)
+    }
+
+    /// Splits `connection` into a stream of ops and a sender, polling for new ops every
+    /// `poll_interval` when idle.
+    pub fn with_poll_interval<C>(connection: C, poll_interval: Duration) -> (Self, OpSender<C>)
+    where
+        C: Connection + Send + 'static,
+    {
+        let sender = OpSender {
+            worker_id: connection.get_worker_id().to_owned(),
+            worker_attributes: connection.get_worker_attributes().to_vec(),
+            connection: Arc::new(Mutex::new(connection)),
+        };
+
+        let (op_sender, receiver) = mpsc::unbounded();
+        let connection = sender.connection.clone();
+        thread::spawn(move || poll_connection(&connection, &op_sender, poll_interval));
+
+        (OpStream::from_receiver(receiver), sender)
+    }
+
+    pub(crate) fn from_receiver(receiver: UnboundedReceiver<OwnedWorkerOp>) -> Self {
+        OpStream { receiver }
+    }
+}
+
+impl Stream for OpStream {
+    type Item = OwnedWorkerOp;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.receiver).poll_next(cx)
+    }
+}
+
+fn poll_connection<C: Connection>(
+    connection: &Mutex<C>,
+    sender: &UnboundedSender<OwnedWorkerOp>,
+    poll_interval: Duration,
+) {
+    // Stop polling once the stream has been dropped.
+    while !sender.is_closed() {
+        let ops = connection.lock().unwrap().get_op_list(0).to_owned_ops();
+        if ops.is_empty() {
+            thread::sleep(poll_interval);
+            continue;
+        }
+
+        for op in ops {
+            match op {
+                // No more ops are received after a disconnect, so the stream ends there.
+                OwnedWorkerOp::Disconnect(_) => {
+                    let _ = sender.unbounded_send(op);
+                    return;
+                }
+                _ => {
+                    if sender.unbounded_send(op).is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The sending half of a connection that has been split by [`OpStream`].
+///
+/// `OpSender` provides the sending methods of [`Connection`], each of which forwards the
+/// message to the underlying connection. It doesn't implement `Connection` itself, since
+/// incoming ops are only delivered by the paired [`OpStream`].
+///
+/// Senders can be cloned in order to send messages from multiple tasks or threads.
+///
+/// [`OpStream`]: struct.OpStream.html
+/// [`Connection`]: trait.Connection.html
+#[derive(Debug)]
+pub struct OpSender<C> {
+    connection: Arc<Mutex<C>>,
+    worker_id: String,
+    worker_attributes: Vec<String>,
+}
+
+impl<C> OpSender<C> {
+    /// Locks the underlying connection.
+    ///
+    /// This provides access to methods that `OpSender` doesn't forward, such as those
+    /// specific to the type of connection. Any ops retrieved directly from the locked
+    /// connection will not be delivered to the paired [`OpStream`].
+    ///
+    /// [`OpStream`]: struct.OpStream.html
+    pub fn lock(&self) -> MutexGuard<'_, C> {
+        self.connection.lock().unwrap()
+    }
+}
+
+impl<C> Clone for OpSender<C> {
+    fn clone(&self) -> Self {
+        OpSender {
+            connection: self.connection.clone(),
+            worker_id: self.worker_id.clone(),
+            worker_attributes: self.worker_attributes.clone(),
+        }
+    }
+}
+
+impl<C: Connection> OpSender<C> {
+    pub fn send_log_message(
+        &self,
+        level: LogLevel,
+        logger_name: &str,
+        message: &str,
+        entity_id: Option<EntityId>,
+    ) {
+        self.lock()
+            .send_log_message(level, logger_name, message, entity_id)
+    }
+
+    pub fn send_metrics(&self, metrics: &Metrics) {
+        self.lock().send_metrics(metrics)
+    }
+
+    pub fn send_reserve_entity_ids_request(
+        &self,
+        payload: ReserveEntityIdsRequest,
+        timeout_millis: Option<u32>,
+    ) -> RequestId {
+        self.lock()
+            .send_reserve_entity_ids_request(payload, timeout_millis)
+    }
+
+    pub fn send_create_entity_request(
+        &self,
+        payload: CreateEntityRequest,
+        timeout_millis: Option<u32>,
+    ) -> RequestId {
+        self.lock()
+            .send_create_entity_request(payload, timeout_millis)
+    }
+
+    pub fn send_delete_entity_request(
+        &self,
+        payload: DeleteEntityRequest,
+        timeout_millis: Option<u32>,
+    ) -> RequestId {
+        self.lock()
+            .send_delete_entity_request(payload, timeout_millis)
+    }
+
+    pub fn send_entity_query_request(
+        &self,
+        payload: EntityQueryRequest,
+        timeout_millis: Option<u32>,
+    ) -> RequestId {
+        self.lock()
+            .send_entity_query_request(payload, timeout_millis)
+    }
+
+    pub fn send_command_request<T: Into<CommandRequest>>(
+        &self,
+        entity_id: EntityId,
+        request: T,
+        timeout_millis: Option<u32>,
+        params: CommandParameters,
+    ) -> RequestId {
+        self.lock()
+            .send_command_request(entity_id, request, timeout_millis, params)
+    }
+
+    pub fn send_command_response<T: Into<CommandResponse>>(
+        &self,
+        request_id: RequestId,
+        response: T,
+    ) {
+        self.lock().send_command_response(request_id, response)
+    }
+
+    pub fn send_command_failure(
+        &self,
+        request_id: RequestId,
+        message: &str,
+    ) -> Result<(), NulError> {
+        self.lock().send_command_failure(request_id, message)
+    }
+
+    pub fn send_component_update<T: Into<ComponentUpdate>>(
+        &self,
+        entity_id: EntityId,
+        update: T,
+        parameters: UpdateParameters,
+    ) {
+        self.lock()
+            .send_component_update(entity_id, update, parameters)
+    }
+
+    pub fn send_add_component<T: Component>(
+        &self,
+        entity_id: EntityId,
+        component: &T,
+        parameters: UpdateParameters,
+    ) {
+        self.lock()
+            .send_add_component(entity_id, component, parameters)
+    }
+
+    pub fn send_remove_component<T: Component>(
+        &self,
+        entity_id: EntityId,
+        parameters: UpdateParameters,
+    ) {
+        self.lock()
+            .send_remove_component::<T>(entity_id, parameters)
+    }
+
+    pub fn send_authority_loss_imminent_acknowledgement(
+        &self,
+        entity_id: EntityId,
+        component_id: ComponentId,
+    ) {
+        self.lock()
+            .send_authority_loss_imminent_acknowledgement(entity_id, component_id)
+    }
+
+    pub fn send_component_interest(&self, entity_id: EntityId, interest: &ComponentInterest) {
+        self.lock().send_component_interest(entity_id, interest)
+    }
+
+    pub fn flush(&self) {
+        self.lock().flush()
+    }
+
+    pub fn enable_logging(&self) {
+        self.lock().enable_logging()
+    }
+
+    pub fn disable_logging(&self) {
+        self.lock().disable_logging()
+    }
+
+    pub fn get_connection_status(&self) -> ConnectionStatus {
+        self.lock().get_connection_status()
+    }
+
+    pub fn get_worker_flag(&self, name: &str) -> Option<String> {
+        self.lock().get_worker_flag(name)
+    }
+
+    pub fn get_worker_id(&self) -> &str {
+        &self.worker_id
+    }
+
+    pub fn get_worker_attributes(&self) -> &[String] {
+        &self.worker_attributes
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod test {
+    use super::*;
+    use crate::{
+        connection::ConnectionStatusErrorCode,
+        testing::{MockConnection, SentMessage},
+    };
+    use futures::{executor::block_on, StreamExt};
+
+    #[test]
+    fn stream_yields_ops_and_ends_after_disconnect() {
+        let mut connection = MockConnection::new("test_worker");
+        connection.push_add_entity(EntityId::new(1));
+        connection
+            .pending_ops_mut()
+            .disconnect(ConnectionStatusErrorCode::ServerShutdown, "Shutting down");
+
+        let (ops, _sender) = OpStream::new(connection);
+        let ops = block_on(ops.collect::<Vec<_>>());
+
+        assert_eq!(2, ops.len());
+        match &ops[0] {
+            OwnedWorkerOp::AddEntity(op) => assert_eq!(EntityId::new(1), op.entity_id),
+            _ => panic!("Expected an add entity op"),
+        }
+        match &ops[1] {
+            OwnedWorkerOp::Disconnect(op) => assert_eq!("Shutting down", op.reason),
+            _ => panic!("Expected a disconnect op"),
+        }
+    }
+
+    #[test]
+    fn sender_forwards_messages_to_connection() {
+        let (_ops, sender) = OpStream::new(MockConnection::new("test_worker"));
+        assert_eq!("test_worker", sender.get_worker_id());
+
+        sender.send_log_message(LogLevel::Info, "test", "Hello", None);
+        sender.clone().flush();
+
+        let sent = sender.lock().take_sent();
+        assert_eq!(2, sent.len());
+        match &sent[0] {
+            SentMessage::LogMessage { message, .. } => assert_eq!("Hello", message),
+            _ => panic!("Expected a log message"),
+        }
+    }
+
+    #[test]
+    fn stream_yields_ops_that_arrive_after_it_was_created() {
+        let (mut ops, sender) = OpStream::new(MockConnection::new("test_worker"));
+
+        sender.lock().push_add_entity(EntityId::new(7));
+        match block_on(ops.next()) {
+            Some(OwnedWorkerOp::AddEntity(op)) => assert_eq!(EntityId::new(7), op.entity_id),
+            _ => panic!("Expected an add entity op"),
+        }
+    }
+}
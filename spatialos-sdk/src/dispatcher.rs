@@ -0,0 +1,406 @@
+//! Routing of ops to callbacks registered per op kind and per component type.
+//!
+//! Rather than matching on every [`WorkerOp`] by hand and deserializing component
+//! data with `get::<C>()`, callbacks can be registered with a [`Dispatcher`] for the
+//! ops and components that a worker cares about. The dispatcher then routes each op
+//! list to the matching callbacks, deserializing component data, updates and command
+//! payloads into their generated types along the way.
+//!
+//! Callbacks are invoked in the order that the ops appear in the op list. Where
+//! multiple callbacks are registered for the same op, they are invoked in the order
+//! that they were registered.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use spatialos_sdk::{
+//!     connection::{Connection, WorkerConnection},
+//!     dispatcher::Dispatcher,
+//! };
+//!
+//! # let mut connection: WorkerConnection = unimplemented!();
+//! let mut dispatcher = Dispatcher::new();
+//! dispatcher
+//!     .on_add_entity(|op| println!("{} entered the worker's view", op.entity_id))
+//!     .on_flag_update(|op| println!("Flag {} changed to {}", op.name, op.value));
+//!
+//! loop {
+//!     let ops = connection.get_op_list(0);
+//!     dispatcher.process_op_list(&ops);
+//! }
+//! ```
+//!
+//! [`WorkerOp`]: ../op/enum.WorkerOp.html
+//! [`Dispatcher`]: struct.Dispatcher.html
+
+use crate::{
+    commands::Commands,
+    component::{Component, ComponentId},
+    op::*,
+    schema, Authority, EntityId,
+};
+use std::collections::HashMap;
+
+type Callbacks<'a, T> = Vec<Box<dyn FnMut(&T) + 'a>>;
+
+// Ops carrying component data borrow from the op list, so their callbacks take the
+// whole op in order to be callable for any op list lifetime.
+type ComponentCallbacks<'a> = HashMap<ComponentId, Vec<Box<dyn FnMut(&WorkerOp<'_>) + 'a>>>;
+
+/// Routes ops to callbacks registered per op kind and per component type.
+///
+/// Callbacks may borrow from their environment for the lifetime `'a` of the
+/// dispatcher. Each callback is boxed, so registering callbacks allocates, but
+/// dispatching ops to them does not.
+///
+/// See the [module documentation] for more details.
+///
+/// [module documentation]: index.html
+#[derive(Default)]
+pub struct Dispatcher<'a> {
+    disconnect: Callbacks<'a, DisconnectOp>,
+    flag_update: Callbacks<'a, FlagUpdateOp>,
+    log_message: Callbacks<'a, LogMessageOp>,
+    metrics: Callbacks<'a, MetricsOp>,
+    critical_section: Callbacks<'a, CriticalSectionOp>,
+    add_entity: Callbacks<'a, AddEntityOp>,
+    remove_entity: Callbacks<'a, RemoveEntityOp>,
+    reserve_entity_ids_response: Callbacks<'a, ReserveEntityIdsResponseOp>,
+    create_entity_response: Callbacks<'a, CreateEntityResponseOp>,
+    delete_entity_response: Callbacks<'a, DeleteEntityResponseOp>,
+    entity_query_response: Callbacks<'a, EntityQueryResponseOp>,
+
+    add_component: ComponentCallbacks<'a>,
+    remove_component: ComponentCallbacks<'a>,
+    authority_change: ComponentCallbacks<'a>,
+    component_update: ComponentCallbacks<'a>,
+    command_request: ComponentCallbacks<'a>,
+    command_response: ComponentCallbacks<'a>,
+}
+
+impl<'a> Dispatcher<'a> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Invokes the registered callbacks for every op in `ops`.
+    pub fn process_op_list(&mut self, ops: &OpList) {
+        for op in ops {
+            self.process_op(&op);
+        }
+    }
+
+    /// Invokes the registered callbacks for a single op.
+    pub fn process_op(&mut self, op: &WorkerOp<'_>) {
+        match op {
+            WorkerOp::Disconnect(op) => invoke(&mut self.disconnect, op),
+            WorkerOp::FlagUpdate(op) => invoke(&mut self.flag_update, op),
+            WorkerOp::LogMessage(op) => invoke(&mut self.log_message, op),
+            WorkerOp::Metrics(op) => invoke(&mut self.metrics, op),
+            WorkerOp::CriticalSection(op) => invoke(&mut self.critical_section, op),
+            WorkerOp::AddEntity(op) => invoke(&mut self.add_entity, op),
+            WorkerOp::RemoveEntity(op) => invoke(&mut self.remove_entity, op),
+            WorkerOp::ReserveEntityIdsResponse(op) => {
+                invoke(&mut self.reserve_entity_ids_response, op)
+            }
+            WorkerOp::CreateEntityResponse(op) => invoke(&mut self.create_entity_response, op),
+            WorkerOp::DeleteEntityResponse(op) => invoke(&mut self.delete_entity_response, op),
+            WorkerOp::EntityQueryResponse(op) => invoke(&mut self.entity_query_response, op),
+
+            WorkerOp::AddComponent(inner) => {
+                invoke_for_component(&mut self.add_component, inner.component_id, op)
+            }
+            WorkerOp::RemoveComponent(inner) => {
+                invoke_for_component(&mut self.remove_component, inner.component_id, op)
+            }
+            WorkerOp::AuthorityChange(inner) => {
+                invoke_for_component(&mut self.authority_change, inner.component_id, op)
+            }
+            WorkerOp::ComponentUpdate(inner) => {
+                invoke_for_component(&mut self.component_update, inner.component_id, op)
+            }
+            WorkerOp::CommandRequest(inner) => {
+                invoke_for_component(&mut self.command_request, inner.component_id, op)
+            }
+            WorkerOp::CommandResponse(inner) => {
+                invoke_for_component(&mut self.command_response, inner.component_id, op)
+            }
+        }
+    }
+
+    pub fn on_disconnect<F: FnMut(&DisconnectOp) + 'a>(&mut self, callback: F) -> &mut Self {
+        self.disconnect.push(Box::new(callback));
+        self
+    }
+
+    pub fn on_flag_update<F: FnMut(&FlagUpdateOp) + 'a>(&mut self, callback: F) -> &mut Self {
+        self.flag_update.push(Box::new(callback));
+        self
+    }
+
+    pub fn on_log_message<F: FnMut(&LogMessageOp) + 'a>(&mut self, callback: F) -> &mut Self {
+        self.log_message.push(Box::new(callback));
+        self
+    }
+
+    pub fn on_metrics<F: FnMut(&MetricsOp) + 'a>(&mut self, callback: F) -> &mut Self {
+        self.metrics.push(Box::new(callback));
+        self
+    }
+
+    pub fn on_critical_section<F: FnMut(&CriticalSectionOp) + 'a>(
+        &mut self,
+        callback: F,
+    ) -> &mut Self {
+        self.critical_section.push(Box::new(callback));
+        self
+    }
+
+    pub fn on_add_entity<F: FnMut(&AddEntityOp) + 'a>(&mut self, callback: F) -> &mut Self {
+        self.add_entity.push(Box::new(callback));
+        self
+    }
+
+    pub fn on_remove_entity<F: FnMut(&RemoveEntityOp) + 'a>(&mut self, callback: F) -> &mut Self {
+        self.remove_entity.push(Box::new(callback));
+        self
+    }
+
+    pub fn on_reserve_entity_ids_response<F: FnMut(&ReserveEntityIdsResponseOp) + 'a>(
+        &mut self,
+        callback: F,
+    ) -> &mut Self {
+        self.reserve_entity_ids_response.push(Box::new(callback));
+        self
+    }
+
+    pub fn on_create_entity_response<F: FnMut(&CreateEntityResponseOp) + 'a>(
+        &mut self,
+        callback: F,
+    ) -> &mut Self {
+        self.create_entity_response.push(Box::new(callback));
+        self
+    }
+
+    pub fn on_delete_entity_response<F: FnMut(&DeleteEntityResponseOp) + 'a>(
+        &mut self,
+        callback: F,
+    ) -> &mut Self {
+        self.delete_entity_response.push(Box::new(callback));
+        self
+    }
+
+    pub fn on_entity_query_response<F: FnMut(&EntityQueryResponseOp) + 'a>(
+        &mut self,
+        callback: F,
+    ) -> &mut Self {
+        self.entity_query_response.push(Box::new(callback));
+        self
+    }
+
+    /// Registers a callback for when component `C` is added to an entity.
+    ///
+    /// The callback receives the deserialized component data, or the error that
+    /// occurred while deserializing it.
+    pub fn on_add_component<C, F>(&mut self, mut callback: F) -> &mut Self
+    where
+        C: Component,
+        F: FnMut(EntityId, schema::Result<C>) + 'a,
+    {
+        register(&mut self.add_component, C::ID, move |op| {
+            if let WorkerOp::AddComponent(op) = op {
+                if let Some(result) = op.get::<C>() {
+                    callback(op.entity_id, result);
+                }
+            }
+        });
+        self
+    }
+
+    /// Registers a callback for when component `C` is removed from an entity.
+    pub fn on_remove_component<C, F>(&mut self, mut callback: F) -> &mut Self
+    where
+        C: Component,
+        F: FnMut(EntityId) + 'a,
+    {
+        register(&mut self.remove_component, C::ID, move |op| {
+            if let WorkerOp::RemoveComponent(op) = op {
+                callback(op.entity_id);
+            }
+        });
+        self
+    }
+
+    /// Registers a callback for when the worker's authority over component `C` changes.
+    pub fn on_authority_change<C, F>(&mut self, mut callback: F) -> &mut Self
+    where
+        C: Component,
+        F: FnMut(EntityId, Authority) + 'a,
+    {
+        register(&mut self.authority_change, C::ID, move |op| {
+            if let WorkerOp::AuthorityChange(op) = op {
+                callback(op.entity_id, op.authority);
+            }
+        });
+        self
+    }
+
+    /// Registers a callback for updates to component `C`.
+    ///
+    /// The callback receives the deserialized update, or the error that occurred while
+    /// deserializing it.
+    pub fn on_component_update<C, F>(&mut self, mut callback: F) -> &mut Self
+    where
+        C: Component,
+        F: FnMut(EntityId, schema::Result<C::Update>) + 'a,
+    {
+        register(&mut self.component_update, C::ID, move |op| {
+            if let WorkerOp::ComponentUpdate(op) = op {
+                if let Some(result) = op.get::<C>() {
+                    callback(op.entity_id, result);
+                }
+            }
+        });
+        self
+    }
+
+    /// Registers a callback for incoming command requests for the commands `C`.
+    ///
+    /// The callback receives the op, so that the request ID and caller details are
+    /// available when responding, along with the deserialized request or the error
+    /// that occurred while deserializing it.
+    pub fn on_command_request<C, F>(&mut self, mut callback: F) -> &mut Self
+    where
+        C: Commands,
+        F: FnMut(&CommandRequestOp<'_>, schema::Result<C::Request>) + 'a,
+    {
+        register(&mut self.command_request, C::Component::ID, move |op| {
+            if let WorkerOp::CommandRequest(op) = op {
+                if let Some(result) = op.get::<C>() {
+                    callback(op, result);
+                }
+            }
+        });
+        self
+    }
+
+    /// Registers a callback for responses to command requests sent for the commands `C`.
+    ///
+    /// If the command failed, the callback receives the `CommandResponseError` for the
+    /// failure. Otherwise it receives the deserialized response, or the error that
+    /// occurred while deserializing it.
+    pub fn on_command_response<C, F>(&mut self, mut callback: F) -> &mut Self
+    where
+        C: Commands,
+        F: FnMut(
+                &CommandResponseOp<'_>,
+                Result<schema::Result<C::Response>, &CommandResponseError>,
+            ) + 'a,
+    {
+        register(&mut self.command_response, C::Component::ID, move |op| {
+            if let WorkerOp::CommandResponse(op) = op {
+                match &op.response {
+                    Ok(response) => {
+                        if let Some(result) = response.get::<C>() {
+                            callback(op, Ok(result));
+                        }
+                    }
+                    Err(error) => callback(op, Err(error)),
+                }
+            }
+        });
+        self
+    }
+}
+
+fn invoke<T>(callbacks: &mut Callbacks<'_, T>, op: &T) {
+    for callback in callbacks {
+        callback(op);
+    }
+}
+
+fn invoke_for_component(
+    callbacks: &mut ComponentCallbacks<'_>,
+    component_id: ComponentId,
+    op: &WorkerOp<'_>,
+) {
+    if let Some(callbacks) = callbacks.get_mut(&component_id) {
+        for callback in callbacks {
+            callback(op);
+        }
+    }
+}
+
+fn register<'a, F>(callbacks: &mut ComponentCallbacks<'a>, component_id: ComponentId, callback: F)
+where
+    F: FnMut(&WorkerOp<'_>) + 'a,
+{
+    callbacks
+        .entry(component_id)
+        .or_default()
+        .push(Box::new(callback));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::connection::ConnectionStatusErrorCode;
+    use std::cell::RefCell;
+
+    #[test]
+    fn routes_ops_to_matching_callbacks_in_order() {
+        let events = RefCell::new(Vec::new());
+
+        let mut dispatcher = Dispatcher::new();
+        dispatcher
+            .on_add_entity(|op| events.borrow_mut().push(format!("add {}", op.entity_id.id)))
+            .on_remove_entity(|op| {
+                events
+                    .borrow_mut()
+                    .push(format!("remove {}", op.entity_id.id))
+            })
+            .on_critical_section(|op| {
+                events
+                    .borrow_mut()
+                    .push(format!("critical {}", op.in_critical_section))
+            });
+
+        let ops = OpListBuilder::new()
+            .critical_section(true)
+            .add_entity(EntityId::new(1))
+            .add_entity(EntityId::new(2))
+            .remove_entity(EntityId::new(1))
+            .critical_section(false)
+            .disconnect(ConnectionStatusErrorCode::NetworkError, "ignored")
+            .build();
+        dispatcher.process_op_list(&ops);
+        drop(dispatcher);
+
+        assert_eq!(
+            vec![
+                "critical true",
+                "add 1",
+                "add 2",
+                "remove 1",
+                "critical false"
+            ],
+            events.into_inner()
+        );
+    }
+
+    #[test]
+    fn invokes_every_callback_for_an_op() {
+        let mut count = 0;
+
+        let mut dispatcher = Dispatcher::new();
+        dispatcher
+            .on_flag_update(|_| count += 1)
+            .on_flag_update(|op| assert_eq!("my-flag", op.name));
+
+        let ops = OpListBuilder::new().flag_update("my-flag", "1").build();
+        dispatcher.process_op_list(&ops);
+        drop(dispatcher);
+
+        assert_eq!(1, count);
+    }
+}
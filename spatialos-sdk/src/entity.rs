@@ -116,6 +116,13 @@ impl Entity {
             .collect()
     }
 
+    #[cfg(feature = "serde")]
+    pub(crate) fn components(
+        &self,
+    ) -> impl Iterator<Item = (ComponentId, &Owned<SchemaComponentData>)> {
+        self.components.iter().map(|(id, data)| (*id, data))
+    }
+
     fn pre_add_check(&self, id: ComponentId) -> Result<(), String> {
         if self.components.contains_key(&id) {
             return Err(format!(
@@ -127,3 +134,42 @@ impl Entity {
         Ok(())
     }
 }
+
+/// Entities are serialized as a map from component ID to the binary schema encoding of the
+/// component's data, since the component types aren't known without a schema bundle.
+///
+/// This format is opaque: it round-trips losslessly through any serde format, but the component
+/// data is only readable by deserializing it back into an `Entity`. To produce JSON that tools
+/// and dashboards can inspect, use [`Bundle::entity_to_value`] instead.
+///
+/// [`Bundle::entity_to_value`]: schema/struct.Bundle.html#method.entity_to_value
+#[cfg(feature = "serde")]
+impl serde_crate::Serialize for Entity {
+    fn serialize<S: serde_crate::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde_crate::ser::{Error, SerializeMap};
+
+        let mut map = serializer.serialize_map(Some(self.components.len()))?;
+        for (component_id, data) in &self.components {
+            let bytes = data.to_bytes().map_err(S::Error::custom)?;
+            map.serialize_entry(component_id, &bytes)?;
+        }
+        map.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde_crate::Deserialize<'de> for Entity {
+    fn deserialize<D: serde_crate::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde_crate::de::Error;
+
+        let serialized = BTreeMap::<ComponentId, Vec<u8>>::deserialize(deserializer)?;
+        let mut entity = Entity::new();
+        for (component_id, bytes) in serialized {
+            let data =
+                Owned::<SchemaComponentData>::from_bytes(&bytes).map_err(D::Error::custom)?;
+            entity.components.insert(component_id, data);
+        }
+
+        Ok(entity)
+    }
+}
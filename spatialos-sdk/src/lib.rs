@@ -3,6 +3,7 @@
 pub mod commands;
 pub mod component;
 pub mod connection;
+pub mod dispatcher;
 pub mod entity;
 pub mod entity_builder;
 pub mod locator;
@@ -11,19 +12,35 @@ pub mod metrics;
 pub mod op;
 pub mod parameters;
 pub mod query;
+pub mod request_tracker;
 pub mod schema;
 pub mod snapshot;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod tracing;
+pub mod view;
+pub mod worker_flags;
 pub mod worker_future;
 
 pub(crate) mod ptr;
 pub(crate) mod utils;
 
+// Re-exported so that generated code can derive `Serialize` and `Deserialize` without
+// depending on serde directly.
+#[cfg(feature = "serde")]
+pub use serde_crate as serde;
+
 use std::fmt::{Display, Error, Formatter};
 
 // NOTE: This must be `repr(transparent)` in order for it to be ABI-compatible with
 // the C API, which uses a raw `i64` to represent an entity ID. See the comment on
 // the `impl_primitive_field!` macro for more details.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
 #[repr(transparent)]
 pub struct EntityId {
     pub id: i64,
@@ -86,6 +103,16 @@ impl From<u8> for Authority {
     }
 }
 
+impl From<Authority> for u8 {
+    fn from(authority: Authority) -> Self {
+        match authority {
+            Authority::NotAuthoritative => 0,
+            Authority::Authoritative => 1,
+            Authority::AuthorityLossImminent => 2,
+        }
+    }
+}
+
 #[derive(Copy, Clone, PartialOrd, PartialEq, Debug)]
 pub enum ConnectionStatusCode {
     Success,
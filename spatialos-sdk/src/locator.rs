@@ -1,20 +1,37 @@
 use std::ffi::CString;
+use std::future::Future;
 use std::ptr;
+use std::sync::{Arc, Mutex};
 
 use spatialos_sdk_sys::worker::*;
 
 use crate::{
+    connection::{ConnectionStatusError, WorkerConnection, WorkerConnectionFuture},
     logging::*,
-    parameters::ProtocolLoggingParameters,
+    parameters::{ConnectionParameters, ProtocolLoggingParameters},
     utils::cstr_to_string,
     worker_future::{WorkerFuture, WorkerSdkFuture},
 };
 
+/// A handle to a SpatialOS Locator.
+///
+/// Cloning a `Locator` is cheap, and all clones refer to the same underlying locator,
+/// which is destroyed once the last clone is dropped.
+#[derive(Clone)]
 pub struct Locator {
-    pub(crate) locator: *mut Worker_Locator,
+    inner: Arc<Mutex<LocatorInner>>,
+}
+
+struct LocatorInner {
+    locator: *mut Worker_Locator,
     release_callback_handles: Vec<ReleaseCallbackHandle>,
 }
 
+// SAFETY: The Worker SDK locator is not tied to the thread that created it, so it can be moved
+// between threads. The SDK makes no guarantees about concurrent calls on the same locator, so
+// `LocatorInner` is not `Sync` and all calls go through the mutex in `Locator`.
+unsafe impl Send for LocatorInner {}
+
 impl Locator {
     pub fn new<T: Into<Vec<u8>>>(hostname: T, port: u16, params: &LocatorParameters) -> Self {
         unsafe {
@@ -23,12 +40,26 @@ impl Locator {
             let ptr = Worker_Locator_Create(hostname.as_ptr(), port, &worker_params.as_raw());
             assert!(!ptr.is_null());
             Locator {
-                locator: ptr,
-                release_callback_handles,
+                inner: Arc::new(Mutex::new(LocatorInner {
+                    locator: ptr,
+                    release_callback_handles,
+                })),
             }
         }
     }
 
+    /// Calls `f` with the raw locator pointer, holding the lock for the duration of the call.
+    pub(crate) fn with_ptr<R, F: FnOnce(*mut Worker_Locator) -> R>(&self, f: F) -> R {
+        let inner = self.inner.lock().expect("Locator mutex was poisoned");
+        f(inner.locator)
+    }
+
+    /// Queries the list of deployments for the project set with
+    /// `LocatorParameters::with_project_name`.
+    pub fn get_deployment_list(&self) -> WorkerFuture<DeploymentListFuture> {
+        WorkerFuture::new(DeploymentListFuture::new(self.clone()))
+    }
+
     pub fn create_development_player_identity_token(
         hostname: &str,
         port: u16,
@@ -48,7 +79,54 @@ impl Locator {
     }
 }
 
-impl Drop for Locator {
+/// The locator operations needed to list deployments and join one.
+///
+/// `Locator` implements this on top of the Worker SDK. Launcher code written against this
+/// trait can be given a stub implementation in tests, without a running Locator service.
+pub trait DeploymentLocator {
+    type Connection;
+    type DeploymentListFuture: Future<Output = Result<Vec<Deployment>, String>>;
+    type ConnectionFuture: Future<Output = Result<Self::Connection, ConnectionStatusError>>;
+
+    /// Queries the list of deployments available to this locator.
+    fn get_deployment_list(&self) -> Self::DeploymentListFuture;
+
+    /// Connects to the named deployment, queueing if it is at capacity.
+    ///
+    /// See `WorkerConnection::connect_locator_and_queue` for how `on_queue_status` is used.
+    fn connect_and_queue(
+        &self,
+        deployment_name: &str,
+        params: ConnectionParameters,
+        on_queue_status: QueueStatusCallback,
+    ) -> Self::ConnectionFuture;
+}
+
+impl DeploymentLocator for Locator {
+    type Connection = WorkerConnection;
+    type DeploymentListFuture = WorkerFuture<DeploymentListFuture>;
+    type ConnectionFuture = WorkerFuture<WorkerConnectionFuture>;
+
+    fn get_deployment_list(&self) -> Self::DeploymentListFuture {
+        Locator::get_deployment_list(self)
+    }
+
+    fn connect_and_queue(
+        &self,
+        deployment_name: &str,
+        params: ConnectionParameters,
+        on_queue_status: QueueStatusCallback,
+    ) -> Self::ConnectionFuture {
+        WorkerConnection::connect_locator_and_queue(
+            self.clone(),
+            deployment_name,
+            params,
+            on_queue_status,
+        )
+    }
+}
+
+impl Drop for LocatorInner {
     fn drop(&mut self) {
         if !self.locator.is_null() {
             unsafe { Worker_Locator_Destroy(self.locator) }
@@ -61,6 +139,7 @@ impl Drop for Locator {
 }
 
 pub struct LocatorParameters {
+    pub project_name: Option<CString>,
     pub credentials: PlayerIdentityCredentials,
     pub use_insecure_connection: bool,
     pub logging: Option<ProtocolLoggingParameters>,
@@ -91,6 +170,7 @@ impl LocatorParameters {
 
     pub fn new(credentials: PlayerIdentityCredentials) -> Self {
         LocatorParameters {
+            project_name: None,
             credentials,
             use_insecure_connection: false,
             logging: None,
@@ -98,6 +178,13 @@ impl LocatorParameters {
         }
     }
 
+    pub fn with_project_name<S: AsRef<str>>(mut self, project_name: S) -> Self {
+        self.project_name = Some(
+            CString::new(project_name.as_ref()).expect("`project_name` contained a null byte"),
+        );
+        self
+    }
+
     pub fn with_insecure_connection(mut self) -> Self {
         self.use_insecure_connection = true;
         self
@@ -121,7 +208,10 @@ struct IntermediateLocatorParameters<'a> {
 impl<'a> IntermediateLocatorParameters<'a> {
     fn as_raw(&self) -> Worker_LocatorParameters {
         Worker_LocatorParameters {
-            project_name: ::std::ptr::null(),
+            project_name: match self.params.project_name {
+                Some(ref cstr) => cstr.as_ptr(),
+                None => ::std::ptr::null(),
+            },
             credentials_type:
                 Worker_LocatorCredentialsTypes_WORKER_LOCATOR_PLAYER_IDENTITY_CREDENTIALS as u8,
             login_token: Worker_LoginTokenCredentials::default(),
@@ -468,3 +558,297 @@ impl WorkerSdkFuture for LoginTokensFuture {
         Worker_Alpha_LoginTokensResponseFuture_Destroy(ptr)
     }
 }
+
+/// Details of a deployment obtained via `Locator::get_deployment_list`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deployment {
+    pub deployment_name: String,
+    pub assembly_name: String,
+    pub description: String,
+    pub users_connected: u32,
+    pub users_capacity: u32,
+}
+
+impl Deployment {
+    fn from_worker_sdk(deployment: &Worker_Deployment) -> Self {
+        Deployment {
+            deployment_name: cstr_to_string(deployment.deployment_name),
+            assembly_name: cstr_to_string(deployment.assembly_name),
+            description: cstr_to_string(deployment.description),
+            users_connected: deployment.users_connected,
+            users_capacity: deployment.users_capacity,
+        }
+    }
+}
+
+pub struct DeploymentListFuture {
+    locator: Locator,
+}
+
+impl DeploymentListFuture {
+    fn new(locator: Locator) -> Self {
+        DeploymentListFuture { locator }
+    }
+
+    extern "C" fn callback_handler(
+        user_data: *mut ::std::os::raw::c_void,
+        deployment_list: *const Worker_DeploymentList,
+    ) {
+        assert!(!deployment_list.is_null());
+        unsafe {
+            let deployment_list = *deployment_list;
+            let data = &mut *(user_data as *mut Result<Vec<Deployment>, String>);
+            if !deployment_list.error.is_null() {
+                *data = Err(cstr_to_string(deployment_list.error));
+                return;
+            }
+
+            if deployment_list.deployments.is_null() {
+                *data = Ok(Vec::new());
+                return;
+            }
+
+            let deployments = ::std::slice::from_raw_parts(
+                deployment_list.deployments,
+                deployment_list.deployment_count as usize,
+            )
+            .iter()
+            .map(Deployment::from_worker_sdk)
+            .collect();
+
+            *data = Ok(deployments);
+        }
+    }
+}
+
+impl WorkerSdkFuture for DeploymentListFuture {
+    type RawPointer = Worker_DeploymentListFuture;
+    type Output = Result<Vec<Deployment>, String>;
+
+    fn start(&mut self) -> *mut Self::RawPointer {
+        self.locator
+            .with_ptr(|locator| unsafe { Worker_Locator_GetDeploymentListAsync(locator) })
+    }
+
+    unsafe fn get(&mut self, ptr: *mut Self::RawPointer) -> Self::Output {
+        let mut data: Result<Vec<Deployment>, String> = Err("Callback never called.".into());
+        Worker_DeploymentListFuture_Get(
+            ptr,
+            ptr::null(),
+            &mut data as *mut _ as *mut ::std::os::raw::c_void,
+            Some(DeploymentListFuture::callback_handler),
+        );
+
+        data
+    }
+
+    unsafe fn destroy(&mut self, ptr: *mut Self::RawPointer) {
+        Worker_DeploymentListFuture_Destroy(ptr)
+    }
+}
+
+/// Callback invoked with queue status updates while connecting via
+/// `WorkerConnection::connect_locator_and_queue`.
+///
+/// The callback receives either the current position in the queue, or an error
+/// message. Returning `false` cancels queueing, and the connection attempt fails.
+pub type QueueStatusCallback = Box<dyn FnMut(Result<u32, String>) -> bool + Send>;
+
+pub(crate) extern "C" fn queue_status_handler(
+    user_data: *mut ::std::os::raw::c_void,
+    queue_status: *const Worker_QueueStatus,
+) -> u8 {
+    assert!(!queue_status.is_null());
+    unsafe {
+        let queue_status = *queue_status;
+        let callback = &mut *(user_data as *mut QueueStatusCallback);
+        let status = if queue_status.error.is_null() {
+            Ok(queue_status.position_in_queue)
+        } else {
+            Err(cstr_to_string(queue_status.error))
+        };
+
+        callback(status) as u8
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::connection::ConnectionStatusErrorCode;
+    use futures::{
+        executor::block_on,
+        future::{ready, Ready},
+    };
+    use std::os::raw::c_void;
+
+    /// A locator that serves a fixed deployment list and queue, without the Worker SDK.
+    struct StubLocator {
+        deployments: Vec<Deployment>,
+        queue_positions: Vec<u32>,
+    }
+
+    impl DeploymentLocator for StubLocator {
+        type Connection = String;
+        type DeploymentListFuture = Ready<Result<Vec<Deployment>, String>>;
+        type ConnectionFuture = Ready<Result<String, ConnectionStatusError>>;
+
+        fn get_deployment_list(&self) -> Self::DeploymentListFuture {
+            ready(Ok(self.deployments.clone()))
+        }
+
+        fn connect_and_queue(
+            &self,
+            deployment_name: &str,
+            _params: ConnectionParameters,
+            mut on_queue_status: QueueStatusCallback,
+        ) -> Self::ConnectionFuture {
+            for position in &self.queue_positions {
+                if !on_queue_status(Ok(*position)) {
+                    return ready(Err(ConnectionStatusError {
+                        code: ConnectionStatusErrorCode::Cancelled,
+                        detail: "Queueing was cancelled".to_owned(),
+                    }));
+                }
+            }
+
+            ready(Ok(deployment_name.to_owned()))
+        }
+    }
+
+    /// Joins the first deployment with free capacity, as a launcher would.
+    fn join_first_available<L: DeploymentLocator>(
+        locator: &L,
+        on_queue_status: QueueStatusCallback,
+    ) -> Result<L::Connection, String> {
+        let deployments = block_on(locator.get_deployment_list())?;
+        let deployment = deployments
+            .iter()
+            .find(|deployment| deployment.users_connected < deployment.users_capacity)
+            .ok_or_else(|| "No deployment has free capacity".to_owned())?;
+
+        let params = ConnectionParameters::new("Launcher");
+        block_on(locator.connect_and_queue(&deployment.deployment_name, params, on_queue_status))
+            .map_err(|e| e.detail)
+    }
+
+    fn deployment(name: &str, users_connected: u32) -> Deployment {
+        Deployment {
+            deployment_name: name.to_owned(),
+            assembly_name: "assembly".to_owned(),
+            description: String::new(),
+            users_connected,
+            users_capacity: 10,
+        }
+    }
+
+    #[test]
+    fn stub_locator_lists_deployments_and_reports_queue_status() {
+        let locator = StubLocator {
+            deployments: vec![deployment("full", 10), deployment("open", 3)],
+            queue_positions: vec![2, 1, 0],
+        };
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let connection = join_first_available(
+            &locator,
+            Box::new(move |status| {
+                tx.send(status).unwrap();
+                true
+            }),
+        );
+
+        assert_eq!(Ok("open".to_owned()), connection);
+        assert_eq!(vec![Ok(2), Ok(1), Ok(0)], rx.try_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn stub_locator_cancels_queueing_when_callback_returns_false() {
+        let locator = StubLocator {
+            deployments: vec![deployment("open", 0)],
+            queue_positions: vec![5, 4],
+        };
+
+        let connection = join_first_available(&locator, Box::new(|_| false));
+
+        assert_eq!(Err("Queueing was cancelled".to_owned()), connection);
+    }
+
+    #[test]
+    fn deployment_list_callback_converts_deployments() {
+        let names = [
+            CString::new("dep_a").unwrap(),
+            CString::new("dep_b").unwrap(),
+        ];
+        let assembly = CString::new("assembly").unwrap();
+        let description = CString::new("description").unwrap();
+        let mut deployments = names
+            .iter()
+            .enumerate()
+            .map(|(index, name)| Worker_Deployment {
+                deployment_name: name.as_ptr(),
+                assembly_name: assembly.as_ptr(),
+                description: description.as_ptr(),
+                users_connected: index as u32,
+                users_capacity: 10,
+            })
+            .collect::<Vec<_>>();
+        let list = Worker_DeploymentList {
+            deployment_count: deployments.len() as u32,
+            deployments: deployments.as_mut_ptr(),
+            error: ptr::null(),
+        };
+
+        let mut data: Result<Vec<Deployment>, String> = Err("Callback never called.".into());
+        DeploymentListFuture::callback_handler(&mut data as *mut _ as *mut c_void, &list);
+
+        let deployments = data.expect("Expected a successful deployment list");
+        assert_eq!(2, deployments.len());
+        assert_eq!("dep_b", deployments[1].deployment_name);
+        assert_eq!("assembly", deployments[1].assembly_name);
+        assert_eq!(1, deployments[1].users_connected);
+        assert_eq!(10, deployments[1].users_capacity);
+    }
+
+    #[test]
+    fn deployment_list_callback_reports_errors() {
+        let error = CString::new("project not found").unwrap();
+        let list = Worker_DeploymentList {
+            deployment_count: 0,
+            deployments: ptr::null_mut(),
+            error: error.as_ptr(),
+        };
+
+        let mut data: Result<Vec<Deployment>, String> = Ok(Vec::new());
+        DeploymentListFuture::callback_handler(&mut data as *mut _ as *mut c_void, &list);
+
+        assert_eq!(Err("project not found".to_owned()), data);
+    }
+
+    #[test]
+    fn queue_status_handler_forwards_status_to_callback() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut callback: QueueStatusCallback = Box::new(move |status| {
+            let keep_queueing = status.is_ok();
+            tx.send(status).unwrap();
+            keep_queueing
+        });
+        let user_data = &mut callback as *mut _ as *mut c_void;
+
+        let status = Worker_QueueStatus {
+            position_in_queue: 3,
+            error: ptr::null(),
+        };
+        assert_eq!(1, queue_status_handler(user_data, &status));
+
+        let error = CString::new("queue closed").unwrap();
+        let status = Worker_QueueStatus {
+            position_in_queue: 0,
+            error: error.as_ptr(),
+        };
+        assert_eq!(0, queue_status_handler(user_data, &status));
+
+        let updates = rx.try_iter().collect::<Vec<_>>();
+        assert_eq!(vec![Ok(3), Err("queue closed".to_owned())], updates);
+    }
+}
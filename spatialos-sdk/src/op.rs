@@ -1,12 +1,13 @@
 #![allow(non_upper_case_globals)]
 
 use crate::{
-    commands::{CommandRequestRef, CommandResponseRef, Commands},
+    commands::{CommandIndex, CommandRequestRef, CommandResponseRef, Commands},
     component::{self, *},
+    connection::ConnectionStatusErrorCode,
     entity::Entity,
     logging::LogLevel,
     metrics::Metrics,
-    schema,
+    schema::{self, ObjectFieldRef},
     utils::{cstr_array_to_vec_string, cstr_to_string},
     {Authority, EntityId, RequestId},
 };
@@ -14,17 +15,42 @@ use spatialos_sdk_sys::worker::*;
 use std::{
     collections::HashMap,
     fmt::{Display, Formatter},
+    iter::FromIterator,
     slice,
 };
 
+mod critical_section;
+mod owned;
+mod recording;
+
+pub use self::{critical_section::*, owned::*, recording::*};
+
+/// A list of ops, either received from the Worker SDK or constructed in Rust.
+///
+/// Op lists are usually obtained from [`Connection::get_op_list`], but can also be
+/// constructed from [`OwnedWorkerOp`]s, either directly or with an [`OpListBuilder`].
+/// This allows code written against [`WorkerOp`] to be tested, or to be fed recorded
+/// ops, without connecting to SpatialOS.
+///
+/// [`Connection::get_op_list`]: ../connection/trait.Connection.html#tymethod.get_op_list
+/// [`OwnedWorkerOp`]: enum.OwnedWorkerOp.html
+/// [`OpListBuilder`]: struct.OpListBuilder.html
+/// [`WorkerOp`]: enum.WorkerOp.html
 pub struct OpList {
-    raw: *mut Worker_OpList,
+    inner: OpListInner,
+}
+
+enum OpListInner {
+    Raw(*mut Worker_OpList),
+    Owned(Vec<OwnedWorkerOp>),
 }
 
 impl OpList {
     pub(crate) fn new(raw: *mut Worker_OpList) -> Self {
         assert!(!raw.is_null());
-        OpList { raw }
+        OpList {
+            inner: OpListInner::Raw(raw),
+        }
     }
 
     /// Returns an iterator over the list.
@@ -43,16 +69,37 @@ impl OpList {
     }
 
     /// Returns the number of ops in the list.
-    fn len(&self) -> usize {
-        self.raw().op_count as usize
+    pub fn len(&self) -> usize {
+        match &self.inner {
+            OpListInner::Raw(raw) => unsafe { (**raw).op_count as usize },
+            OpListInner::Owned(ops) => ops.len(),
+        }
+    }
+
+    /// Returns `true` if the list contains no ops.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
     }
 
-    /// Returns a reference to the raw `Worker_OpList`.
+    /// Converts every op in the list into an [`OwnedWorkerOp`].
     ///
-    /// This is a simple helper to reduce the `unsafe` boilerplate in all the
-    /// places where we need to access data on the raw op list.
-    fn raw(&self) -> &Worker_OpList {
-        unsafe { &*self.raw }
+    /// [`OwnedWorkerOp`]: enum.OwnedWorkerOp.html
+    pub fn to_owned_ops(&self) -> Vec<OwnedWorkerOp> {
+        self.iter().map(OwnedWorkerOp::from).collect()
+    }
+}
+
+impl From<Vec<OwnedWorkerOp>> for OpList {
+    fn from(ops: Vec<OwnedWorkerOp>) -> Self {
+        OpList {
+            inner: OpListInner::Owned(ops),
+        }
+    }
+}
+
+impl FromIterator<OwnedWorkerOp> for OpList {
+    fn from_iter<I: IntoIterator<Item = OwnedWorkerOp>>(iter: I) -> Self {
+        OpList::from(iter.into_iter().collect::<Vec<_>>())
     }
 }
 
@@ -61,29 +108,47 @@ impl<'a> IntoIterator for &'a OpList {
     type IntoIter = Iter<'a>;
 
     fn into_iter(self) -> Self::IntoIter {
-        let slice = unsafe { slice::from_raw_parts(self.raw().ops, self.len()) };
-        Iter { iter: slice.iter() }
+        let inner = match &self.inner {
+            OpListInner::Raw(raw) => {
+                let raw = unsafe { &**raw };
+                let slice = unsafe { slice::from_raw_parts(raw.ops, raw.op_count as usize) };
+                IterInner::Raw(slice.iter())
+            }
+            OpListInner::Owned(ops) => IterInner::Owned(ops.iter()),
+        };
+
+        Iter { inner }
     }
 }
 
 impl Drop for OpList {
     fn drop(&mut self) {
-        assert!(!self.raw.is_null());
-        unsafe {
-            Worker_OpList_Destroy(self.raw);
+        if let OpListInner::Raw(raw) = self.inner {
+            assert!(!raw.is_null());
+            unsafe {
+                Worker_OpList_Destroy(raw);
+            }
         }
     }
 }
 
 pub struct Iter<'a> {
-    iter: slice::Iter<'a, Worker_Op>,
+    inner: IterInner<'a>,
+}
+
+enum IterInner<'a> {
+    Raw(slice::Iter<'a, Worker_Op>),
+    Owned(slice::Iter<'a, OwnedWorkerOp>),
 }
 
 impl<'a> Iterator for Iter<'a> {
     type Item = WorkerOp<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.iter.next().map(WorkerOp::from)
+        match &mut self.inner {
+            IterInner::Raw(iter) => iter.next().map(WorkerOp::from),
+            IterInner::Owned(iter) => iter.next().map(OwnedWorkerOp::as_worker_op),
+        }
     }
 }
 
@@ -176,6 +241,7 @@ impl<'a> From<&'a Worker_Op> for WorkerOp<'a> {
                 Worker_OpType_WORKER_OP_TYPE_DISCONNECT => {
                     let op = erased_op.disconnect;
                     let disconnect_op = DisconnectOp {
+                        code: ConnectionStatusErrorCode::from(i32::from(op.connection_status_code)),
                         reason: cstr_to_string(op.reason),
                     };
                     WorkerOp::Disconnect(disconnect_op)
@@ -294,6 +360,7 @@ impl<'a> From<&'a Worker_Op> for WorkerOp<'a> {
                         entity_id: EntityId::new(op.entity_id),
                         request_id: RequestId::new(op.request_id),
                         component_id: op.response.component_id,
+                        command_index: op.response.command_index,
                         response: result,
                     };
                     WorkerOp::CommandResponse(command_response_op)
@@ -401,44 +468,45 @@ impl<'a> From<&'a Worker_Op> for WorkerOp<'a> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct DisconnectOp {
+    pub code: ConnectionStatusErrorCode,
     pub reason: String,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct FlagUpdateOp {
     pub name: String,
     pub value: String,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LogMessageOp {
     pub message: String,
     pub log_level: LogLevel,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct MetricsOp {
     pub metrics: Metrics,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct CriticalSectionOp {
     pub in_critical_section: bool,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct AddEntityOp {
     pub entity_id: EntityId,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct RemoveEntityOp {
     pub entity_id: EntityId,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ReserveEntityIdsResponseOp {
     pub request_id: RequestId,
     pub status_code: Result<ReservedEntityIdRange, CommandResponseError>,
@@ -446,7 +514,7 @@ pub struct ReserveEntityIdsResponseOp {
 
 // TODO: When https://doc.rust-lang.org/std/iter/trait.Step.html is stabilized - replace this
 //       with std::ops::Range<EntityId> and implement Step for EntityId.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ReservedEntityIdRange {
     current: i64,
     consumed: u32,
@@ -479,26 +547,26 @@ impl Iterator for ReservedEntityIdRange {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct CreateEntityResponseOp {
     pub request_id: RequestId,
     pub response: Result<EntityId, CommandResponseError>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct DeleteEntityResponseOp {
     pub request_id: RequestId,
     pub entity_id: EntityId,
     pub response: Result<(), CommandResponseError>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum QueryResponse {
     Snapshot(HashMap<EntityId, Entity>),
     Result(u32),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct EntityQueryResponseOp {
     pub request_id: RequestId,
     pub response: Result<QueryResponse, CommandResponseError>,
@@ -518,15 +586,23 @@ impl<'a> AddComponentOp<'a> {
     {
         self.component_data.get::<C>()
     }
+
+    /// Reads the component data as a borrowed view, without copying it out of the op.
+    pub fn get_ref<C>(&self) -> Option<schema::Result<<C as ObjectFieldRef<'a>>::Ref>>
+    where
+        C: Component + ObjectFieldRef<'a>,
+    {
+        self.component_data.get_ref::<C>()
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct RemoveComponentOp {
     pub entity_id: EntityId,
     pub component_id: ComponentId,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct AuthorityChangeOp {
     pub entity_id: EntityId,
     pub component_id: ComponentId,
@@ -547,6 +623,15 @@ impl<'a> ComponentUpdateOp<'a> {
     {
         self.component_update.get::<C>()
     }
+
+    /// Reads the update as a borrowed view, without copying it out of the op.
+    pub fn get_ref<C>(&self) -> Option<schema::Result<<C::Update as UpdateRef<'a>>::Ref>>
+    where
+        C: Component,
+        C::Update: UpdateRef<'a>,
+    {
+        self.component_update.get_ref::<C>()
+    }
 }
 
 #[derive(Debug)]
@@ -571,6 +656,7 @@ pub struct CommandResponseOp<'a> {
     pub request_id: RequestId,
     pub entity_id: EntityId,
     pub component_id: ComponentId,
+    pub command_index: CommandIndex,
     pub response: Result<CommandResponseRef<'a>, CommandResponseError>,
 }
 
@@ -0,0 +1,155 @@
+use crate::op::*;
+
+/// Holds back ops received inside a critical section until the critical section ends.
+///
+/// SpatialOS uses critical sections to group ops that must be observed together, such
+/// as the `AddEntity` op for an entity and the `AddComponent` ops for its initial
+/// components. Ops in a critical section may be split across several op lists, so a
+/// worker that processes each op list as it arrives can observe an entity that has
+/// only been partially added.
+///
+/// Passing every op list through a `CriticalSectionBuffer` ensures that each critical
+/// section is only released once it is complete, as a single op list that includes the
+/// `CriticalSection` ops themselves. Ops received outside of a critical section are
+/// released immediately, and the order of all ops is preserved.
+///
+/// # Examples
+///
+/// ```no_run
+/// use spatialos_sdk::{
+///     connection::{Connection, WorkerConnection},
+///     op::CriticalSectionBuffer,
+///     view::View,
+/// };
+///
+/// # let mut connection: WorkerConnection = unimplemented!();
+/// let mut buffer = CriticalSectionBuffer::new();
+/// let mut view = View::new();
+/// loop {
+///     let ops = buffer.process_op_list(&connection.get_op_list(0));
+///     view.process_op_list(&ops).expect("Failed to apply ops to the view");
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CriticalSectionBuffer {
+    pending: Vec<OwnedWorkerOp>,
+    in_critical_section: bool,
+}
+
+impl CriticalSectionBuffer {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns `true` if the last op received was inside a critical section.
+    pub fn in_critical_section(&self) -> bool {
+        self.in_critical_section
+    }
+
+    /// Returns the number of ops being held back until the current critical section
+    /// ends.
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Adds `ops` to the buffer, returning the ops that are ready to be processed.
+    ///
+    /// A `Disconnect` op releases any ops being held back, since the critical section
+    /// will never be completed.
+    pub fn process_op_list(&mut self, ops: &OpList) -> OpList {
+        let mut ready = Vec::new();
+        for op in ops {
+            self.process_op(OwnedWorkerOp::from(op), &mut ready);
+        }
+
+        OpList::from(ready)
+    }
+
+    fn process_op(&mut self, op: OwnedWorkerOp, ready: &mut Vec<OwnedWorkerOp>) {
+        match &op {
+            OwnedWorkerOp::CriticalSection(critical_section) => {
+                self.in_critical_section = critical_section.in_critical_section;
+            }
+            OwnedWorkerOp::Disconnect(_) => self.in_critical_section = false,
+            _ => {}
+        }
+
+        if self.pending.is_empty() && !self.in_critical_section {
+            ready.push(op);
+            return;
+        }
+
+        self.pending.push(op);
+        if !self.in_critical_section {
+            ready.append(&mut self.pending);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{connection::ConnectionStatusErrorCode, EntityId};
+
+    fn entity_ids(ops: &OpList) -> Vec<i64> {
+        ops.iter()
+            .filter_map(|op| match op {
+                WorkerOp::AddEntity(op) => Some(op.entity_id.id),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn ops_outside_critical_sections_are_released_immediately() {
+        let mut buffer = CriticalSectionBuffer::new();
+        let ops = OpListBuilder::new()
+            .add_entity(EntityId::new(1))
+            .add_entity(EntityId::new(2))
+            .build();
+
+        let ready = buffer.process_op_list(&ops);
+        assert_eq!(vec![1, 2], entity_ids(&ready));
+        assert_eq!(0, buffer.pending_count());
+    }
+
+    #[test]
+    fn critical_sections_are_released_once_complete() {
+        let mut buffer = CriticalSectionBuffer::new();
+
+        let ops = OpListBuilder::new()
+            .add_entity(EntityId::new(1))
+            .critical_section(true)
+            .add_entity(EntityId::new(2))
+            .build();
+        let ready = buffer.process_op_list(&ops);
+        assert_eq!(vec![1], entity_ids(&ready));
+        assert!(buffer.in_critical_section());
+        assert_eq!(2, buffer.pending_count());
+
+        let ops = OpListBuilder::new()
+            .add_entity(EntityId::new(3))
+            .critical_section(false)
+            .add_entity(EntityId::new(4))
+            .build();
+        let ready = buffer.process_op_list(&ops);
+        assert_eq!(vec![2, 3, 4], entity_ids(&ready));
+        assert_eq!(5, ready.len());
+        assert!(!buffer.in_critical_section());
+        assert_eq!(0, buffer.pending_count());
+    }
+
+    #[test]
+    fn disconnect_releases_pending_ops() {
+        let mut buffer = CriticalSectionBuffer::new();
+        let ops = OpListBuilder::new()
+            .critical_section(true)
+            .add_entity(EntityId::new(1))
+            .disconnect(ConnectionStatusErrorCode::NetworkError, "Connection lost")
+            .build();
+
+        let ready = buffer.process_op_list(&ops);
+        assert_eq!(3, ready.len());
+        assert_eq!(0, buffer.pending_count());
+    }
+}
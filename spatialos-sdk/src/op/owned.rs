@@ -0,0 +1,584 @@
+use crate::{
+    commands::{
+        CommandIndex, CommandRequest, CommandRequestRef, CommandResponse, CommandResponseRef,
+        Commands, Request, Response,
+    },
+    component::*,
+    connection::ConnectionStatusErrorCode,
+    op::*,
+    schema::{
+        self, Owned, SchemaCommandRequest, SchemaCommandResponse, SchemaComponentData,
+        SchemaComponentUpdate,
+    },
+    {Authority, EntityId, RequestId},
+};
+use std::mem;
+
+/// An owned version of [`WorkerOp`], which doesn't borrow from an [`OpList`].
+///
+/// Owned ops can be stored beyond the lifetime of the op list they came from, and can
+/// be constructed directly in order to build an [`OpList`] from Rust data.
+///
+/// [`WorkerOp`]: enum.WorkerOp.html
+/// [`OpList`]: struct.OpList.html
+#[derive(Debug, Clone)]
+pub enum OwnedWorkerOp {
+    Disconnect(DisconnectOp),
+    FlagUpdate(FlagUpdateOp),
+    LogMessage(LogMessageOp),
+    Metrics(MetricsOp),
+    CriticalSection(CriticalSectionOp),
+    AddEntity(AddEntityOp),
+    RemoveEntity(RemoveEntityOp),
+    AddComponent(OwnedAddComponentOp),
+    RemoveComponent(RemoveComponentOp),
+    ComponentUpdate(OwnedComponentUpdateOp),
+    AuthorityChange(AuthorityChangeOp),
+    CommandRequest(OwnedCommandRequestOp),
+    CommandResponse(OwnedCommandResponseOp),
+    ReserveEntityIdsResponse(ReserveEntityIdsResponseOp),
+    CreateEntityResponse(CreateEntityResponseOp),
+    DeleteEntityResponse(DeleteEntityResponseOp),
+    EntityQueryResponse(EntityQueryResponseOp),
+}
+
+impl OwnedWorkerOp {
+    /// Returns a [`WorkerOp`] that borrows from this op.
+    ///
+    /// [`WorkerOp`]: enum.WorkerOp.html
+    pub fn as_worker_op(&self) -> WorkerOp<'_> {
+        match self {
+            OwnedWorkerOp::Disconnect(op) => WorkerOp::Disconnect(op.clone()),
+            OwnedWorkerOp::FlagUpdate(op) => WorkerOp::FlagUpdate(op.clone()),
+            OwnedWorkerOp::LogMessage(op) => WorkerOp::LogMessage(op.clone()),
+            OwnedWorkerOp::Metrics(op) => WorkerOp::Metrics(op.clone()),
+            OwnedWorkerOp::CriticalSection(op) => WorkerOp::CriticalSection(op.clone()),
+            OwnedWorkerOp::AddEntity(op) => WorkerOp::AddEntity(op.clone()),
+            OwnedWorkerOp::RemoveEntity(op) => WorkerOp::RemoveEntity(op.clone()),
+            OwnedWorkerOp::AddComponent(op) => WorkerOp::AddComponent(AddComponentOp {
+                entity_id: op.entity_id,
+                component_id: op.component_id,
+                component_data: ComponentDataRef {
+                    component_id: op.component_id,
+                    schema_type: &op.component_data,
+                },
+            }),
+            OwnedWorkerOp::RemoveComponent(op) => WorkerOp::RemoveComponent(op.clone()),
+            OwnedWorkerOp::ComponentUpdate(op) => WorkerOp::ComponentUpdate(ComponentUpdateOp {
+                entity_id: op.entity_id,
+                component_id: op.component_id,
+                component_update: ComponentUpdateRef {
+                    component_id: op.component_id,
+                    schema_type: &op.component_update,
+                },
+            }),
+            OwnedWorkerOp::AuthorityChange(op) => WorkerOp::AuthorityChange(op.clone()),
+            OwnedWorkerOp::CommandRequest(op) => WorkerOp::CommandRequest(CommandRequestOp {
+                request_id: op.request_id,
+                entity_id: op.entity_id,
+                timeout_millis: op.timeout_millis,
+                caller_worker_id: op.caller_worker_id.clone(),
+                caller_attribute_set: op.caller_attribute_set.clone(),
+                component_id: op.component_id,
+                request: CommandRequestRef {
+                    component_id: op.component_id,
+                    command_index: op.command_index,
+                    schema_type: &op.request,
+                },
+            }),
+            OwnedWorkerOp::CommandResponse(op) => WorkerOp::CommandResponse(CommandResponseOp {
+                request_id: op.request_id,
+                entity_id: op.entity_id,
+                component_id: op.component_id,
+                command_index: op.command_index,
+                response: match &op.response {
+                    Ok(response) => Ok(CommandResponseRef {
+                        component_id: op.component_id,
+                        command_index: op.command_index,
+                        schema_type: response,
+                    }),
+                    Err(error) => Err(error.clone()),
+                },
+            }),
+            OwnedWorkerOp::ReserveEntityIdsResponse(op) => {
+                WorkerOp::ReserveEntityIdsResponse(op.clone())
+            }
+            OwnedWorkerOp::CreateEntityResponse(op) => WorkerOp::CreateEntityResponse(op.clone()),
+            OwnedWorkerOp::DeleteEntityResponse(op) => WorkerOp::DeleteEntityResponse(op.clone()),
+            OwnedWorkerOp::EntityQueryResponse(op) => WorkerOp::EntityQueryResponse(op.clone()),
+        }
+    }
+}
+
+impl<'a> From<WorkerOp<'a>> for OwnedWorkerOp {
+    fn from(op: WorkerOp<'a>) -> Self {
+        match op {
+            WorkerOp::Disconnect(op) => OwnedWorkerOp::Disconnect(op),
+            WorkerOp::FlagUpdate(op) => OwnedWorkerOp::FlagUpdate(op),
+            WorkerOp::LogMessage(op) => OwnedWorkerOp::LogMessage(op),
+            WorkerOp::Metrics(op) => OwnedWorkerOp::Metrics(op),
+            WorkerOp::CriticalSection(op) => OwnedWorkerOp::CriticalSection(op),
+            WorkerOp::AddEntity(op) => OwnedWorkerOp::AddEntity(op),
+            WorkerOp::RemoveEntity(op) => OwnedWorkerOp::RemoveEntity(op),
+            WorkerOp::AddComponent(op) => OwnedWorkerOp::AddComponent(OwnedAddComponentOp {
+                entity_id: op.entity_id,
+                component_id: op.component_id,
+                component_data: op.component_data.schema_type.to_owned(),
+            }),
+            WorkerOp::RemoveComponent(op) => OwnedWorkerOp::RemoveComponent(op),
+            WorkerOp::ComponentUpdate(op) => {
+                OwnedWorkerOp::ComponentUpdate(OwnedComponentUpdateOp {
+                    entity_id: op.entity_id,
+                    component_id: op.component_id,
+                    component_update: op.component_update.schema_type.to_owned(),
+                })
+            }
+            WorkerOp::AuthorityChange(op) => OwnedWorkerOp::AuthorityChange(op),
+            WorkerOp::CommandRequest(op) => OwnedWorkerOp::CommandRequest(OwnedCommandRequestOp {
+                request_id: op.request_id,
+                entity_id: op.entity_id,
+                timeout_millis: op.timeout_millis,
+                caller_worker_id: op.caller_worker_id,
+                caller_attribute_set: op.caller_attribute_set,
+                component_id: op.component_id,
+                command_index: op.request.command_index,
+                request: op.request.schema_type.to_owned(),
+            }),
+            WorkerOp::CommandResponse(op) => {
+                OwnedWorkerOp::CommandResponse(OwnedCommandResponseOp {
+                    request_id: op.request_id,
+                    entity_id: op.entity_id,
+                    component_id: op.component_id,
+                    command_index: op.command_index,
+                    response: op.response.map(|response| response.schema_type.to_owned()),
+                })
+            }
+            WorkerOp::ReserveEntityIdsResponse(op) => OwnedWorkerOp::ReserveEntityIdsResponse(op),
+            WorkerOp::CreateEntityResponse(op) => OwnedWorkerOp::CreateEntityResponse(op),
+            WorkerOp::DeleteEntityResponse(op) => OwnedWorkerOp::DeleteEntityResponse(op),
+            WorkerOp::EntityQueryResponse(op) => OwnedWorkerOp::EntityQueryResponse(op),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OwnedAddComponentOp {
+    pub entity_id: EntityId,
+    pub component_id: ComponentId,
+    pub component_data: Owned<SchemaComponentData>,
+}
+
+impl OwnedAddComponentOp {
+    pub fn get<C>(&self) -> Option<schema::Result<C>>
+    where
+        C: Component,
+    {
+        if C::ID != self.component_id {
+            return None;
+        }
+
+        Some(self.component_data.deserialize())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OwnedComponentUpdateOp {
+    pub entity_id: EntityId,
+    pub component_id: ComponentId,
+    pub component_update: Owned<SchemaComponentUpdate>,
+}
+
+impl OwnedComponentUpdateOp {
+    pub fn get<C>(&self) -> Option<schema::Result<C::Update>>
+    where
+        C: Component,
+    {
+        if C::ID != self.component_id {
+            return None;
+        }
+
+        Some(self.component_update.deserialize())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OwnedCommandRequestOp {
+    pub request_id: RequestId,
+    pub entity_id: EntityId,
+    pub timeout_millis: u32,
+    pub caller_worker_id: String,
+    pub caller_attribute_set: Vec<String>,
+    pub component_id: ComponentId,
+    pub command_index: CommandIndex,
+    pub request: Owned<SchemaCommandRequest>,
+}
+
+impl OwnedCommandRequestOp {
+    pub fn get<C: Commands>(&self) -> Option<schema::Result<C::Request>> {
+        if C::Component::ID != self.component_id {
+            return None;
+        }
+
+        Some(C::Request::from_schema(self.command_index, &self.request))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OwnedCommandResponseOp {
+    pub request_id: RequestId,
+    pub entity_id: EntityId,
+    pub component_id: ComponentId,
+    pub command_index: CommandIndex,
+    pub response: Result<Owned<SchemaCommandResponse>, CommandResponseError>,
+}
+
+impl OwnedCommandResponseOp {
+    /// Deserializes the response, if it belongs to the commands of component `C`.
+    ///
+    /// Returns `None` if the command failed, or if it belongs to a different component.
+    pub fn get<C: Commands>(&self) -> Option<schema::Result<C::Response>> {
+        if C::Component::ID != self.component_id {
+            return None;
+        }
+
+        let response = self.response.as_ref().ok()?;
+        Some(C::Response::from_schema(self.command_index, response))
+    }
+}
+
+/// Builds an [`OpList`] from Rust data.
+///
+/// # Examples
+///
+/// ```
+/// use spatialos_sdk::{
+///     op::{OpListBuilder, WorkerOp},
+///     Authority, EntityId,
+/// };
+///
+/// let ops = OpListBuilder::new()
+///     .add_entity(EntityId::new(1))
+///     .authority_change(EntityId::new(1), 54, Authority::Authoritative)
+///     .build();
+///
+/// assert_eq!(2, ops.len());
+/// for op in &ops {
+///     if let WorkerOp::AuthorityChange(op) = op {
+///         assert!(op.authority.has_authority());
+///     }
+/// }
+/// ```
+///
+/// [`OpList`]: struct.OpList.html
+#[derive(Debug, Clone, Default)]
+pub struct OpListBuilder {
+    ops: Vec<OwnedWorkerOp>,
+}
+
+impl OpListBuilder {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the number of ops added to the builder so far.
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// Builds an `OpList` from the ops added so far, leaving the builder empty.
+    pub fn build(&mut self) -> OpList {
+        OpList::from(mem::replace(&mut self.ops, Vec::new()))
+    }
+
+    pub fn op(&mut self, op: OwnedWorkerOp) -> &mut Self {
+        self.ops.push(op);
+        self
+    }
+
+    pub fn disconnect<S: Into<String>>(
+        &mut self,
+        code: ConnectionStatusErrorCode,
+        reason: S,
+    ) -> &mut Self {
+        self.op(OwnedWorkerOp::Disconnect(DisconnectOp {
+            code,
+            reason: reason.into(),
+        }))
+    }
+
+    pub fn flag_update<S: Into<String>, T: Into<String>>(
+        &mut self,
+        name: S,
+        value: T,
+    ) -> &mut Self {
+        self.op(OwnedWorkerOp::FlagUpdate(FlagUpdateOp {
+            name: name.into(),
+            value: value.into(),
+        }))
+    }
+
+    pub fn critical_section(&mut self, in_critical_section: bool) -> &mut Self {
+        self.op(OwnedWorkerOp::CriticalSection(CriticalSectionOp {
+            in_critical_section,
+        }))
+    }
+
+    pub fn add_entity(&mut self, entity_id: EntityId) -> &mut Self {
+        self.op(OwnedWorkerOp::AddEntity(AddEntityOp { entity_id }))
+    }
+
+    pub fn remove_entity(&mut self, entity_id: EntityId) -> &mut Self {
+        self.op(OwnedWorkerOp::RemoveEntity(RemoveEntityOp { entity_id }))
+    }
+
+    pub fn add_component<C: Component>(&mut self, entity_id: EntityId, component: &C) -> &mut Self {
+        self.op(OwnedWorkerOp::AddComponent(OwnedAddComponentOp {
+            entity_id,
+            component_id: C::ID,
+            component_data: SchemaComponentData::from_component(component),
+        }))
+    }
+
+    pub fn remove_component(
+        &mut self,
+        entity_id: EntityId,
+        component_id: ComponentId,
+    ) -> &mut Self {
+        self.op(OwnedWorkerOp::RemoveComponent(RemoveComponentOp {
+            entity_id,
+            component_id,
+        }))
+    }
+
+    pub fn component_update<T: Into<ComponentUpdate>>(
+        &mut self,
+        entity_id: EntityId,
+        update: T,
+    ) -> &mut Self {
+        let update = update.into();
+        self.op(OwnedWorkerOp::ComponentUpdate(OwnedComponentUpdateOp {
+            entity_id,
+            component_id: update.component_id,
+            component_update: update.schema_data,
+        }))
+    }
+
+    pub fn authority_change(
+        &mut self,
+        entity_id: EntityId,
+        component_id: ComponentId,
+        authority: Authority,
+    ) -> &mut Self {
+        self.op(OwnedWorkerOp::AuthorityChange(AuthorityChangeOp {
+            entity_id,
+            component_id,
+            authority,
+        }))
+    }
+
+    /// Adds a command request from another worker.
+    pub fn command_request<T: Into<CommandRequest>>(
+        &mut self,
+        request_id: RequestId,
+        entity_id: EntityId,
+        caller_worker_id: &str,
+        request: T,
+    ) -> &mut Self {
+        let request = request.into();
+        self.op(OwnedWorkerOp::CommandRequest(OwnedCommandRequestOp {
+            request_id,
+            entity_id,
+            timeout_millis: 0,
+            caller_worker_id: caller_worker_id.to_owned(),
+            caller_attribute_set: Vec::new(),
+            component_id: request.component_id,
+            command_index: request.command_index,
+            request: request.schema_data,
+        }))
+    }
+
+    /// Adds a successful response to a command request sent by the worker.
+    pub fn command_response<T: Into<CommandResponse>>(
+        &mut self,
+        request_id: RequestId,
+        entity_id: EntityId,
+        response: T,
+    ) -> &mut Self {
+        let response = response.into();
+        self.op(OwnedWorkerOp::CommandResponse(OwnedCommandResponseOp {
+            request_id,
+            entity_id,
+            component_id: response.component_id,
+            command_index: response.command_index,
+            response: Ok(response.schema_data),
+        }))
+    }
+
+    /// Adds a failed response to a command request sent by the worker.
+    pub fn command_failure(
+        &mut self,
+        request_id: RequestId,
+        entity_id: EntityId,
+        component_id: ComponentId,
+        command_index: CommandIndex,
+        error: CommandResponseError,
+    ) -> &mut Self {
+        self.op(OwnedWorkerOp::CommandResponse(OwnedCommandResponseOp {
+            request_id,
+            entity_id,
+            component_id,
+            command_index,
+            response: Err(error),
+        }))
+    }
+
+    pub fn reserve_entity_ids_response(
+        &mut self,
+        request_id: RequestId,
+        response: Result<(EntityId, u32), CommandResponseError>,
+    ) -> &mut Self {
+        self.op(OwnedWorkerOp::ReserveEntityIdsResponse(
+            ReserveEntityIdsResponseOp {
+                request_id,
+                status_code: response
+                    .map(|(first, number)| ReservedEntityIdRange::new(first.id, number)),
+            },
+        ))
+    }
+
+    pub fn create_entity_response(
+        &mut self,
+        request_id: RequestId,
+        response: Result<EntityId, CommandResponseError>,
+    ) -> &mut Self {
+        self.op(OwnedWorkerOp::CreateEntityResponse(
+            CreateEntityResponseOp {
+                request_id,
+                response,
+            },
+        ))
+    }
+
+    pub fn delete_entity_response(
+        &mut self,
+        request_id: RequestId,
+        entity_id: EntityId,
+        response: Result<(), CommandResponseError>,
+    ) -> &mut Self {
+        self.op(OwnedWorkerOp::DeleteEntityResponse(
+            DeleteEntityResponseOp {
+                request_id,
+                entity_id,
+                response,
+            },
+        ))
+    }
+
+    pub fn entity_query_response(
+        &mut self,
+        request_id: RequestId,
+        response: Result<QueryResponse, CommandResponseError>,
+    ) -> &mut Self {
+        self.op(OwnedWorkerOp::EntityQueryResponse(EntityQueryResponseOp {
+            request_id,
+            response,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::schema::SchemaInt32;
+
+    #[test]
+    fn built_op_list_yields_ops_in_order() {
+        let ops = OpListBuilder::new()
+            .add_entity(EntityId::new(1))
+            .remove_component(EntityId::new(1), 100)
+            .critical_section(false)
+            .build();
+
+        assert_eq!(3, ops.len());
+
+        let ops = ops.iter().collect::<Vec<_>>();
+        match &ops[0] {
+            WorkerOp::AddEntity(op) => assert_eq!(EntityId::new(1), op.entity_id),
+            _ => panic!("Expected an add entity op"),
+        }
+
+        match &ops[1] {
+            WorkerOp::RemoveComponent(op) => assert_eq!(100, op.component_id),
+            _ => panic!("Expected a remove component op"),
+        }
+
+        match &ops[2] {
+            WorkerOp::CriticalSection(op) => assert!(!op.in_critical_section),
+            _ => panic!("Expected a critical section op"),
+        }
+    }
+
+    #[test]
+    fn build_leaves_builder_empty() {
+        let mut builder = OpListBuilder::new();
+        builder.add_entity(EntityId::new(1));
+
+        assert_eq!(1, builder.build().len());
+        assert!(builder.is_empty());
+        assert!(builder.build().is_empty());
+    }
+
+    #[test]
+    fn owned_op_round_trips_through_worker_op() {
+        let mut data = SchemaComponentData::new();
+        data.fields_mut().add::<SchemaInt32>(1, &42);
+
+        let op = OwnedWorkerOp::AddComponent(OwnedAddComponentOp {
+            entity_id: EntityId::new(3),
+            component_id: 1000,
+            component_data: data,
+        });
+
+        let copy = OwnedWorkerOp::from(op.as_worker_op());
+        match copy {
+            OwnedWorkerOp::AddComponent(copy) => {
+                assert_eq!(EntityId::new(3), copy.entity_id);
+                assert_eq!(1000, copy.component_id);
+                assert_eq!(
+                    42,
+                    copy.component_data.fields().get::<SchemaInt32>(1).unwrap()
+                );
+            }
+            _ => panic!("Expected an add component op"),
+        }
+    }
+
+    #[test]
+    fn failed_command_response_is_preserved() {
+        let ops = OpListBuilder::new()
+            .command_failure(
+                RequestId::new(9),
+                EntityId::new(2),
+                1000,
+                3,
+                CommandResponseError {
+                    code: CommandStatusCode::NotFound,
+                    detail: "No such entity".to_owned(),
+                },
+            )
+            .build();
+
+        match ops.iter().next() {
+            Some(WorkerOp::CommandResponse(op)) => {
+                assert_eq!(RequestId::new(9), op.request_id);
+                assert_eq!(3, op.command_index);
+                assert_eq!("No such entity", op.response.as_ref().unwrap_err().detail);
+            }
+            _ => panic!("Expected a command response op"),
+        }
+    }
+}
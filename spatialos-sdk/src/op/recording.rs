@@ -0,0 +1,845 @@
+//! Recording ops to a file and replaying them offline.
+//!
+//! A recording starts with a short header, followed by one frame per recorded op
+//! list, including empty op lists so that replaying preserves tick boundaries. Each
+//! frame is a little-endian `u32` length followed by a schema object serialized with
+//! [`SchemaObject::to_bytes`], in which every op is a nested object. Schema payloads
+//! (component data, updates and command requests and responses) are stored in their
+//! binary wire format, so replaying a recording reproduces exactly the data that the
+//! worker received.
+//!
+//! [`SchemaObject::to_bytes`]: ../schema/struct.SchemaObject.html#method.to_bytes
+
+use crate::{
+    connection::ConnectionStatusErrorCode,
+    entity::Entity,
+    logging::LogLevel,
+    metrics::{HistogramMetric, HistogramMetricBucket, Metrics},
+    op::*,
+    schema::{
+        self, BufferSerializable, FieldId, FloatOrd, Owned, SchemaBool, SchemaBytes,
+        SchemaCommandRequest, SchemaCommandResponse, SchemaComponentData, SchemaComponentUpdate,
+        SchemaDouble, SchemaEntityId, SchemaGenericData, SchemaInt32, SchemaInt64, SchemaObject,
+        SchemaString, SchemaUint32,
+    },
+    {EntityId, RequestId},
+};
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    error::Error,
+    fmt::{self, Display, Formatter},
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+const MAGIC: &[u8; 8] = b"SPOSOPS\x01";
+
+// The field of a frame that contains the ops.
+const OPS_FIELD_ID: FieldId = 1;
+
+// Fields of an op. Fields are shared between op kinds where they have the same meaning.
+const KIND_FIELD_ID: FieldId = 1;
+const ENTITY_ID_FIELD_ID: FieldId = 2;
+const COMPONENT_ID_FIELD_ID: FieldId = 3;
+const REQUEST_ID_FIELD_ID: FieldId = 4;
+const COMMAND_INDEX_FIELD_ID: FieldId = 5;
+const PAYLOAD_FIELD_ID: FieldId = 6;
+const ERROR_FIELD_ID: FieldId = 7;
+const NAME_FIELD_ID: FieldId = 8;
+const VALUE_FIELD_ID: FieldId = 9;
+const MESSAGE_FIELD_ID: FieldId = 10;
+const LOG_LEVEL_FIELD_ID: FieldId = 11;
+const AUTHORITY_FIELD_ID: FieldId = 12;
+const IN_CRITICAL_SECTION_FIELD_ID: FieldId = 13;
+const TIMEOUT_MILLIS_FIELD_ID: FieldId = 14;
+const ATTRIBUTES_FIELD_ID: FieldId = 15;
+const COUNT_FIELD_ID: FieldId = 16;
+const SNAPSHOT_FIELD_ID: FieldId = 17;
+const LOAD_FIELD_ID: FieldId = 18;
+const GAUGE_METRICS_FIELD_ID: FieldId = 19;
+const HISTOGRAM_METRICS_FIELD_ID: FieldId = 20;
+const STATUS_CODE_FIELD_ID: FieldId = 21;
+
+// Fields of the nested objects within an op.
+const ERROR_CODE_FIELD_ID: FieldId = 1;
+const ERROR_DETAIL_FIELD_ID: FieldId = 2;
+const SNAPSHOT_ENTITY_ID_FIELD_ID: FieldId = 1;
+const SNAPSHOT_ENTITY_FIELD_ID: FieldId = 2;
+const METRIC_KEY_FIELD_ID: FieldId = 1;
+const METRIC_VALUE_FIELD_ID: FieldId = 2;
+const METRIC_BUCKETS_FIELD_ID: FieldId = 3;
+const BUCKET_UPPER_BOUND_FIELD_ID: FieldId = 1;
+const BUCKET_SAMPLES_FIELD_ID: FieldId = 2;
+
+mod kind {
+    pub const DISCONNECT: u32 = 1;
+    pub const FLAG_UPDATE: u32 = 2;
+    pub const LOG_MESSAGE: u32 = 3;
+    pub const METRICS: u32 = 4;
+    pub const CRITICAL_SECTION: u32 = 5;
+    pub const ADD_ENTITY: u32 = 6;
+    pub const REMOVE_ENTITY: u32 = 7;
+    pub const ADD_COMPONENT: u32 = 8;
+    pub const REMOVE_COMPONENT: u32 = 9;
+    pub const COMPONENT_UPDATE: u32 = 10;
+    pub const AUTHORITY_CHANGE: u32 = 11;
+    pub const COMMAND_REQUEST: u32 = 12;
+    pub const COMMAND_RESPONSE: u32 = 13;
+    pub const RESERVE_ENTITY_IDS_RESPONSE: u32 = 14;
+    pub const CREATE_ENTITY_RESPONSE: u32 = 15;
+    pub const DELETE_ENTITY_RESPONSE: u32 = 16;
+    pub const ENTITY_QUERY_RESPONSE: u32 = 17;
+}
+
+#[derive(Debug)]
+pub enum RecordingError {
+    Io(io::Error),
+    InvalidHeader,
+    InvalidData(String),
+    Schema(schema::Error),
+
+    /// An op list serialized to more bytes than a frame can hold.
+    FrameTooLarge(usize),
+}
+
+impl Display for RecordingError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordingError::Io(error) => write!(f, "I/O error: {}", error),
+            RecordingError::InvalidHeader => f.write_str("Not an op recording"),
+            RecordingError::InvalidData(message) => write!(f, "Invalid recording: {}", message),
+            RecordingError::Schema(error) => write!(f, "Invalid schema data: {}", error),
+            RecordingError::FrameTooLarge(length) => write!(
+                f,
+                "Frame of {} bytes exceeds the maximum frame size of {} bytes",
+                length,
+                u32::max_value()
+            ),
+        }
+    }
+}
+
+impl Error for RecordingError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RecordingError::Io(error) => Some(error),
+            RecordingError::Schema(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for RecordingError {
+    fn from(error: io::Error) -> Self {
+        RecordingError::Io(error)
+    }
+}
+
+impl From<schema::Error> for RecordingError {
+    fn from(error: schema::Error) -> Self {
+        RecordingError::Schema(error)
+    }
+}
+
+/// Records op lists to a file, so that they can be replayed later with [`OpReplayer`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use spatialos_sdk::{
+///     connection::{Connection, WorkerConnection},
+///     op::OpRecorder,
+/// };
+///
+/// # let mut connection: WorkerConnection = unimplemented!();
+/// let mut recorder = OpRecorder::create("worker.ops").expect("Failed to create recording");
+/// loop {
+///     let ops = connection.get_op_list(0);
+///     recorder.record(&ops).expect("Failed to record ops");
+///
+///     // Process `ops`.
+/// }
+/// ```
+///
+/// [`OpReplayer`]: struct.OpReplayer.html
+#[derive(Debug)]
+pub struct OpRecorder<W: Write> {
+    writer: W,
+}
+
+impl OpRecorder<BufWriter<File>> {
+    /// Creates a new recording at `path`, replacing any existing file.
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, RecordingError> {
+        OpRecorder::new(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl<W: Write> OpRecorder<W> {
+    /// Starts a new recording, writing the recording header to `writer`.
+    pub fn new(mut writer: W) -> Result<Self, RecordingError> {
+        writer.write_all(MAGIC)?;
+        Ok(OpRecorder { writer })
+    }
+
+    /// Records every op in `ops` as a single frame.
+    ///
+    /// Empty op lists are recorded too, so that each replayed op list corresponds to
+    /// one call to `get_op_list` in the original run.
+    pub fn record(&mut self, ops: &OpList) -> Result<(), RecordingError> {
+        let mut frame = SchemaGenericData::new();
+        for op in ops {
+            write_op(&op, frame.object_mut().add_object(OPS_FIELD_ID))?;
+        }
+
+        let bytes = frame.to_bytes()?;
+        let length =
+            u32::try_from(bytes.len()).map_err(|_| RecordingError::FrameTooLarge(bytes.len()))?;
+        self.writer.write_all(&length.to_le_bytes())?;
+        self.writer.write_all(&bytes)?;
+
+        Ok(())
+    }
+
+    /// Flushes any buffered frames to the underlying writer.
+    pub fn flush(&mut self) -> Result<(), RecordingError> {
+        self.writer.flush().map_err(RecordingError::from)
+    }
+
+    /// Returns the underlying writer, without flushing it.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Replays a recording created with [`OpRecorder`].
+///
+/// Each recorded frame is replayed as an [`OpList`] with the same ops as the original,
+/// so a recording can be fed into a [`View`] (or any other code that processes op
+/// lists) in order to reproduce the state of a worker without connecting to
+/// SpatialOS. With the `testing` feature enabled, replayed ops can also be queued on a
+/// `MockConnection` with `pending_ops_mut().op(...)`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use spatialos_sdk::{op::OpReplayer, view::View};
+///
+/// let replayer = OpReplayer::open("worker.ops").expect("Failed to open recording");
+///
+/// let mut view = View::new();
+/// for ops in replayer {
+///     let ops = ops.expect("Failed to read ops");
+///     view.process_op_list(&ops).expect("Failed to apply ops");
+/// }
+/// ```
+///
+/// [`OpRecorder`]: struct.OpRecorder.html
+/// [`OpList`]: struct.OpList.html
+/// [`View`]: ../view/struct.View.html
+#[derive(Debug)]
+pub struct OpReplayer<R: Read> {
+    reader: R,
+}
+
+impl OpReplayer<BufReader<File>> {
+    /// Opens the recording at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, RecordingError> {
+        OpReplayer::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> OpReplayer<R> {
+    /// Starts replaying a recording, reading and validating the header from `reader`.
+    pub fn new(mut reader: R) -> Result<Self, RecordingError> {
+        let mut header = [0; 8];
+        match reader.read_exact(&mut header) {
+            Ok(()) if &header == MAGIC => Ok(OpReplayer { reader }),
+            Ok(()) => Err(RecordingError::InvalidHeader),
+            Err(ref error) if error.kind() == io::ErrorKind::UnexpectedEof => {
+                Err(RecordingError::InvalidHeader)
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Reads the next op list from the recording, or returns `None` at the end of the
+    /// recording.
+    pub fn read_op_list(&mut self) -> Result<Option<OpList>, RecordingError> {
+        let length = match self.read_frame_length()? {
+            Some(length) => length,
+            None => return Ok(None),
+        };
+
+        let mut bytes = vec![0; length];
+        self.reader.read_exact(&mut bytes)?;
+
+        let frame = Owned::<SchemaGenericData>::from_bytes(&bytes)?;
+        let frame = frame.object();
+        (0..frame.object_count(OPS_FIELD_ID))
+            .map(|index| read_op(frame.index_object(OPS_FIELD_ID, index)))
+            .collect::<Result<Vec<_>, _>>()
+            .map(|ops| Some(OpList::from(ops)))
+    }
+
+    // Reads the length prefix of the next frame, distinguishing between the end of the
+    // recording and a truncated length prefix.
+    fn read_frame_length(&mut self) -> Result<Option<usize>, RecordingError> {
+        let mut length = [0; 4];
+        let mut read = 0;
+        while read < length.len() {
+            match self.reader.read(&mut length[read..]) {
+                Ok(0) if read == 0 => return Ok(None),
+                Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+                Ok(count) => read += count,
+                Err(ref error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => return Err(error.into()),
+            }
+        }
+
+        Ok(Some(u32::from_le_bytes(length) as usize))
+    }
+}
+
+impl<R: Read> Iterator for OpReplayer<R> {
+    type Item = Result<OpList, RecordingError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_op_list().transpose()
+    }
+}
+
+fn write_op(op: &WorkerOp<'_>, object: &mut SchemaObject) -> Result<(), RecordingError> {
+    match op {
+        WorkerOp::Disconnect(op) => {
+            object.add::<SchemaUint32>(KIND_FIELD_ID, &kind::DISCONNECT);
+            object.add::<SchemaInt32>(STATUS_CODE_FIELD_ID, &connection_status_code(&op.code));
+            object.add::<SchemaString>(MESSAGE_FIELD_ID, &op.reason);
+        }
+        WorkerOp::FlagUpdate(op) => {
+            object.add::<SchemaUint32>(KIND_FIELD_ID, &kind::FLAG_UPDATE);
+            object.add::<SchemaString>(NAME_FIELD_ID, &op.name);
+            object.add::<SchemaString>(VALUE_FIELD_ID, &op.value);
+        }
+        WorkerOp::LogMessage(op) => {
+            object.add::<SchemaUint32>(KIND_FIELD_ID, &kind::LOG_MESSAGE);
+            object.add::<SchemaString>(MESSAGE_FIELD_ID, &op.message);
+            object.add::<SchemaUint32>(LOG_LEVEL_FIELD_ID, &(op.log_level as u32));
+        }
+        WorkerOp::Metrics(op) => {
+            object.add::<SchemaUint32>(KIND_FIELD_ID, &kind::METRICS);
+            write_metrics(&op.metrics, object);
+        }
+        WorkerOp::CriticalSection(op) => {
+            object.add::<SchemaUint32>(KIND_FIELD_ID, &kind::CRITICAL_SECTION);
+            object.add::<SchemaBool>(IN_CRITICAL_SECTION_FIELD_ID, &op.in_critical_section);
+        }
+        WorkerOp::AddEntity(op) => {
+            object.add::<SchemaUint32>(KIND_FIELD_ID, &kind::ADD_ENTITY);
+            object.add::<SchemaEntityId>(ENTITY_ID_FIELD_ID, &op.entity_id);
+        }
+        WorkerOp::RemoveEntity(op) => {
+            object.add::<SchemaUint32>(KIND_FIELD_ID, &kind::REMOVE_ENTITY);
+            object.add::<SchemaEntityId>(ENTITY_ID_FIELD_ID, &op.entity_id);
+        }
+        WorkerOp::AddComponent(op) => {
+            object.add::<SchemaUint32>(KIND_FIELD_ID, &kind::ADD_COMPONENT);
+            object.add::<SchemaEntityId>(ENTITY_ID_FIELD_ID, &op.entity_id);
+            object.add::<SchemaUint32>(COMPONENT_ID_FIELD_ID, &op.component_id);
+            object.add::<SchemaBytes>(PAYLOAD_FIELD_ID, &op.component_data.schema_type.to_bytes()?);
+        }
+        WorkerOp::RemoveComponent(op) => {
+            object.add::<SchemaUint32>(KIND_FIELD_ID, &kind::REMOVE_COMPONENT);
+            object.add::<SchemaEntityId>(ENTITY_ID_FIELD_ID, &op.entity_id);
+            object.add::<SchemaUint32>(COMPONENT_ID_FIELD_ID, &op.component_id);
+        }
+        WorkerOp::ComponentUpdate(op) => {
+            object.add::<SchemaUint32>(KIND_FIELD_ID, &kind::COMPONENT_UPDATE);
+            object.add::<SchemaEntityId>(ENTITY_ID_FIELD_ID, &op.entity_id);
+            object.add::<SchemaUint32>(COMPONENT_ID_FIELD_ID, &op.component_id);
+            object.add::<SchemaBytes>(
+                PAYLOAD_FIELD_ID,
+                &op.component_update.schema_type.to_bytes()?,
+            );
+        }
+        WorkerOp::AuthorityChange(op) => {
+            object.add::<SchemaUint32>(KIND_FIELD_ID, &kind::AUTHORITY_CHANGE);
+            object.add::<SchemaEntityId>(ENTITY_ID_FIELD_ID, &op.entity_id);
+            object.add::<SchemaUint32>(COMPONENT_ID_FIELD_ID, &op.component_id);
+            object.add::<SchemaUint32>(AUTHORITY_FIELD_ID, &u8::from(op.authority).into());
+        }
+        WorkerOp::CommandRequest(op) => {
+            object.add::<SchemaUint32>(KIND_FIELD_ID, &kind::COMMAND_REQUEST);
+            object.add::<SchemaInt64>(REQUEST_ID_FIELD_ID, &op.request_id.id);
+            object.add::<SchemaEntityId>(ENTITY_ID_FIELD_ID, &op.entity_id);
+            object.add::<SchemaUint32>(TIMEOUT_MILLIS_FIELD_ID, &op.timeout_millis);
+            object.add::<SchemaString>(NAME_FIELD_ID, &op.caller_worker_id);
+            object.add_list::<SchemaString>(ATTRIBUTES_FIELD_ID, &op.caller_attribute_set);
+            object.add::<SchemaUint32>(COMPONENT_ID_FIELD_ID, &op.component_id);
+            object.add::<SchemaUint32>(COMMAND_INDEX_FIELD_ID, &op.request.command_index);
+            object.add::<SchemaBytes>(PAYLOAD_FIELD_ID, &op.request.schema_type.to_bytes()?);
+        }
+        WorkerOp::CommandResponse(op) => {
+            object.add::<SchemaUint32>(KIND_FIELD_ID, &kind::COMMAND_RESPONSE);
+            object.add::<SchemaInt64>(REQUEST_ID_FIELD_ID, &op.request_id.id);
+            object.add::<SchemaEntityId>(ENTITY_ID_FIELD_ID, &op.entity_id);
+            object.add::<SchemaUint32>(COMPONENT_ID_FIELD_ID, &op.component_id);
+            object.add::<SchemaUint32>(COMMAND_INDEX_FIELD_ID, &op.command_index);
+            match &op.response {
+                Ok(response) => {
+                    object.add::<SchemaBytes>(PAYLOAD_FIELD_ID, &response.schema_type.to_bytes()?);
+                }
+                Err(error) => write_error(error, object),
+            }
+        }
+        WorkerOp::ReserveEntityIdsResponse(op) => {
+            object.add::<SchemaUint32>(KIND_FIELD_ID, &kind::RESERVE_ENTITY_IDS_RESPONSE);
+            object.add::<SchemaInt64>(REQUEST_ID_FIELD_ID, &op.request_id.id);
+            match &op.status_code {
+                Ok(range) => {
+                    object.add::<SchemaEntityId>(ENTITY_ID_FIELD_ID, &EntityId::new(range.current));
+                    object.add::<SchemaUint32>(COUNT_FIELD_ID, &(range.reserved - range.consumed));
+                }
+                Err(error) => write_error(error, object),
+            }
+        }
+        WorkerOp::CreateEntityResponse(op) => {
+            object.add::<SchemaUint32>(KIND_FIELD_ID, &kind::CREATE_ENTITY_RESPONSE);
+            object.add::<SchemaInt64>(REQUEST_ID_FIELD_ID, &op.request_id.id);
+            match &op.response {
+                Ok(entity_id) => object.add::<SchemaEntityId>(ENTITY_ID_FIELD_ID, entity_id),
+                Err(error) => write_error(error, object),
+            }
+        }
+        WorkerOp::DeleteEntityResponse(op) => {
+            object.add::<SchemaUint32>(KIND_FIELD_ID, &kind::DELETE_ENTITY_RESPONSE);
+            object.add::<SchemaInt64>(REQUEST_ID_FIELD_ID, &op.request_id.id);
+            object.add::<SchemaEntityId>(ENTITY_ID_FIELD_ID, &op.entity_id);
+            if let Err(error) = &op.response {
+                write_error(error, object);
+            }
+        }
+        WorkerOp::EntityQueryResponse(op) => {
+            object.add::<SchemaUint32>(KIND_FIELD_ID, &kind::ENTITY_QUERY_RESPONSE);
+            object.add::<SchemaInt64>(REQUEST_ID_FIELD_ID, &op.request_id.id);
+            match &op.response {
+                Ok(QueryResponse::Result(count)) => {
+                    object.add::<SchemaUint32>(COUNT_FIELD_ID, count);
+                }
+                Ok(QueryResponse::Snapshot(entities)) => {
+                    for (entity_id, entity) in entities {
+                        let field = object.add_object(SNAPSHOT_FIELD_ID);
+                        field.add::<SchemaEntityId>(SNAPSHOT_ENTITY_ID_FIELD_ID, entity_id);
+                        entity.to_schema_field(field.add_object(SNAPSHOT_ENTITY_FIELD_ID));
+                    }
+                }
+                Err(error) => write_error(error, object),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn read_op(object: &SchemaObject) -> Result<OwnedWorkerOp, RecordingError> {
+    let op = match object.get::<SchemaUint32>(KIND_FIELD_ID)? {
+        kind::DISCONNECT => OwnedWorkerOp::Disconnect(DisconnectOp {
+            code: ConnectionStatusErrorCode::from(object.get::<SchemaInt32>(STATUS_CODE_FIELD_ID)?),
+            reason: object.get::<SchemaString>(MESSAGE_FIELD_ID)?,
+        }),
+        kind::FLAG_UPDATE => OwnedWorkerOp::FlagUpdate(FlagUpdateOp {
+            name: object.get::<SchemaString>(NAME_FIELD_ID)?,
+            value: object.get::<SchemaString>(VALUE_FIELD_ID)?,
+        }),
+        kind::LOG_MESSAGE => OwnedWorkerOp::LogMessage(LogMessageOp {
+            message: object.get::<SchemaString>(MESSAGE_FIELD_ID)?,
+            log_level: LogLevel::from(object.get::<SchemaUint32>(LOG_LEVEL_FIELD_ID)? as u8),
+        }),
+        kind::METRICS => OwnedWorkerOp::Metrics(MetricsOp {
+            metrics: read_metrics(object)?,
+        }),
+        kind::CRITICAL_SECTION => OwnedWorkerOp::CriticalSection(CriticalSectionOp {
+            in_critical_section: object.get::<SchemaBool>(IN_CRITICAL_SECTION_FIELD_ID)?,
+        }),
+        kind::ADD_ENTITY => OwnedWorkerOp::AddEntity(AddEntityOp {
+            entity_id: object.get::<SchemaEntityId>(ENTITY_ID_FIELD_ID)?,
+        }),
+        kind::REMOVE_ENTITY => OwnedWorkerOp::RemoveEntity(RemoveEntityOp {
+            entity_id: object.get::<SchemaEntityId>(ENTITY_ID_FIELD_ID)?,
+        }),
+        kind::ADD_COMPONENT => OwnedWorkerOp::AddComponent(OwnedAddComponentOp {
+            entity_id: object.get::<SchemaEntityId>(ENTITY_ID_FIELD_ID)?,
+            component_id: object.get::<SchemaUint32>(COMPONENT_ID_FIELD_ID)?,
+            component_data: read_payload::<SchemaComponentData>(object)?,
+        }),
+        kind::REMOVE_COMPONENT => OwnedWorkerOp::RemoveComponent(RemoveComponentOp {
+            entity_id: object.get::<SchemaEntityId>(ENTITY_ID_FIELD_ID)?,
+            component_id: object.get::<SchemaUint32>(COMPONENT_ID_FIELD_ID)?,
+        }),
+        kind::COMPONENT_UPDATE => OwnedWorkerOp::ComponentUpdate(OwnedComponentUpdateOp {
+            entity_id: object.get::<SchemaEntityId>(ENTITY_ID_FIELD_ID)?,
+            component_id: object.get::<SchemaUint32>(COMPONENT_ID_FIELD_ID)?,
+            component_update: read_payload::<SchemaComponentUpdate>(object)?,
+        }),
+        kind::AUTHORITY_CHANGE => OwnedWorkerOp::AuthorityChange(AuthorityChangeOp {
+            entity_id: object.get::<SchemaEntityId>(ENTITY_ID_FIELD_ID)?,
+            component_id: object.get::<SchemaUint32>(COMPONENT_ID_FIELD_ID)?,
+            authority: (object.get::<SchemaUint32>(AUTHORITY_FIELD_ID)? as u8).into(),
+        }),
+        kind::COMMAND_REQUEST => OwnedWorkerOp::CommandRequest(OwnedCommandRequestOp {
+            request_id: RequestId::new(object.get::<SchemaInt64>(REQUEST_ID_FIELD_ID)?),
+            entity_id: object.get::<SchemaEntityId>(ENTITY_ID_FIELD_ID)?,
+            timeout_millis: object.get::<SchemaUint32>(TIMEOUT_MILLIS_FIELD_ID)?,
+            caller_worker_id: object.get::<SchemaString>(NAME_FIELD_ID)?,
+            caller_attribute_set: object.get_list::<SchemaString>(ATTRIBUTES_FIELD_ID)?,
+            component_id: object.get::<SchemaUint32>(COMPONENT_ID_FIELD_ID)?,
+            command_index: object.get::<SchemaUint32>(COMMAND_INDEX_FIELD_ID)?,
+            request: read_payload::<SchemaCommandRequest>(object)?,
+        }),
+        kind::COMMAND_RESPONSE => {
+            let response = match read_error(object)? {
+                Some(error) => Err(error),
+                None => Ok(read_payload::<SchemaCommandResponse>(object)?),
+            };
+
+            OwnedWorkerOp::CommandResponse(OwnedCommandResponseOp {
+                request_id: RequestId::new(object.get::<SchemaInt64>(REQUEST_ID_FIELD_ID)?),
+                entity_id: object.get::<SchemaEntityId>(ENTITY_ID_FIELD_ID)?,
+                component_id: object.get::<SchemaUint32>(COMPONENT_ID_FIELD_ID)?,
+                command_index: object.get::<SchemaUint32>(COMMAND_INDEX_FIELD_ID)?,
+                response,
+            })
+        }
+        kind::RESERVE_ENTITY_IDS_RESPONSE => {
+            OwnedWorkerOp::ReserveEntityIdsResponse(ReserveEntityIdsResponseOp {
+                request_id: RequestId::new(object.get::<SchemaInt64>(REQUEST_ID_FIELD_ID)?),
+                status_code: match read_error(object)? {
+                    Some(error) => Err(error),
+                    None => Ok(ReservedEntityIdRange::new(
+                        object.get::<SchemaEntityId>(ENTITY_ID_FIELD_ID)?.id,
+                        object.get::<SchemaUint32>(COUNT_FIELD_ID)?,
+                    )),
+                },
+            })
+        }
+        kind::CREATE_ENTITY_RESPONSE => {
+            OwnedWorkerOp::CreateEntityResponse(CreateEntityResponseOp {
+                request_id: RequestId::new(object.get::<SchemaInt64>(REQUEST_ID_FIELD_ID)?),
+                response: match read_error(object)? {
+                    Some(error) => Err(error),
+                    None => Ok(object.get::<SchemaEntityId>(ENTITY_ID_FIELD_ID)?),
+                },
+            })
+        }
+        kind::DELETE_ENTITY_RESPONSE => {
+            OwnedWorkerOp::DeleteEntityResponse(DeleteEntityResponseOp {
+                request_id: RequestId::new(object.get::<SchemaInt64>(REQUEST_ID_FIELD_ID)?),
+                entity_id: object.get::<SchemaEntityId>(ENTITY_ID_FIELD_ID)?,
+                response: match read_error(object)? {
+                    Some(error) => Err(error),
+                    None => Ok(()),
+                },
+            })
+        }
+        kind::ENTITY_QUERY_RESPONSE => OwnedWorkerOp::EntityQueryResponse(EntityQueryResponseOp {
+            request_id: RequestId::new(object.get::<SchemaInt64>(REQUEST_ID_FIELD_ID)?),
+            response: match read_error(object)? {
+                Some(error) => Err(error),
+                None if object.count::<SchemaUint32>(COUNT_FIELD_ID) > 0 => Ok(
+                    QueryResponse::Result(object.get::<SchemaUint32>(COUNT_FIELD_ID)?),
+                ),
+                None => Ok(QueryResponse::Snapshot(read_snapshot(object)?)),
+            },
+        }),
+        unknown => {
+            return Err(RecordingError::InvalidData(format!(
+                "Unknown op kind: {}",
+                unknown
+            )))
+        }
+    };
+
+    Ok(op)
+}
+
+fn read_payload<T: BufferSerializable>(object: &SchemaObject) -> Result<Owned<T>, RecordingError> {
+    let bytes = object.get::<SchemaBytes>(PAYLOAD_FIELD_ID)?;
+    Owned::<T>::from_bytes(&bytes).map_err(RecordingError::from)
+}
+
+fn write_error(error: &CommandResponseError, object: &mut SchemaObject) {
+    let code = match error.code {
+        CommandStatusCode::Timeout => Worker_StatusCode_WORKER_STATUS_CODE_TIMEOUT,
+        CommandStatusCode::NotFound => Worker_StatusCode_WORKER_STATUS_CODE_NOT_FOUND,
+        CommandStatusCode::AuthorityLost => Worker_StatusCode_WORKER_STATUS_CODE_AUTHORITY_LOST,
+        CommandStatusCode::PermissionDenied => {
+            Worker_StatusCode_WORKER_STATUS_CODE_PERMISSION_DENIED
+        }
+        CommandStatusCode::ApplicationError => {
+            Worker_StatusCode_WORKER_STATUS_CODE_APPLICATION_ERROR
+        }
+        CommandStatusCode::InternalError => Worker_StatusCode_WORKER_STATUS_CODE_INTERNAL_ERROR,
+        CommandStatusCode::Unknown => 0,
+    };
+
+    let field = object.add_object(ERROR_FIELD_ID);
+    field.add::<SchemaInt32>(ERROR_CODE_FIELD_ID, &code);
+    field.add::<SchemaString>(ERROR_DETAIL_FIELD_ID, &error.detail);
+}
+
+fn connection_status_code(code: &ConnectionStatusErrorCode) -> i32 {
+    match code {
+        ConnectionStatusErrorCode::InternalError => {
+            Worker_ConnectionStatusCode_WORKER_CONNECTION_STATUS_CODE_INTERNAL_ERROR
+        }
+        ConnectionStatusErrorCode::InvalidArgument => {
+            Worker_ConnectionStatusCode_WORKER_CONNECTION_STATUS_CODE_INVALID_ARGUMENT
+        }
+        ConnectionStatusErrorCode::NetworkError => {
+            Worker_ConnectionStatusCode_WORKER_CONNECTION_STATUS_CODE_NETWORK_ERROR
+        }
+        ConnectionStatusErrorCode::Timeout => {
+            Worker_ConnectionStatusCode_WORKER_CONNECTION_STATUS_CODE_TIMEOUT
+        }
+        ConnectionStatusErrorCode::Cancelled => {
+            Worker_ConnectionStatusCode_WORKER_CONNECTION_STATUS_CODE_CANCELLED
+        }
+        ConnectionStatusErrorCode::Rejected => {
+            Worker_ConnectionStatusCode_WORKER_CONNECTION_STATUS_CODE_REJECTED
+        }
+        ConnectionStatusErrorCode::PlayerIdentityTokenExpired => {
+            Worker_ConnectionStatusCode_WORKER_CONNECTION_STATUS_CODE_PLAYER_IDENTITY_TOKEN_EXPIRED
+        }
+        ConnectionStatusErrorCode::LoginTokenExpired => {
+            Worker_ConnectionStatusCode_WORKER_CONNECTION_STATUS_CODE_LOGIN_TOKEN_EXPIRED
+        }
+        ConnectionStatusErrorCode::CapacityExceeded => {
+            Worker_ConnectionStatusCode_WORKER_CONNECTION_STATUS_CODE_CAPACITY_EXCEEDED
+        }
+        ConnectionStatusErrorCode::RateExceeded => {
+            Worker_ConnectionStatusCode_WORKER_CONNECTION_STATUS_CODE_RATE_EXCEEDED
+        }
+        ConnectionStatusErrorCode::ServerShutdown => {
+            Worker_ConnectionStatusCode_WORKER_CONNECTION_STATUS_CODE_SERVER_SHUTDOWN
+        }
+        ConnectionStatusErrorCode::Unknown => 0,
+    }
+}
+
+fn read_error(object: &SchemaObject) -> Result<Option<CommandResponseError>, RecordingError> {
+    if object.object_count(ERROR_FIELD_ID) == 0 {
+        return Ok(None);
+    }
+
+    let field = object.get_object(ERROR_FIELD_ID);
+    Ok(Some(CommandResponseError {
+        code: CommandStatusCode::from(field.get::<SchemaInt32>(ERROR_CODE_FIELD_ID)?),
+        detail: field.get::<SchemaString>(ERROR_DETAIL_FIELD_ID)?,
+    }))
+}
+
+fn read_snapshot(object: &SchemaObject) -> Result<HashMap<EntityId, Entity>, RecordingError> {
+    (0..object.object_count(SNAPSHOT_FIELD_ID))
+        .map(|index| {
+            let field = object.index_object(SNAPSHOT_FIELD_ID, index);
+            let entity_id = field.get::<SchemaEntityId>(SNAPSHOT_ENTITY_ID_FIELD_ID)?;
+            let entity = Entity::from_schema_field(field.get_object(SNAPSHOT_ENTITY_FIELD_ID))?;
+            Ok((entity_id, entity))
+        })
+        .collect()
+}
+
+fn write_metrics(metrics: &Metrics, object: &mut SchemaObject) {
+    if let Some(load) = metrics.load {
+        object.add::<SchemaDouble>(LOAD_FIELD_ID, &FloatOrd(load));
+    }
+
+    for (key, value) in &metrics.gauge_metrics {
+        let field = object.add_object(GAUGE_METRICS_FIELD_ID);
+        field.add::<SchemaString>(METRIC_KEY_FIELD_ID, key);
+        field.add::<SchemaDouble>(METRIC_VALUE_FIELD_ID, &FloatOrd(*value));
+    }
+
+    for (key, histogram) in &metrics.histogram_metrics {
+        let field = object.add_object(HISTOGRAM_METRICS_FIELD_ID);
+        field.add::<SchemaString>(METRIC_KEY_FIELD_ID, key);
+        field.add::<SchemaDouble>(METRIC_VALUE_FIELD_ID, &FloatOrd(histogram.sum));
+
+        for bucket in &histogram.buckets {
+            let bucket_field = field.add_object(METRIC_BUCKETS_FIELD_ID);
+            bucket_field
+                .add::<SchemaDouble>(BUCKET_UPPER_BOUND_FIELD_ID, &FloatOrd(bucket.upper_bound));
+            bucket_field.add::<SchemaUint32>(BUCKET_SAMPLES_FIELD_ID, &bucket.samples);
+        }
+    }
+}
+
+fn read_metrics(object: &SchemaObject) -> Result<Metrics, RecordingError> {
+    let mut metrics = Metrics::new();
+
+    if object.count::<SchemaDouble>(LOAD_FIELD_ID) > 0 {
+        metrics.load = Some(object.get::<SchemaDouble>(LOAD_FIELD_ID)?.0);
+    }
+
+    for index in 0..object.object_count(GAUGE_METRICS_FIELD_ID) {
+        let field = object.index_object(GAUGE_METRICS_FIELD_ID, index);
+        metrics.gauge_metrics.insert(
+            field.get::<SchemaString>(METRIC_KEY_FIELD_ID)?,
+            field.get::<SchemaDouble>(METRIC_VALUE_FIELD_ID)?.0,
+        );
+    }
+
+    for index in 0..object.object_count(HISTOGRAM_METRICS_FIELD_ID) {
+        let field = object.index_object(HISTOGRAM_METRICS_FIELD_ID, index);
+        let buckets = (0..field.object_count(METRIC_BUCKETS_FIELD_ID))
+            .map(|index| {
+                let bucket_field = field.index_object(METRIC_BUCKETS_FIELD_ID, index);
+                Ok(HistogramMetricBucket {
+                    upper_bound: bucket_field
+                        .get::<SchemaDouble>(BUCKET_UPPER_BOUND_FIELD_ID)?
+                        .0,
+                    samples: bucket_field.get::<SchemaUint32>(BUCKET_SAMPLES_FIELD_ID)?,
+                })
+            })
+            .collect::<schema::Result<Vec<_>>>()?;
+
+        metrics.histogram_metrics.insert(
+            field.get::<SchemaString>(METRIC_KEY_FIELD_ID)?,
+            HistogramMetric {
+                sum: field.get::<SchemaDouble>(METRIC_VALUE_FIELD_ID)?.0,
+                buckets,
+            },
+        );
+    }
+
+    Ok(metrics)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{view::View, Authority};
+    use std::io::Cursor;
+
+    fn round_trip(ops: &OpList) -> Vec<OpList> {
+        let mut recorder = OpRecorder::new(Vec::new()).unwrap();
+        recorder.record(ops).unwrap();
+        recorder.record(&OpList::from(Vec::new())).unwrap();
+
+        OpReplayer::new(Cursor::new(recorder.into_inner()))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn op_lists_round_trip_through_a_recording() {
+        let mut data = SchemaComponentData::new();
+        data.fields_mut().add::<SchemaInt32>(1, &5);
+        let mut update = SchemaComponentUpdate::new();
+        update.fields_mut().add::<SchemaInt32>(1, &7);
+
+        let ops = OpList::from(vec![
+            OwnedWorkerOp::FlagUpdate(FlagUpdateOp {
+                name: "flag".to_owned(),
+                value: "value".to_owned(),
+            }),
+            OwnedWorkerOp::AddEntity(AddEntityOp {
+                entity_id: EntityId::new(1),
+            }),
+            OwnedWorkerOp::AddComponent(OwnedAddComponentOp {
+                entity_id: EntityId::new(1),
+                component_id: 1000,
+                component_data: data,
+            }),
+            OwnedWorkerOp::ComponentUpdate(OwnedComponentUpdateOp {
+                entity_id: EntityId::new(1),
+                component_id: 1000,
+                component_update: update,
+            }),
+            OwnedWorkerOp::AuthorityChange(AuthorityChangeOp {
+                entity_id: EntityId::new(1),
+                component_id: 1000,
+                authority: Authority::Authoritative,
+            }),
+            OwnedWorkerOp::CommandResponse(OwnedCommandResponseOp {
+                request_id: RequestId::new(2),
+                entity_id: EntityId::new(1),
+                component_id: 1000,
+                command_index: 4,
+                response: Err(CommandResponseError {
+                    code: CommandStatusCode::Timeout,
+                    detail: "Timed out".to_owned(),
+                }),
+            }),
+            OwnedWorkerOp::CreateEntityResponse(CreateEntityResponseOp {
+                request_id: RequestId::new(3),
+                response: Err(CommandResponseError {
+                    code: CommandStatusCode::PermissionDenied,
+                    detail: "Denied".to_owned(),
+                }),
+            }),
+        ]);
+
+        let replayed = round_trip(&ops);
+        assert_eq!(2, replayed.len());
+        assert_eq!(ops.len(), replayed[0].len());
+        assert!(replayed[1].is_empty());
+
+        let mut view = View::new();
+        view.process_op_list(&replayed[0]).unwrap();
+        let entity = view.entity(EntityId::new(1)).expect("Entity wasn't added");
+        assert_eq!(
+            7,
+            entity
+                .get_data(1000)
+                .unwrap()
+                .fields()
+                .get::<SchemaInt32>(1)
+                .unwrap()
+        );
+        assert_eq!(Some(Authority::Authoritative), entity.authority_by_id(1000));
+
+        match replayed[0].iter().last() {
+            Some(WorkerOp::CreateEntityResponse(op)) => {
+                let error = op.response.as_ref().unwrap_err();
+                assert_eq!(RequestId::new(3), op.request_id);
+                assert_eq!("Denied", error.detail);
+            }
+            _ => panic!("Expected a create entity response"),
+        }
+
+        match replayed[0].iter().nth(ops.len() - 2) {
+            Some(WorkerOp::CommandResponse(op)) => {
+                assert_eq!(4, op.command_index);
+                assert!(op.response.is_err());
+            }
+            _ => panic!("Expected a command response"),
+        }
+    }
+
+    #[test]
+    fn replayer_rejects_other_files() {
+        match OpReplayer::new(Cursor::new(b"not a recording".to_vec())) {
+            Err(RecordingError::InvalidHeader) => {}
+            _ => panic!("Expected an invalid header error"),
+        }
+    }
+
+    #[test]
+    fn truncated_frames_are_reported() {
+        let mut recorder = OpRecorder::new(Vec::new()).unwrap();
+        recorder
+            .record(&OpList::from(vec![OwnedWorkerOp::AddEntity(AddEntityOp {
+                entity_id: EntityId::new(1),
+            })]))
+            .unwrap();
+
+        let mut bytes = recorder.into_inner();
+        bytes.pop();
+
+        let mut replayer = OpReplayer::new(Cursor::new(bytes)).unwrap();
+        assert!(replayer.read_op_list().is_err());
+    }
+}
@@ -1,8 +1,12 @@
-use crate::logging::{LogsinkParameters, ReleaseCallbackHandle};
+use crate::{
+    logging::{LogsinkParameters, ReleaseCallbackHandle},
+    tracing::EventTracer,
+};
 use spatialos_sdk_sys::worker::*;
 use std::{
     ffi::{CStr, CString},
     ptr,
+    sync::Arc,
 };
 
 #[derive(Debug)]
@@ -17,6 +21,7 @@ pub struct ConnectionParameters {
     pub enable_logging_at_startup: bool,
     pub enable_dynamic_components: bool,
     pub thread_affinity: ThreadAffinityParameters,
+    pub event_tracer: Option<Arc<EventTracer>>,
 }
 
 impl ConnectionParameters {
@@ -61,6 +66,15 @@ impl ConnectionParameters {
         self
     }
 
+    /// Attaches an event tracer to the connection.
+    ///
+    /// The connection keeps the tracer alive for as long as it exists. The active span
+    /// ID of the tracer is attached to messages sent through the connection.
+    pub fn with_event_tracer(mut self, event_tracer: Arc<EventTracer>) -> Self {
+        self.event_tracer = Some(event_tracer);
+        self
+    }
+
     pub(crate) fn flatten(
         &self,
     ) -> (
@@ -136,13 +150,23 @@ impl ConnectionParameters {
             .map(LogsinkParameters::to_worker_sdk)
             .unzip();
 
+        let mut release_callbacks: Vec<ReleaseCallbackHandle> =
+            release_callbacks.into_iter().flatten().collect();
+
+        // The C API only borrows the event tracer, so we hold on to a reference until
+        // the connection (or connection future) releases its callbacks.
+        if let Some(event_tracer) = &self.event_tracer {
+            let event_tracer = event_tracer.clone();
+            release_callbacks.push(Box::new(move || drop(event_tracer)));
+        }
+
         (
             IntermediateConnectionParameters {
                 params: self,
                 protocol,
                 logsinks,
             },
-            release_callbacks.into_iter().flatten().collect(),
+            release_callbacks,
         )
     }
 }
@@ -161,6 +185,7 @@ impl Default for ConnectionParameters {
             enable_logging_at_startup: false,
             enable_dynamic_components: WORKER_DEFAULTS_ENABLE_DYNAMIC_COMPONENTS != 0,
             thread_affinity: ThreadAffinityParameters::default(),
+            event_tracer: None,
         }
     }
 }
@@ -683,7 +708,12 @@ impl<'a> IntermediateConnectionParameters<'a> {
             component_vtable_count: 0,
             component_vtables: ptr::null(),
             default_component_vtable: default_vtable,
-            event_tracer: ptr::null(),
+            event_tracer: self
+                .params
+                .event_tracer
+                .as_ref()
+                .map(|tracer| tracer.as_ptr())
+                .unwrap_or_else(ptr::null),
         }
     }
 }
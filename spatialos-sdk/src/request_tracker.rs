@@ -0,0 +1,507 @@
+//! Futures that resolve when the response to a request is received.
+//!
+//! Sending a command or entity request through a [`Connection`] returns a
+//! [`RequestId`], and the response arrives later as an op with the same request ID.
+//! A [`RequestTracker`] performs this correlation: requests sent through the tracker
+//! return a [`ResponseFuture`], which resolves once the tracker processes the matching
+//! response op.
+//!
+//! If no response op arrives before the request's timeout has elapsed (plus a small
+//! margin, to allow for the response to be delivered), the future resolves to a
+//! `Timeout` error locally. Failures that happen locally rather than in SpatialOS are
+//! reported through the other variants of [`TrackedError`].
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use futures::FutureExt;
+//! use spatialos_sdk::{
+//!     commands::ReserveEntityIdsRequest,
+//!     connection::{Connection, WorkerConnection},
+//!     request_tracker::RequestTracker,
+//! };
+//!
+//! # let mut connection: WorkerConnection = unimplemented!();
+//! let mut tracker = RequestTracker::new();
+//! let mut response = tracker.send_reserve_entity_ids_request(
+//!     &mut connection,
+//!     ReserveEntityIdsRequest(10),
+//!     None,
+//! );
+//!
+//! loop {
+//!     let ops = connection.get_op_list(0);
+//!     tracker.process_op_list(&ops);
+//!
+//!     if let Some(result) = (&mut response).now_or_never() {
+//!         match result {
+//!             Ok(range) => println!("Reserved {} entity IDs", range.count()),
+//!             Err(error) => println!("Failed to reserve entity IDs: {}", error),
+//!         }
+//!         break;
+//!     }
+//! }
+//! ```
+//!
+//! [`Connection`]: ../connection/trait.Connection.html
+//! [`RequestId`]: ../struct.RequestId.html
+//! [`RequestTracker`]: struct.RequestTracker.html
+//! [`ResponseFuture`]: struct.ResponseFuture.html
+//! [`TrackedError`]: enum.TrackedError.html
+
+use crate::{
+    commands::*,
+    connection::Connection,
+    op::*,
+    schema, {EntityId, RequestId},
+};
+use futures::channel::oneshot::{self, Receiver};
+use spatialos_sdk_sys::worker::WORKER_DEFAULTS_DEFAULT_COMMAND_TIMEOUT_MILLIS;
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter},
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+/// The time to wait past a request's timeout before timing it out locally.
+///
+/// SpatialOS sends a response with a `Timeout` status code once a request's timeout
+/// elapses, so the local timeout only takes effect if that response is lost.
+pub const LOCAL_TIMEOUT_MARGIN: Duration = Duration::from_secs(1);
+
+type ResponseResult<T> = Result<T, TrackedError>;
+
+/// The reasons a [`ResponseFuture`] can fail.
+///
+/// [`ResponseFuture`]: struct.ResponseFuture.html
+#[derive(Debug)]
+pub enum TrackedError {
+    /// The request failed with the given status, either as reported by SpatialOS or
+    /// because it timed out locally.
+    Response(CommandResponseError),
+
+    /// The response was received, but its payload could not be deserialized.
+    Deserialize(schema::Error),
+
+    /// The response op did not match the type of the request it was for.
+    UnexpectedResponse(String),
+
+    /// The tracker was dropped before a response was received.
+    Dropped,
+}
+
+impl Display for TrackedError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TrackedError::Response(error) => error.fmt(f),
+            TrackedError::Deserialize(error) => {
+                write!(f, "Failed to deserialize response: {}", error)
+            }
+            TrackedError::UnexpectedResponse(op) => {
+                write!(f, "Unexpected response op for request: {}", op)
+            }
+            TrackedError::Dropped => {
+                f.write_str("The request tracker was dropped before a response was received")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TrackedError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TrackedError::Deserialize(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<CommandResponseError> for TrackedError {
+    fn from(error: CommandResponseError) -> Self {
+        TrackedError::Response(error)
+    }
+}
+
+// Resolves a pending request with its response op, or with `None` if the request
+// timed out locally.
+type Resolver = Box<dyn FnOnce(Option<&WorkerOp<'_>>) + Send>;
+
+struct PendingRequest {
+    deadline: Instant,
+    resolve: Resolver,
+}
+
+/// Tracks in-flight requests and resolves their [`ResponseFuture`]s.
+///
+/// The tracker must be passed every op list received from the connection via
+/// [`process_op_list`], which both resolves requests whose responses have arrived
+/// and times out requests whose responses are overdue.
+///
+/// [`ResponseFuture`]: struct.ResponseFuture.html
+/// [`process_op_list`]: #method.process_op_list
+pub struct RequestTracker {
+    pending: HashMap<RequestId, PendingRequest>,
+    default_timeout_millis: u32,
+}
+
+impl RequestTracker {
+    pub fn new() -> Self {
+        RequestTracker {
+            pending: HashMap::new(),
+            default_timeout_millis: WORKER_DEFAULTS_DEFAULT_COMMAND_TIMEOUT_MILLIS,
+        }
+    }
+
+    /// Sets the timeout used for requests sent without an explicit timeout.
+    ///
+    /// This should match the `default_command_timeout_millis` network parameter that
+    /// the connection was created with.
+    pub fn with_default_timeout_millis(mut self, timeout_millis: u32) -> Self {
+        self.default_timeout_millis = timeout_millis;
+        self
+    }
+
+    /// Returns the number of requests that are still awaiting a response.
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Resolves the requests that have received a response in `ops`, then times out any
+    /// requests that are overdue.
+    pub fn process_op_list(&mut self, ops: &OpList) {
+        for op in ops {
+            self.process_op(&op);
+        }
+
+        self.expire_requests(Instant::now());
+    }
+
+    /// Resolves the request that `op` is a response to, if it is being tracked.
+    pub fn process_op(&mut self, op: &WorkerOp<'_>) {
+        let request_id = match response_request_id(op) {
+            Some(request_id) => request_id,
+            None => return,
+        };
+
+        if let Some(pending) = self.pending.remove(&request_id) {
+            (pending.resolve)(Some(op));
+        }
+    }
+
+    /// Times out every request whose deadline is earlier than `now`.
+    pub fn expire_requests(&mut self, now: Instant) {
+        let expired = self
+            .pending
+            .iter()
+            .filter(|(_, pending)| pending.deadline < now)
+            .map(|(&request_id, _)| request_id)
+            .collect::<Vec<_>>();
+
+        for request_id in expired {
+            if let Some(pending) = self.pending.remove(&request_id) {
+                (pending.resolve)(None);
+            }
+        }
+    }
+
+    /// Sends a command request and returns a future for the typed response.
+    pub fn send_command_request<C, R>(
+        &mut self,
+        connection: &mut C,
+        entity_id: EntityId,
+        request: &R,
+        timeout_millis: Option<u32>,
+        params: CommandParameters,
+    ) -> ResponseFuture<<R::Commands as Commands>::Response>
+    where
+        C: Connection,
+        R: Request,
+        <R::Commands as Commands>::Response: Send + 'static,
+    {
+        let request_id =
+            connection.send_command_request(entity_id, request, timeout_millis, params);
+        self.track(request_id, timeout_millis, |op| match op {
+            WorkerOp::CommandResponse(response_op) => match &response_op.response {
+                Ok(response) => response
+                    .get::<R::Commands>()
+                    .ok_or_else(|| mismatched_response(op))?
+                    .map_err(TrackedError::Deserialize),
+                Err(error) => Err(error.clone().into()),
+            },
+            _ => Err(mismatched_response(op)),
+        })
+    }
+
+    pub fn send_reserve_entity_ids_request<C: Connection>(
+        &mut self,
+        connection: &mut C,
+        payload: ReserveEntityIdsRequest,
+        timeout_millis: Option<u32>,
+    ) -> ResponseFuture<ReservedEntityIdRange> {
+        let request_id = connection.send_reserve_entity_ids_request(payload, timeout_millis);
+        self.track(request_id, timeout_millis, |op| match op {
+            WorkerOp::ReserveEntityIdsResponse(op) => Ok(op.status_code.clone()?),
+            _ => Err(mismatched_response(op)),
+        })
+    }
+
+    pub fn send_create_entity_request<C: Connection>(
+        &mut self,
+        connection: &mut C,
+        payload: CreateEntityRequest,
+        timeout_millis: Option<u32>,
+    ) -> ResponseFuture<EntityId> {
+        let request_id = connection.send_create_entity_request(payload, timeout_millis);
+        self.track(request_id, timeout_millis, |op| match op {
+            WorkerOp::CreateEntityResponse(op) => Ok(op.response.clone()?),
+            _ => Err(mismatched_response(op)),
+        })
+    }
+
+    pub fn send_delete_entity_request<C: Connection>(
+        &mut self,
+        connection: &mut C,
+        payload: DeleteEntityRequest,
+        timeout_millis: Option<u32>,
+    ) -> ResponseFuture<()> {
+        let request_id = connection.send_delete_entity_request(payload, timeout_millis);
+        self.track(request_id, timeout_millis, |op| match op {
+            WorkerOp::DeleteEntityResponse(op) => Ok(op.response.clone()?),
+            _ => Err(mismatched_response(op)),
+        })
+    }
+
+    pub fn send_entity_query_request<C: Connection>(
+        &mut self,
+        connection: &mut C,
+        payload: EntityQueryRequest,
+        timeout_millis: Option<u32>,
+    ) -> ResponseFuture<QueryResponse> {
+        let request_id = connection.send_entity_query_request(payload, timeout_millis);
+        self.track(request_id, timeout_millis, |op| match op {
+            WorkerOp::EntityQueryResponse(op) => Ok(op.response.clone()?),
+            _ => Err(mismatched_response(op)),
+        })
+    }
+
+    fn track<T, F>(
+        &mut self,
+        request_id: RequestId,
+        timeout_millis: Option<u32>,
+        get_response: F,
+    ) -> ResponseFuture<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&WorkerOp<'_>) -> ResponseResult<T> + Send + 'static,
+    {
+        let (sender, receiver) = oneshot::channel();
+        let timeout_millis = timeout_millis.unwrap_or(self.default_timeout_millis);
+        let deadline = Instant::now()
+            + Duration::from_millis(u64::from(timeout_millis))
+            + LOCAL_TIMEOUT_MARGIN;
+
+        let resolve = move |op: Option<&WorkerOp<'_>>| {
+            let result = match op {
+                Some(op) => get_response(op),
+                None => Err(TrackedError::Response(CommandResponseError {
+                    code: CommandStatusCode::Timeout,
+                    detail: format!("No response received within {}ms", timeout_millis),
+                })),
+            };
+
+            // The future may have been dropped, in which case nobody is interested in
+            // the response.
+            let _ = sender.send(result);
+        };
+
+        self.pending.insert(
+            request_id,
+            PendingRequest {
+                deadline,
+                resolve: Box::new(resolve),
+            },
+        );
+
+        ResponseFuture { receiver }
+    }
+}
+
+impl Default for RequestTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn response_request_id(op: &WorkerOp<'_>) -> Option<RequestId> {
+    match op {
+        WorkerOp::CommandResponse(op) => Some(op.request_id),
+        WorkerOp::ReserveEntityIdsResponse(op) => Some(op.request_id),
+        WorkerOp::CreateEntityResponse(op) => Some(op.request_id),
+        WorkerOp::DeleteEntityResponse(op) => Some(op.request_id),
+        WorkerOp::EntityQueryResponse(op) => Some(op.request_id),
+        _ => None,
+    }
+}
+
+// Request IDs are unique across all request types, so a response op should always
+// match the type of the request it's for.
+fn mismatched_response(op: &WorkerOp<'_>) -> TrackedError {
+    TrackedError::UnexpectedResponse(format!("{:?}", op))
+}
+
+/// A future that resolves to the response to a request sent through a
+/// [`RequestTracker`].
+///
+/// If the tracker is dropped before the response is received, the future resolves to
+/// `TrackedError::Dropped`.
+///
+/// [`RequestTracker`]: struct.RequestTracker.html
+#[derive(Debug)]
+pub struct ResponseFuture<T> {
+    receiver: Receiver<ResponseResult<T>>,
+}
+
+impl<T> Future for ResponseFuture<T> {
+    type Output = ResponseResult<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.receiver)
+            .poll(cx)
+            .map(|result| result.unwrap_or_else(|_| Err(TrackedError::Dropped)))
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod test {
+    use super::*;
+    use crate::{
+        entity::Entity,
+        testing::{MockConnection, SentMessage},
+    };
+    use futures::FutureExt;
+
+    #[test]
+    fn future_resolves_when_response_is_processed() {
+        let mut connection = MockConnection::new("test_worker");
+        let mut tracker = RequestTracker::new();
+        let mut response = tracker.send_reserve_entity_ids_request(
+            &mut connection,
+            ReserveEntityIdsRequest(3),
+            None,
+        );
+        let request_id = match &connection.sent()[0] {
+            SentMessage::ReserveEntityIdsRequest { request_id, .. } => *request_id,
+            _ => panic!("Expected a reserve entity IDs request"),
+        };
+
+        assert!((&mut response).now_or_never().is_none());
+
+        let ops = OpListBuilder::new()
+            .reserve_entity_ids_response(request_id, Ok((EntityId::new(10), 3)))
+            .build();
+        tracker.process_op_list(&ops);
+
+        let range = response
+            .now_or_never()
+            .expect("Future was not resolved")
+            .expect("Request failed");
+        assert_eq!(3, range.count());
+        assert_eq!(0, tracker.pending_count());
+    }
+
+    #[test]
+    fn failed_response_resolves_to_error() {
+        let mut connection = MockConnection::new("test_worker");
+        let mut tracker = RequestTracker::new();
+        let response = tracker.send_delete_entity_request(
+            &mut connection,
+            DeleteEntityRequest(EntityId::new(1)),
+            None,
+        );
+
+        let ops = OpListBuilder::new()
+            .delete_entity_response(
+                RequestId::new(1),
+                EntityId::new(1),
+                Err(CommandResponseError {
+                    code: CommandStatusCode::NotFound,
+                    detail: "No such entity".to_owned(),
+                }),
+            )
+            .build();
+        tracker.process_op_list(&ops);
+
+        match response.now_or_never().unwrap().unwrap_err() {
+            TrackedError::Response(error) => assert_eq!("No such entity", error.detail),
+            error => panic!("Expected a response error, got {:?}", error),
+        }
+    }
+
+    #[test]
+    fn request_times_out_locally() {
+        let mut connection = MockConnection::new("test_worker");
+        let mut tracker = RequestTracker::new();
+        let response = tracker.send_create_entity_request(
+            &mut connection,
+            CreateEntityRequest(Entity::new(), None),
+            Some(100),
+        );
+
+        tracker.expire_requests(Instant::now());
+        assert_eq!(1, tracker.pending_count());
+
+        tracker.expire_requests(
+            Instant::now() + Duration::from_millis(100) + LOCAL_TIMEOUT_MARGIN * 2,
+        );
+        assert_eq!(0, tracker.pending_count());
+
+        match response.now_or_never().unwrap().unwrap_err() {
+            TrackedError::Response(CommandResponseError {
+                code: CommandStatusCode::Timeout,
+                ..
+            }) => {}
+            error => panic!("Expected a timeout, got {:?}", error),
+        }
+    }
+
+    #[test]
+    fn dropped_tracker_resolves_to_error() {
+        let mut connection = MockConnection::new("test_worker");
+        let mut tracker = RequestTracker::new();
+        let response = tracker.send_reserve_entity_ids_request(
+            &mut connection,
+            ReserveEntityIdsRequest(1),
+            None,
+        );
+        drop(tracker);
+
+        match response.now_or_never().unwrap() {
+            Err(TrackedError::Dropped) => {}
+            result => panic!("Expected the request to be dropped, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn mismatched_response_op_resolves_to_unexpected_response() {
+        let mut connection = MockConnection::new("test_worker");
+        let mut tracker = RequestTracker::new();
+        let response = tracker.send_delete_entity_request(
+            &mut connection,
+            DeleteEntityRequest(EntityId::new(1)),
+            None,
+        );
+
+        let ops = OpListBuilder::new()
+            .reserve_entity_ids_response(RequestId::new(1), Ok((EntityId::new(10), 3)))
+            .build();
+        tracker.process_op_list(&ops);
+
+        match response.now_or_never().unwrap() {
+            Err(TrackedError::UnexpectedResponse(_)) => {}
+            result => panic!("Expected an unexpected response error, got {:?}", result),
+        }
+    }
+}
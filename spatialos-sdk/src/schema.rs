@@ -70,23 +70,28 @@ use std::{
 #[macro_use]
 mod macros;
 
+mod borrowed;
+mod buffer;
 mod bundle;
 mod collections;
 mod command_request;
 mod command_response;
 mod component_data;
 mod component_update;
+mod descriptor;
 mod float_ord;
 mod generic_data;
 mod object;
 mod primitives;
 mod ptr;
+mod value;
 
 pub mod owned;
 
 pub use self::{
-    bundle::*, collections::*, command_request::*, command_response::*, component_data::*,
-    component_update::*, float_ord::*, generic_data::*, object::*, owned::Owned, primitives::*,
+    borrowed::*, buffer::*, bundle::*, collections::*, command_request::*, command_response::*,
+    component_data::*, component_update::*, descriptor::*, float_ord::*, generic_data::*,
+    object::*, owned::Owned, primitives::*, value::*,
 };
 #[doc(inline)]
 pub use crate::impl_field_for_enum_field;
@@ -143,6 +148,20 @@ pub trait IndexedField: Field {
             .map(|index| Self::index(object, field, index))
             .collect()
     }
+
+    /// Replaces the contents of `dest` with the list in `field`, reusing its allocation.
+    fn copy_list(
+        object: &SchemaObject,
+        field: FieldId,
+        dest: &mut Vec<Self::RustType>,
+    ) -> Result<()> {
+        dest.clear();
+        for index in 0..Self::count(object, field) {
+            dest.push(Self::index(object, field, index)?);
+        }
+
+        Ok(())
+    }
 }
 
 /// A struct that can be serialized into (and deserialized from) a [`SchemaObject`].
@@ -322,6 +341,27 @@ macro_rules! impl_field_for_enum_field {
                     .map_err(Into::into)
             }
         }
+
+        impl<'a> $crate::schema::BorrowedField<'a> for $type {
+            type RefType = Self;
+
+            fn get_ref(
+                object: &'a $crate::schema::SchemaObject,
+                field: $crate::schema::FieldId,
+            ) -> $crate::schema::Result<Self::RefType> {
+                <Self as $crate::schema::Field>::get(object, field)
+            }
+        }
+
+        impl<'a> $crate::schema::IndexedBorrowedField<'a> for $type {
+            fn index_ref(
+                object: &'a $crate::schema::SchemaObject,
+                field: $crate::schema::FieldId,
+                index: usize,
+            ) -> $crate::schema::Result<Self::RefType> {
+                <Self as $crate::schema::IndexedField>::index(object, field, index)
+            }
+        }
     };
 }
 
@@ -391,6 +431,13 @@ impl Error {
             kind: ErrorKind::SchemaError(msg),
         }
     }
+
+    pub fn buffer_serialization<T>(msg: String) -> Self {
+        Self {
+            type_name: std::any::type_name::<T>(),
+            kind: ErrorKind::BufferSerialization(msg),
+        }
+    }
 }
 
 impl Display for Error {
@@ -435,6 +482,12 @@ impl Display for Error {
             },
 
             ErrorKind::SchemaError(msg) => write!(f, "Generic schema error {}", msg),
+
+            ErrorKind::BufferSerialization(msg) => write!(
+                f,
+                "Failed to serialize {} to or from a buffer: {}",
+                self.type_name, msg
+            ),
         }
     }
 }
@@ -473,4 +526,5 @@ pub enum ErrorKind {
         error: Box<Error>,
     },
     SchemaError(String),
+    BufferSerialization(String),
 }
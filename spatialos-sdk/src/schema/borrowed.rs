@@ -0,0 +1,462 @@
+use crate::schema::{
+    DataPointer, Error, Field, FieldId, IndexedField, List, Map, ObjectField, Optional,
+    RecursiveOptional, Result, SchemaBool, SchemaBytes, SchemaComponentUpdate, SchemaDouble,
+    SchemaEntity, SchemaEntityId, SchemaEnum, SchemaFixed32, SchemaFixed64, SchemaFloat,
+    SchemaInt32, SchemaInt64, SchemaObject, SchemaSfixed32, SchemaSfixed64, SchemaSint32,
+    SchemaSint64, SchemaString, SchemaUint32, SchemaUint64,
+};
+use spatialos_sdk_sys::worker::*;
+use std::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+    slice, str,
+};
+
+/// A [`Field`] that can be read without copying its data out of the [`SchemaObject`].
+///
+/// The borrowed representation `RefType` of a field has the lifetime `'a` of the
+/// object it's read from. Strings are read as `&'a str` and bytes as `&'a [u8]`, lists
+/// and maps are read lazily through [`ListRef`] and [`MapRef`], and schema types are
+/// read as the `FooRef<'a>` view structs that the code generator can emit alongside
+/// each type. Primitive values are cheap to copy, so they're read as their usual Rust
+/// type.
+///
+/// [`Field`]: trait.Field.html
+/// [`SchemaObject`]: struct.SchemaObject.html
+/// [`ListRef`]: struct.ListRef.html
+/// [`MapRef`]: struct.MapRef.html
+pub trait BorrowedField<'a>: Field {
+    type RefType;
+
+    fn get_ref(object: &'a SchemaObject, field: FieldId) -> Result<Self::RefType>;
+
+    fn get_update_ref(
+        update: &'a SchemaComponentUpdate,
+        field: FieldId,
+    ) -> Result<Option<Self::RefType>> {
+        if Self::has_update(update, field) {
+            Self::get_ref(update.fields(), field).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+pub trait IndexedBorrowedField<'a>: BorrowedField<'a> + IndexedField {
+    fn index_ref(object: &'a SchemaObject, field: FieldId, index: usize) -> Result<Self::RefType>;
+}
+
+/// A type that can be read from a [`SchemaObject`] as a borrowed view.
+///
+/// This is implemented by the code generator for schema types and components when
+/// borrowed views are enabled, with `Ref` being the corresponding `FooRef<'a>` struct.
+///
+/// [`SchemaObject`]: struct.SchemaObject.html
+pub trait ObjectFieldRef<'a>: ObjectField {
+    type Ref;
+
+    fn from_object_ref(object: &'a SchemaObject) -> Result<Self::Ref>;
+}
+
+impl<'a, T> BorrowedField<'a> for T
+where
+    T: ObjectFieldRef<'a>,
+{
+    type RefType = <T as ObjectFieldRef<'a>>::Ref;
+
+    fn get_ref(object: &'a SchemaObject, field: FieldId) -> Result<Self::RefType> {
+        T::from_object_ref(object.get_object(field))
+    }
+}
+
+impl<'a, T> IndexedBorrowedField<'a> for T
+where
+    T: ObjectFieldRef<'a>,
+{
+    fn index_ref(object: &'a SchemaObject, field: FieldId, index: usize) -> Result<Self::RefType> {
+        T::from_object_ref(object.index_object(field, index))
+    }
+}
+
+macro_rules! impl_borrowed_field_by_value {
+    ($($schema_type:ty),*) => {
+        $(
+            impl<'a> BorrowedField<'a> for $schema_type {
+                type RefType = <$schema_type as Field>::RustType;
+
+                fn get_ref(object: &'a SchemaObject, field: FieldId) -> Result<Self::RefType> {
+                    Self::get(object, field)
+                }
+            }
+
+            impl<'a> IndexedBorrowedField<'a> for $schema_type {
+                fn index_ref(
+                    object: &'a SchemaObject,
+                    field: FieldId,
+                    index: usize,
+                ) -> Result<Self::RefType> {
+                    Self::index(object, field, index)
+                }
+            }
+        )*
+    };
+}
+
+impl_borrowed_field_by_value!(
+    SchemaFloat,
+    SchemaDouble,
+    SchemaInt32,
+    SchemaInt64,
+    SchemaUint32,
+    SchemaUint64,
+    SchemaSint32,
+    SchemaSint64,
+    SchemaFixed32,
+    SchemaFixed64,
+    SchemaSfixed32,
+    SchemaSfixed64,
+    SchemaEnum,
+    SchemaEntityId,
+    SchemaBool,
+    SchemaEntity
+);
+
+impl<'a> BorrowedField<'a> for SchemaBytes {
+    type RefType = &'a [u8];
+
+    fn get_ref(object: &'a SchemaObject, field: FieldId) -> Result<&'a [u8]> {
+        if Self::count(object, field) > 0 {
+            unsafe {
+                let bytes_ptr = Schema_GetBytes(object.as_ptr(), field);
+                let bytes_len = Schema_GetBytesLength(object.as_ptr(), field);
+                Ok(slice::from_raw_parts(bytes_ptr, bytes_len as usize))
+            }
+        } else {
+            Err(Error::missing_field::<Self>())
+        }
+    }
+}
+
+impl<'a> IndexedBorrowedField<'a> for SchemaBytes {
+    fn index_ref(object: &'a SchemaObject, field: FieldId, index: usize) -> Result<&'a [u8]> {
+        let count = Self::count(object, field);
+        if count > index {
+            unsafe {
+                let bytes_ptr = Schema_IndexBytes(object.as_ptr(), field, index as u32);
+                let bytes_len = Schema_IndexBytesLength(object.as_ptr(), field, index as u32);
+                Ok(slice::from_raw_parts(bytes_ptr, bytes_len as usize))
+            }
+        } else {
+            Err(Error::index_out_of_bounds::<Self>(index, count))
+        }
+    }
+}
+
+// NOTE: Unlike `SchemaString::get`, which replaces invalid UTF-8 sequences, a borrowed
+// string must be valid UTF-8, so invalid strings are reported as an error instead.
+impl<'a> BorrowedField<'a> for SchemaString {
+    type RefType = &'a str;
+
+    fn get_ref(object: &'a SchemaObject, field: FieldId) -> Result<&'a str> {
+        let bytes =
+            SchemaBytes::get_ref(object, field).map_err(|_| Error::missing_field::<Self>())?;
+        str::from_utf8(bytes).map_err(|e| Error::schema_error::<Self>(e.to_string()))
+    }
+}
+
+impl<'a> IndexedBorrowedField<'a> for SchemaString {
+    fn index_ref(object: &'a SchemaObject, field: FieldId, index: usize) -> Result<&'a str> {
+        let bytes = SchemaBytes::index_ref(object, field, index)
+            .map_err(|_| Error::index_out_of_bounds::<Self>(index, Self::count(object, field)))?;
+        str::from_utf8(bytes).map_err(|e| Error::schema_error::<Self>(e.to_string()))
+    }
+}
+
+impl<'a, T> BorrowedField<'a> for Optional<T>
+where
+    T: IndexedBorrowedField<'a>,
+{
+    type RefType = Option<T::RefType>;
+
+    fn get_ref(object: &'a SchemaObject, field: FieldId) -> Result<Self::RefType> {
+        if T::count(object, field) > 0 {
+            T::get_ref(object, field).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl<'a, T> BorrowedField<'a> for RecursiveOptional<T>
+where
+    T: ObjectField + IndexedBorrowedField<'a>,
+{
+    type RefType = Option<Box<T::RefType>>;
+
+    fn get_ref(object: &'a SchemaObject, field: FieldId) -> Result<Self::RefType> {
+        if T::count(object, field) > 0 {
+            T::get_ref(object, field).map(Box::new).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl<'a, T> BorrowedField<'a> for List<T>
+where
+    T: IndexedBorrowedField<'a>,
+{
+    type RefType = ListRef<'a, T>;
+
+    fn get_ref(object: &'a SchemaObject, field: FieldId) -> Result<Self::RefType> {
+        Ok(ListRef::new(object, field))
+    }
+}
+
+impl<'a, K, V> BorrowedField<'a> for Map<K, V>
+where
+    K: IndexedBorrowedField<'a>,
+    V: IndexedBorrowedField<'a>,
+    K::RustType: Ord,
+{
+    type RefType = MapRef<'a, K, V>;
+
+    fn get_ref(object: &'a SchemaObject, field: FieldId) -> Result<Self::RefType> {
+        Ok(MapRef::new(object, field))
+    }
+}
+
+/// A borrowed view of a `list` field, which reads each element on demand.
+///
+/// Elements are read from the underlying [`SchemaObject`] by index, so iterating over a
+/// `ListRef` doesn't allocate unless the elements themselves do, but it does cost one
+/// FFI call per element. For lists of primitives, [`to_vec`] and [`copy_into`] instead
+/// copy the whole list in a single call, and `copy_into` reuses an existing buffer so
+/// that large lists can be read repeatedly without allocating.
+///
+/// [`SchemaObject`]: struct.SchemaObject.html
+/// [`to_vec`]: #method.to_vec
+/// [`copy_into`]: #method.copy_into
+pub struct ListRef<'a, T> {
+    object: &'a SchemaObject,
+    field: FieldId,
+    len: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<'a, T> ListRef<'a, T>
+where
+    T: IndexedBorrowedField<'a>,
+{
+    /// Creates a view of the elements of `field` in `object`.
+    pub fn new(object: &'a SchemaObject, field: FieldId) -> Self {
+        ListRef {
+            object,
+            field,
+            len: T::count(object, field),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Reads the element at `index`, or returns `None` if `index` is out of bounds.
+    pub fn get(&self, index: usize) -> Option<Result<T::RefType>> {
+        if index < self.len {
+            Some(T::index_ref(self.object, self.field, index))
+        } else {
+            None
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Result<T::RefType>> + 'a
+    where
+        T: 'a,
+    {
+        let ListRef { object, field, .. } = *self;
+        (0..self.len).map(move |index| T::index_ref(object, field, index))
+    }
+
+    /// Copies every element of the list into a `Vec` of the owned Rust type.
+    pub fn to_vec(&self) -> Result<Vec<T::RustType>> {
+        T::get_list(self.object, self.field)
+    }
+
+    /// Replaces the contents of `dest` with the elements of the list, reusing its
+    /// allocation where possible.
+    pub fn copy_into(&self, dest: &mut Vec<T::RustType>) -> Result<()> {
+        T::copy_list(self.object, self.field, dest)
+    }
+}
+
+impl<'a, T> Clone for ListRef<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for ListRef<'a, T> {}
+
+impl<'a, T> Debug for ListRef<'a, T>
+where
+    T: IndexedBorrowedField<'a> + 'a,
+    T::RefType: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// A borrowed view of a `map` field, which reads each entry on demand.
+///
+/// Entries are visited in the order in which they're stored in the underlying
+/// [`SchemaObject`], which isn't necessarily sorted by key.
+///
+/// [`SchemaObject`]: struct.SchemaObject.html
+pub struct MapRef<'a, K, V> {
+    object: &'a SchemaObject,
+    field: FieldId,
+    len: usize,
+    _marker: PhantomData<fn() -> (K, V)>,
+}
+
+impl<'a, K, V> MapRef<'a, K, V>
+where
+    K: BorrowedField<'a>,
+    V: BorrowedField<'a>,
+{
+    /// Creates a view of the entries of `field` in `object`.
+    pub fn new(object: &'a SchemaObject, field: FieldId) -> Self {
+        MapRef {
+            object,
+            field,
+            len: object.object_count(field),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Reads the key and value of the entry at `index`, or returns `None` if `index` is
+    /// out of bounds.
+    pub fn get(&self, index: usize) -> Option<Result<(K::RefType, V::RefType)>> {
+        if index < self.len {
+            Some(Self::read_entry(self.object, self.field, index))
+        } else {
+            None
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Result<(K::RefType, V::RefType)>> + 'a
+    where
+        K: 'a,
+        V: 'a,
+    {
+        let MapRef { object, field, .. } = *self;
+        (0..self.len).map(move |index| Self::read_entry(object, field, index))
+    }
+
+    // Map fields are represented in schema as a list of pairs of key and value. See
+    // `Map::get` for more details.
+    fn read_entry(
+        object: &'a SchemaObject,
+        field: FieldId,
+        index: usize,
+    ) -> Result<(K::RefType, V::RefType)> {
+        let pair = object.index_object(field, index);
+        let key = K::get_ref(pair, SCHEMA_MAP_KEY_FIELD_ID)
+            .map_err(Error::at_index::<Self>(field, index))?;
+        let value = V::get_ref(pair, SCHEMA_MAP_VALUE_FIELD_ID)
+            .map_err(Error::at_index::<Self>(field, index))?;
+        Ok((key, value))
+    }
+}
+
+impl<'a, K, V> Clone for MapRef<'a, K, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, K, V> Copy for MapRef<'a, K, V> {}
+
+impl<'a, K, V> Debug for MapRef<'a, K, V>
+where
+    K: BorrowedField<'a> + 'a,
+    V: BorrowedField<'a> + 'a,
+    K::RefType: Debug,
+    V::RefType: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::schema::SchemaComponentData;
+
+    #[test]
+    fn strings_and_bytes_are_borrowed() {
+        let mut data = SchemaComponentData::new();
+        let fields = data.fields_mut();
+        fields.add::<SchemaString>(1, &"hello".to_owned());
+        fields.add_list::<SchemaBytes>(2, &[vec![1, 2], vec![3]]);
+
+        let fields = data.fields();
+        assert_eq!("hello", fields.get_ref::<SchemaString>(1).unwrap());
+        assert!(fields.get_ref::<SchemaString>(3).is_err());
+
+        let bytes = fields.get_ref::<List<SchemaBytes>>(2).unwrap();
+        assert_eq!(2, bytes.len());
+        assert_eq!(
+            vec![&[1, 2][..], &[3][..]],
+            bytes.iter().collect::<Result<Vec<_>>>().unwrap()
+        );
+
+        let mut owned = vec![vec![9]];
+        bytes.copy_into(&mut owned).unwrap();
+        assert_eq!(vec![vec![1, 2], vec![3]], owned);
+    }
+
+    #[test]
+    fn collections_are_read_lazily() {
+        let mut data = SchemaComponentData::new();
+        let fields = data.fields_mut();
+        fields.add_list::<SchemaInt32>(1, &[1, 2, 3]);
+        let pair = fields.add_object(2);
+        pair.add::<SchemaString>(SCHEMA_MAP_KEY_FIELD_ID, &"key".to_owned());
+        pair.add::<SchemaUint32>(SCHEMA_MAP_VALUE_FIELD_ID, &5);
+
+        let fields = data.fields();
+        let list = fields.get_ref::<List<SchemaInt32>>(1).unwrap();
+        assert_eq!(Some(2), list.get(1).map(Result::unwrap));
+        assert!(list.get(3).is_none());
+        assert_eq!(vec![1, 2, 3], list.to_vec().unwrap());
+
+        let mut buffer = vec![7; 8];
+        list.copy_into(&mut buffer).unwrap();
+        assert_eq!(vec![1, 2, 3], buffer);
+
+        let map = fields
+            .get_ref::<Map<SchemaString, SchemaUint32>>(2)
+            .unwrap();
+        assert_eq!(1, map.len());
+        assert_eq!(("key", 5), map.get(0).unwrap().unwrap());
+
+        assert_eq!(None, fields.get_ref::<Optional<SchemaInt32>>(3).unwrap());
+    }
+}
@@ -0,0 +1,246 @@
+//! Binary serialization of schema data to and from byte buffers.
+//!
+//! Schema data can be serialized into the same binary wire format that SpatialOS
+//! uses internally. This is useful for persisting schema data, or for sending it
+//! over channels other than a worker connection.
+//!
+//! # Examples
+//!
+//! ```
+//! use spatialos_sdk::schema::*;
+//!
+//! let mut data = SchemaComponentData::new();
+//! data.fields_mut().add::<SchemaInt32>(1, &123);
+//!
+//! let bytes = data.to_bytes().unwrap();
+//! let copy = Owned::<SchemaComponentData>::from_bytes(&bytes).unwrap();
+//!
+//! assert_eq!(123, copy.fields().get::<SchemaInt32>(1).unwrap());
+//! ```
+
+use crate::schema::{
+    DataPointer, Error, FieldId, Owned, OwnedPointer, Result, SchemaCommandRequest,
+    SchemaCommandResponse, SchemaComponentData, SchemaComponentUpdate, SchemaGenericData,
+    SchemaObject, SchemaUint32,
+};
+use spatialos_sdk_sys::worker::*;
+use std::{convert::TryFrom, ptr};
+
+// Field IDs used when wrapping a component update in a single object for serialization.
+const UPDATE_FIELDS_FIELD_ID: FieldId = 1;
+const UPDATE_EVENTS_FIELD_ID: FieldId = 2;
+const UPDATE_CLEARED_FIELDS_FIELD_ID: FieldId = 3;
+
+/// Schema data that can be serialized to (and deserialized from) a byte buffer.
+pub trait BufferSerializable: OwnedPointer {
+    /// Serializes the data into a new byte buffer.
+    fn to_bytes(&self) -> Result<Vec<u8>>;
+
+    /// Deserializes `bytes` and merges the result into `self`.
+    ///
+    /// Fields in the buffer are appended to any fields already present.
+    fn merge_from_bytes(&mut self, bytes: &[u8]) -> Result<()>;
+}
+
+impl<T: BufferSerializable> Owned<T> {
+    /// Deserializes a new instance of `T` from a byte buffer.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut result = Self::new();
+        result.merge_from_bytes(bytes)?;
+        Ok(result)
+    }
+}
+
+impl SchemaObject {
+    /// Serializes the object into a new byte buffer.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        unsafe {
+            let length = Schema_GetWriteBufferLength(self.as_ptr());
+            let mut buffer = vec![0; length as usize];
+
+            if Schema_SerializeToBuffer(self.as_ptr(), buffer.as_mut_ptr(), length) == 0 {
+                return Err(Error::buffer_serialization::<Self>(self.last_error()));
+            }
+
+            Ok(buffer)
+        }
+    }
+
+    /// Deserializes `bytes` and appends the resulting fields to the object.
+    ///
+    /// The bytes are copied into a buffer owned by the object, so `bytes` does not need
+    /// to outlive the object. An empty buffer contains no fields, so merging it leaves
+    /// the object unchanged.
+    pub fn merge_from_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        if bytes.is_empty() {
+            return Ok(());
+        }
+
+        let length = u32::try_from(bytes.len()).map_err(|_| {
+            Error::buffer_serialization::<Self>(format!(
+                "Buffer of {} bytes exceeds the maximum length of {} bytes",
+                bytes.len(),
+                u32::max_value()
+            ))
+        })?;
+
+        unsafe {
+            let buffer = Schema_AllocateBuffer(self.as_ptr_mut(), length);
+            ptr::copy_nonoverlapping(bytes.as_ptr(), buffer, bytes.len());
+
+            if Schema_MergeFromBuffer(self.as_ptr_mut(), buffer, length) == 0 {
+                return Err(Error::buffer_serialization::<Self>(self.last_error()));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+macro_rules! impl_buffer_serializable {
+    ($type:ty, $object:ident, $object_mut:ident) => {
+        impl BufferSerializable for $type {
+            fn to_bytes(&self) -> Result<Vec<u8>> {
+                self.$object().to_bytes()
+            }
+
+            fn merge_from_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+                self.$object_mut().merge_from_bytes(bytes)
+            }
+        }
+    };
+}
+
+impl_buffer_serializable!(SchemaComponentData, fields, fields_mut);
+impl_buffer_serializable!(SchemaGenericData, object, object_mut);
+impl_buffer_serializable!(SchemaCommandRequest, object, object_mut);
+impl_buffer_serializable!(SchemaCommandResponse, object, object_mut);
+
+// A component update is made up of two objects (the fields and the events) plus the
+// set of cleared fields, so it is wrapped in a single object before serialization.
+impl BufferSerializable for SchemaComponentUpdate {
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut envelope = SchemaGenericData::new();
+        let object = envelope.object_mut();
+
+        object
+            .add_object(UPDATE_FIELDS_FIELD_ID)
+            .copy_from(self.fields())
+            .map_err(Error::buffer_serialization::<Self>)?;
+        object
+            .add_object(UPDATE_EVENTS_FIELD_ID)
+            .copy_from(self.events())
+            .map_err(Error::buffer_serialization::<Self>)?;
+        object.add_list::<SchemaUint32>(UPDATE_CLEARED_FIELDS_FIELD_ID, &self.cleared_fields());
+
+        envelope.to_bytes()
+    }
+
+    fn merge_from_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        let envelope = Owned::<SchemaGenericData>::from_bytes(bytes)?;
+        let object = envelope.object();
+
+        self.fields_mut()
+            .copy_from(object.get_object(UPDATE_FIELDS_FIELD_ID))
+            .map_err(Error::buffer_serialization::<Self>)?;
+        self.events_mut()
+            .copy_from(object.get_object(UPDATE_EVENTS_FIELD_ID))
+            .map_err(Error::buffer_serialization::<Self>)?;
+
+        for field in object.get_list::<SchemaUint32>(UPDATE_CLEARED_FIELDS_FIELD_ID)? {
+            self.add_cleared(field);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::schema::*;
+
+    #[test]
+    fn object_round_trips_through_bytes() {
+        let mut data = SchemaGenericData::new();
+        let object = data.object_mut();
+        object.add::<SchemaInt32>(1, &-7);
+        object.add::<SchemaString>(2, &"hello".to_owned());
+        object.add_list::<SchemaDouble>(3, &[FloatOrd(1.5), FloatOrd(2.5)]);
+
+        let bytes = object.to_bytes().expect("Failed to serialize object");
+
+        let mut copy = SchemaGenericData::new();
+        copy.object_mut()
+            .merge_from_bytes(&bytes)
+            .expect("Failed to deserialize object");
+
+        let copy = copy.object();
+        assert_eq!(-7, copy.get::<SchemaInt32>(1).unwrap());
+        assert_eq!("hello", copy.get::<SchemaString>(2).unwrap());
+        assert_eq!(
+            vec![FloatOrd(1.5), FloatOrd(2.5)],
+            copy.get_list::<SchemaDouble>(3).unwrap()
+        );
+    }
+
+    #[test]
+    fn component_data_round_trips_through_bytes() {
+        let mut data = SchemaComponentData::new();
+        data.fields_mut()
+            .add_object(1)
+            .add::<SchemaDouble>(1, &FloatOrd(10.0));
+
+        let bytes = data.to_bytes().expect("Failed to serialize component data");
+        let copy = Owned::<SchemaComponentData>::from_bytes(&bytes)
+            .expect("Failed to deserialize component data");
+
+        assert_eq!(
+            FloatOrd(10.0),
+            copy.fields().get_object(1).get::<SchemaDouble>(1).unwrap()
+        );
+    }
+
+    #[test]
+    fn component_update_round_trips_through_bytes() {
+        let mut update = SchemaComponentUpdate::new();
+        update.fields_mut().add::<SchemaUint32>(1, &5);
+        update
+            .events_mut()
+            .add_object(1)
+            .add::<SchemaBool>(1, &true);
+        update.add_cleared(2);
+
+        let bytes = update.to_bytes().expect("Failed to serialize update");
+        let copy = Owned::<SchemaComponentUpdate>::from_bytes(&bytes)
+            .expect("Failed to deserialize update");
+
+        assert_eq!(5, copy.fields().get::<SchemaUint32>(1).unwrap());
+        assert_eq!(1, copy.events().object_count(1));
+        assert!(copy.events().get_object(1).get::<SchemaBool>(1).unwrap());
+        assert!(copy.is_field_cleared(2));
+        assert!(!copy.is_field_cleared(1));
+    }
+
+    #[test]
+    fn merge_from_empty_bytes_leaves_object_unchanged() {
+        let mut data = SchemaComponentData::new();
+        data.fields_mut().add::<SchemaInt32>(1, &5);
+
+        data.merge_from_bytes(&[])
+            .expect("Failed to merge empty buffer");
+        assert_eq!(5, data.fields().get::<SchemaInt32>(1).unwrap());
+        assert!(Owned::<SchemaComponentData>::from_bytes(&[]).is_ok());
+    }
+
+    #[test]
+    fn merge_from_bytes_returns_error_for_truncated_buffer() {
+        let mut data = SchemaComponentData::new();
+        data.fields_mut()
+            .add::<SchemaString>(1, &"a long string".to_owned());
+
+        let bytes = data.to_bytes().expect("Failed to serialize component data");
+        let result = Owned::<SchemaComponentData>::from_bytes(&bytes[..bytes.len() - 4]);
+
+        assert!(result.is_err());
+    }
+}
@@ -1,3 +1,5 @@
+#[cfg(feature = "serde")]
+use crate::entity::Entity;
 use crate::{component::ComponentId, schema::*, utils::cstr_to_string};
 use spatialos_sdk_sys::worker::*;
 use std::ffi::CString;
@@ -5,6 +7,14 @@ use std::ptr::NonNull;
 
 pub type JsonConversionResult<T> = std::result::Result<(T, Option<String>), String>;
 
+/// A binary schema bundle, used to convert schema data to and from JSON.
+///
+/// The C API doesn't expose the types described by a `Bundle`, so it can't be used as a
+/// [`TypeRegistry`]. Tooling that needs to inspect schema data field by field should build a
+/// [`SchemaTypes`] from the project's JSON schema bundle instead.
+///
+/// [`TypeRegistry`]: trait.TypeRegistry.html
+/// [`SchemaTypes`]: struct.SchemaTypes.html
 pub struct Bundle {
     ptr: NonNull<Schema_Bundle>,
 }
@@ -290,6 +300,103 @@ impl Bundle {
     }
 }
 
+/// Conversions between schema data and [`serde_json::Value`], which can in turn be
+/// serialized into any format supported by serde, such as MessagePack or RON.
+///
+/// These are driven by the schema bundle in the same way as the JSON conversions above,
+/// so they work for any type in the bundle without needing generated code.
+///
+/// [`serde_json::Value`]: https://docs.rs/serde_json/1/serde_json/enum.Value.html
+#[cfg(feature = "serde")]
+impl Bundle {
+    pub fn object_to_value<T: AsRef<str>>(
+        &self,
+        qualified_type_name: T,
+        src: &mut SchemaObject,
+    ) -> JsonConversionResult<serde_json::Value> {
+        let (json, warning) = self.dump_object(qualified_type_name, src)?;
+        Ok((parse_json_value(&json)?, warning))
+    }
+
+    pub fn object_from_value<T: AsRef<str>>(
+        &self,
+        qualified_type_name: T,
+        value: &serde_json::Value,
+        dest: &mut SchemaObject,
+    ) -> JsonConversionResult<()> {
+        self.load_object(qualified_type_name, value.to_string(), dest)
+    }
+
+    pub fn component_data_to_value(
+        &self,
+        component_id: ComponentId,
+        src: &mut SchemaComponentData,
+    ) -> JsonConversionResult<serde_json::Value> {
+        let (json, warning) = self.dump_component_data(component_id, src)?;
+        Ok((parse_json_value(&json)?, warning))
+    }
+
+    pub fn component_data_from_value(
+        &self,
+        component_id: ComponentId,
+        value: &serde_json::Value,
+    ) -> JsonConversionResult<Owned<SchemaComponentData>> {
+        self.load_component_data(component_id, value.to_string())
+    }
+
+    /// Converts every component of `entity` into JSON, returning an object keyed by component
+    /// ID. Warnings produced for individual components are joined with newlines.
+    pub fn entity_to_value(&self, entity: &Entity) -> JsonConversionResult<serde_json::Value> {
+        let mut components = serde_json::Map::new();
+        let mut warnings = Vec::new();
+
+        for (component_id, data) in entity.components() {
+            let mut data = data.clone();
+            let (value, warning) = self.component_data_to_value(component_id, &mut data)?;
+            components.insert(component_id.to_string(), value);
+            warnings.extend(warning);
+        }
+
+        Ok((components.into(), join_warnings(warnings)))
+    }
+
+    /// Builds an `Entity` from JSON in the format produced by [`entity_to_value`].
+    ///
+    /// [`entity_to_value`]: #method.entity_to_value
+    pub fn entity_from_value(&self, value: &serde_json::Value) -> JsonConversionResult<Entity> {
+        let components = value
+            .as_object()
+            .ok_or_else(|| "Expected a JSON object of components".to_string())?;
+        let mut entity = Entity::new();
+        let mut warnings = Vec::new();
+
+        for (key, value) in components {
+            let component_id = key
+                .parse::<ComponentId>()
+                .map_err(|_| format!("Invalid component ID '{}'", key))?;
+            let (data, warning) = self.component_data_from_value(component_id, value)?;
+            unsafe { entity.add_serialized(component_id, data)? };
+            warnings.extend(warning);
+        }
+
+        Ok((entity, join_warnings(warnings)))
+    }
+}
+
+#[cfg(feature = "serde")]
+fn join_warnings(warnings: Vec<String>) -> Option<String> {
+    if warnings.is_empty() {
+        None
+    } else {
+        Some(warnings.join("\n"))
+    }
+}
+
+#[cfg(feature = "serde")]
+fn parse_json_value(json: &str) -> std::result::Result<serde_json::Value, String> {
+    serde_json::from_str(json).map_err(|e| format!("Invalid JSON produced by the bundle: {}", e))
+}
+
 // SAFETY: It should be safe to send a `Bundle` between threads, so long as it's only ever accessed
 // from one thread at a time. It has unsychronized internal mutability (only storing the 'last' error
 // and warning) so it cannot be Sync.
@@ -560,6 +667,35 @@ mod tests {
         check_valid_json(json);
     }
 
+    #[cfg(feature = "serde")]
+    #[test]
+    pub fn component_data_round_trips_through_value() {
+        let value = serde_json::json!({ "coords": { "x": 1.0, "y": 2.0, "z": 3.0 } });
+
+        let bundle = get_valid_bundle();
+        let (mut data, _) = check(bundle.component_data_from_value(POSITION_COMPONENT_ID, &value));
+        let (dumped, warning) =
+            check(bundle.component_data_to_value(POSITION_COMPONENT_ID, &mut data));
+
+        assert!(warning.is_none(), "Unexpected warnings");
+        assert_eq!(value, dumped);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    pub fn entity_round_trips_through_value() {
+        let value = serde_json::json!({
+            "54": { "coords": { "x": 1.0, "y": 2.0, "z": 3.0 } }
+        });
+
+        let bundle = get_valid_bundle();
+        let (entity, _) = check(bundle.entity_from_value(&value));
+        let (dumped, warning) = check(bundle.entity_to_value(&entity));
+
+        assert!(warning.is_none(), "Unexpected warnings");
+        assert_eq!(value, dumped);
+    }
+
     fn get_valid_bundle() -> Bundle {
         read_bundle(true).expect("Failed to load bundle")
     }
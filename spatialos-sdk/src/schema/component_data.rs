@@ -1,6 +1,8 @@
 use crate::{
     component::Component,
-    schema::{DataPointer, Owned, OwnedPointer, Result, SchemaObject},
+    schema::{
+        DataPointer, Error, Owned, OwnedPointer, Result, SchemaComponentUpdate, SchemaObject,
+    },
 };
 use spatialos_sdk_sys::worker::*;
 use std::marker::PhantomData;
@@ -36,6 +38,27 @@ impl SchemaComponentData {
     pub fn fields_mut(&mut self) -> &mut SchemaObject {
         unsafe { SchemaObject::from_raw_mut(Schema_GetComponentDataFields(self.as_ptr_mut())) }
     }
+
+    /// Applies a component update to the data in place, without needing to know the
+    /// component's generated type.
+    ///
+    /// Fields set in `update` overwrite the corresponding fields in the data, and fields
+    /// cleared in `update` are removed. Events are ignored.
+    ///
+    /// Applying an update appends to the data's internal storage, so repeatedly applying
+    /// updates to the same data grows its memory usage without bound. Long-lived data
+    /// should periodically be replaced with a copy (e.g. via `to_owned()`), which only
+    /// takes up as much storage as the serialized data requires.
+    pub fn apply_update(&mut self, update: &SchemaComponentUpdate) -> Result<()> {
+        let success =
+            unsafe { Schema_ApplyComponentUpdateToData(update.as_ptr(), self.as_ptr_mut()) };
+
+        if success == 0 {
+            return Err(Error::schema_error::<Self>(self.fields().last_error()));
+        }
+
+        Ok(())
+    }
 }
 
 unsafe impl DataPointer for SchemaComponentData {
@@ -65,5 +88,26 @@ impl ToOwned for SchemaComponentData {
 
 #[cfg(test)]
 mod test {
+    use crate::schema::*;
+
     pointer_type_tests!(super::SchemaComponentData);
+
+    #[test]
+    fn apply_update_overwrites_and_clears_fields() {
+        let mut data = SchemaComponentData::new();
+        data.fields_mut().add::<SchemaInt32>(1, &1);
+        data.fields_mut().add_list::<SchemaInt32>(2, &[1, 2, 3]);
+        data.fields_mut().add::<SchemaInt32>(3, &3);
+
+        let mut update = SchemaComponentUpdate::new();
+        update.fields_mut().add::<SchemaInt32>(1, &10);
+        update.add_cleared(2);
+
+        data.apply_update(&update).expect("Failed to apply update");
+
+        let fields = data.fields();
+        assert_eq!(10, fields.get::<SchemaInt32>(1).unwrap());
+        assert_eq!(0, fields.count::<SchemaInt32>(2));
+        assert_eq!(3, fields.get::<SchemaInt32>(3).unwrap());
+    }
 }
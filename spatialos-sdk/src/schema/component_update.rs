@@ -1,6 +1,9 @@
 use crate::{
     component::Update,
-    schema::{DataPointer, Field, FieldId, ObjectField, Owned, OwnedPointer, Result, SchemaObject},
+    schema::{
+        BorrowedField, DataPointer, Error, Field, FieldId, ObjectField, Owned, OwnedPointer,
+        Result, SchemaObject,
+    },
 };
 use spatialos_sdk_sys::worker::*;
 use std::marker::PhantomData;
@@ -43,12 +46,39 @@ impl SchemaComponentUpdate {
         0 != unsafe { Schema_IsComponentUpdateFieldCleared(self.as_ptr() as *mut _, field) }
     }
 
+    pub fn cleared_fields(&self) -> Vec<FieldId> {
+        unsafe {
+            let count = Schema_GetComponentUpdateClearedFieldCount(self.as_ptr());
+            let mut fields = vec![0; count as usize];
+            Schema_GetComponentUpdateClearedFieldList(self.as_ptr(), fields.as_mut_ptr());
+            fields
+        }
+    }
+
     pub fn add_cleared(&mut self, field: FieldId) {
         unsafe {
             Schema_AddComponentUpdateClearedField(self.as_ptr_mut(), field);
         }
     }
 
+    /// Merges `other` into this update, without needing to know the component's
+    /// generated type.
+    ///
+    /// The result is equivalent to applying this update followed by `other`: fields set in
+    /// `other` take precedence, and events from both updates are retained.
+    pub fn merge(&mut self, other: &SchemaComponentUpdate) -> Result<()> {
+        // Merging empties the source update, so merge from a copy to leave `other` intact.
+        let mut other = Owned::from(other);
+        let success =
+            unsafe { Schema_MergeComponentUpdateIntoUpdate(other.as_ptr_mut(), self.as_ptr_mut()) };
+
+        if success == 0 {
+            return Err(Error::schema_error::<Self>(self.fields().last_error()));
+        }
+
+        Ok(())
+    }
+
     pub fn get_field<T>(&self, field: FieldId) -> Result<Option<T::RustType>>
     where
         T: Field,
@@ -56,6 +86,13 @@ impl SchemaComponentUpdate {
         T::get_update(self, field)
     }
 
+    pub fn get_field_ref<'a, T>(&'a self, field: FieldId) -> Result<Option<T::RefType>>
+    where
+        T: BorrowedField<'a>,
+    {
+        T::get_update_ref(self, field)
+    }
+
     pub fn add_field<T>(&mut self, field: FieldId, value: &Option<T::RustType>)
     where
         T: Field,
@@ -105,5 +142,31 @@ impl ToOwned for SchemaComponentUpdate {
 
 #[cfg(test)]
 mod tests {
+    use crate::schema::*;
+
     pointer_type_tests!(super::SchemaComponentUpdate);
+
+    #[test]
+    fn merge_combines_updates() {
+        let mut first = SchemaComponentUpdate::new();
+        first.fields_mut().add::<SchemaInt32>(1, &1);
+        first.fields_mut().add::<SchemaInt32>(2, &2);
+        first.events_mut().add_object(1);
+
+        let mut second = SchemaComponentUpdate::new();
+        second.fields_mut().add::<SchemaInt32>(1, &10);
+        second.add_cleared(3);
+        second.events_mut().add_object(1);
+
+        first.merge(&second).expect("Failed to merge updates");
+
+        assert_eq!(10, first.fields().get::<SchemaInt32>(1).unwrap());
+        assert_eq!(2, first.fields().get::<SchemaInt32>(2).unwrap());
+        assert!(first.is_field_cleared(3));
+        assert_eq!(2, first.events().object_count(1));
+
+        // The source update is left untouched.
+        assert_eq!(10, second.fields().get::<SchemaInt32>(1).unwrap());
+        assert!(second.is_field_cleared(3));
+    }
 }
@@ -0,0 +1,576 @@
+use crate::{commands::CommandIndex, component::ComponentId, schema::FieldId};
+use std::collections::{BTreeMap, HashMap};
+
+/// The primitive types defined by schemalang.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PrimitiveType {
+    Int32,
+    Int64,
+    Uint32,
+    Uint64,
+    Sint32,
+    Sint64,
+    Fixed32,
+    Fixed64,
+    Sfixed32,
+    Sfixed64,
+    Bool,
+    Float,
+    Double,
+    String,
+    EntityId,
+    Bytes,
+    Entity,
+}
+
+/// The type of a single value, such as the value of a singular field or an element of
+/// a `list` field.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ValueType {
+    Primitive(PrimitiveType),
+
+    /// A schema enum, identified by its fully qualified name.
+    Enum(String),
+
+    /// A schema type, identified by its fully qualified name.
+    Type(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FieldType {
+    Singular(ValueType),
+    Option(ValueType),
+    List(ValueType),
+    Map { key: ValueType, value: ValueType },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FieldDescriptor {
+    pub name: String,
+    pub field_id: FieldId,
+    pub field_type: FieldType,
+}
+
+/// Describes the fields of a schema type at runtime.
+///
+/// The generated code describes schema types statically, through the [`ObjectField`]
+/// trait. Type descriptors describe the same types at runtime, which allows tooling to
+/// work with schema data without generated code for every project, for example by
+/// reading it into a [`SchemaObjectValue`].
+///
+/// [`ObjectField`]: trait.ObjectField.html
+/// [`SchemaObjectValue`]: struct.SchemaObjectValue.html
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TypeDescriptor {
+    pub qualified_name: String,
+    pub fields: Vec<FieldDescriptor>,
+}
+
+impl TypeDescriptor {
+    /// Returns the field with the given name, or `None` if the type has no such field.
+    pub fn field(&self, name: &str) -> Option<&FieldDescriptor> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    pub fn field_by_id(&self, field_id: FieldId) -> Option<&FieldDescriptor> {
+        self.fields.iter().find(|field| field.field_id == field_id)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EnumValueDescriptor {
+    pub name: String,
+    pub value: u32,
+}
+
+/// Describes the values of a schema enum at runtime.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EnumDescriptor {
+    pub qualified_name: String,
+    pub values: Vec<EnumValueDescriptor>,
+}
+
+impl EnumDescriptor {
+    /// Returns the name of the enum value with the serialized representation `value`.
+    pub fn value_name(&self, value: u32) -> Option<&str> {
+        self.values
+            .iter()
+            .find(|enum_value| enum_value.value == value)
+            .map(|enum_value| enum_value.name.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EventDescriptor {
+    pub name: String,
+    pub event_index: FieldId,
+
+    /// The fully qualified name of the event's schema type.
+    pub type_name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CommandDescriptor {
+    pub name: String,
+    pub command_index: CommandIndex,
+
+    /// The fully qualified name of the command's request type.
+    pub request_type: String,
+
+    /// The fully qualified name of the command's response type.
+    pub response_type: String,
+}
+
+/// Describes a component at runtime, including its fields, events and commands.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ComponentDescriptor {
+    pub qualified_name: String,
+    pub component_id: ComponentId,
+    pub fields: Vec<FieldDescriptor>,
+    pub events: Vec<EventDescriptor>,
+    pub commands: Vec<CommandDescriptor>,
+}
+
+impl ComponentDescriptor {
+    /// Returns the field with the given name, or `None` if the component has no such
+    /// field.
+    pub fn field(&self, name: &str) -> Option<&FieldDescriptor> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    pub fn field_by_id(&self, field_id: FieldId) -> Option<&FieldDescriptor> {
+        self.fields.iter().find(|field| field.field_id == field_id)
+    }
+
+    pub fn event(&self, event_index: FieldId) -> Option<&EventDescriptor> {
+        self.events
+            .iter()
+            .find(|event| event.event_index == event_index)
+    }
+
+    pub fn command(&self, command_index: CommandIndex) -> Option<&CommandDescriptor> {
+        self.commands
+            .iter()
+            .find(|command| command.command_index == command_index)
+    }
+
+    /// Returns a [`TypeDescriptor`] for the component's data, using the component's
+    /// qualified name.
+    ///
+    /// [`TypeDescriptor`]: struct.TypeDescriptor.html
+    pub fn data_type(&self) -> TypeDescriptor {
+        TypeDescriptor {
+            qualified_name: self.qualified_name.clone(),
+            fields: self.fields.clone(),
+        }
+    }
+}
+
+/// A source of [`TypeDescriptor`]s, used to resolve the types referenced by a field.
+///
+/// Registries may also describe enums and components, which lets tooling display enum
+/// values by name and look up the schema for a component from its ID.
+///
+/// [`TypeDescriptor`]: struct.TypeDescriptor.html
+pub trait TypeRegistry {
+    fn type_descriptor(&self, qualified_name: &str) -> Option<&TypeDescriptor>;
+
+    fn enum_descriptor(&self, _qualified_name: &str) -> Option<&EnumDescriptor> {
+        None
+    }
+
+    fn component_descriptor(&self, _component_id: ComponentId) -> Option<&ComponentDescriptor> {
+        None
+    }
+}
+
+/// A [`TypeRegistry`] holding a set of type, enum and component descriptors.
+///
+/// With the `schema-bundle` feature enabled, a `SchemaTypes` can be built from the JSON
+/// schema bundle used by the code generator, either with `SchemaTypes::from_bundle_json`
+/// or from an already loaded `SchemaBundle`. The bundle describes every type, enum and
+/// component in a project's schema, which allows debugging tools to inspect any
+/// component by its ID without generated code:
+///
+/// ```
+/// use spatialos_sdk::{component::ComponentId, schema::*};
+///
+/// fn print_component(types: &SchemaTypes, component_id: ComponentId, data: &SchemaComponentData) {
+///     match SchemaObjectValue::read_component(types, component_id, data) {
+///         Ok(value) => println!("{}", value.display(types)),
+///         Err(e) => eprintln!("Failed to read component {}: {}", component_id, e),
+///     }
+/// }
+/// ```
+///
+/// The data of each component is also registered as a type, using the component's
+/// qualified name.
+///
+/// [`TypeRegistry`]: trait.TypeRegistry.html
+#[derive(Debug, Clone, Default)]
+pub struct SchemaTypes {
+    types: HashMap<String, TypeDescriptor>,
+    enums: HashMap<String, EnumDescriptor>,
+    components: BTreeMap<ComponentId, ComponentDescriptor>,
+}
+
+impl SchemaTypes {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds `descriptor` to the registry, replacing any existing descriptor for a type
+    /// with the same name.
+    pub fn add_type(&mut self, descriptor: TypeDescriptor) {
+        self.types
+            .insert(descriptor.qualified_name.clone(), descriptor);
+    }
+
+    /// Adds `descriptor` to the registry, replacing any existing descriptor for an enum
+    /// with the same name.
+    pub fn add_enum(&mut self, descriptor: EnumDescriptor) {
+        self.enums
+            .insert(descriptor.qualified_name.clone(), descriptor);
+    }
+
+    /// Adds `descriptor` to the registry, along with a type describing the component's
+    /// data. Any existing descriptor for a component with the same ID is replaced.
+    pub fn add_component(&mut self, descriptor: ComponentDescriptor) {
+        self.add_type(descriptor.data_type());
+        self.components.insert(descriptor.component_id, descriptor);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &TypeDescriptor> {
+        self.types.values()
+    }
+
+    pub fn enums(&self) -> impl Iterator<Item = &EnumDescriptor> {
+        self.enums.values()
+    }
+
+    /// Returns an iterator over every registered component, in component ID order.
+    pub fn components(&self) -> impl Iterator<Item = &ComponentDescriptor> {
+        self.components.values()
+    }
+
+    /// Returns the component with the given qualified name, if there is one.
+    pub fn component_by_name(&self, qualified_name: &str) -> Option<&ComponentDescriptor> {
+        self.components()
+            .find(|component| component.qualified_name == qualified_name)
+    }
+}
+
+impl TypeRegistry for SchemaTypes {
+    fn type_descriptor(&self, qualified_name: &str) -> Option<&TypeDescriptor> {
+        self.types.get(qualified_name)
+    }
+
+    fn enum_descriptor(&self, qualified_name: &str) -> Option<&EnumDescriptor> {
+        self.enums.get(qualified_name)
+    }
+
+    fn component_descriptor(&self, component_id: ComponentId) -> Option<&ComponentDescriptor> {
+        self.components.get(&component_id)
+    }
+}
+
+#[cfg(feature = "schema-bundle")]
+mod schema_bundle {
+    use super::*;
+    use spatialos_sdk_code_generator::schema_bundle::{
+        self, ComponentDefinition_CommandDefinition, ComponentDefinition_EventDefinition,
+        EnumDefinition, FieldDefinition, FieldDefinition_FieldType, SchemaBundle, TypeDefinition,
+        TypeReference,
+    };
+    use std::convert::TryFrom;
+
+    impl SchemaTypes {
+        /// Builds a `SchemaTypes` from the JSON schema bundle produced by the schema
+        /// compiler's `--bundle_json_out` option.
+        ///
+        /// This is the same bundle used by the code generator. The binary bundle loaded by
+        /// [`Bundle`] can't be used here, as the C API doesn't expose the types it contains.
+        ///
+        /// [`Bundle`]: struct.Bundle.html
+        pub fn from_bundle_json(json: &str) -> Result<Self, String> {
+            let bundle = schema_bundle::load_bundle(json)
+                .map_err(|e| format!("Failed to load schema bundle: {}", e))?;
+            SchemaTypes::try_from(&bundle)
+        }
+    }
+
+    impl TryFrom<&SchemaBundle> for SchemaTypes {
+        type Error = String;
+
+        fn try_from(bundle: &SchemaBundle) -> Result<Self, Self::Error> {
+            let mut types = SchemaTypes::new();
+            for file in &bundle.schema_files {
+                for enum_def in &file.enums {
+                    types.add_enum(EnumDescriptor::from(enum_def));
+                }
+
+                for type_def in &file.types {
+                    types.add_type(TypeDescriptor::try_from(type_def)?);
+                }
+            }
+
+            // Components either declare their fields inline or reuse the fields of a
+            // data type, so they're registered once every type is known.
+            for file in &bundle.schema_files {
+                for component in &file.components {
+                    let fields = match &component.data_definition {
+                        Some(data_definition) => types
+                            .type_descriptor(data_definition)
+                            .map(|data_type| data_type.fields.clone())
+                            .ok_or_else(|| {
+                                format!(
+                                    "Unknown data type {} of component {}",
+                                    data_definition, component.qualified_name
+                                )
+                            })?,
+                        None => component
+                            .fields
+                            .iter()
+                            .map(FieldDescriptor::try_from)
+                            .collect::<Result<_, _>>()
+                            .map_err(|e| {
+                                format!("{} of component {}", e, component.qualified_name)
+                            })?,
+                    };
+
+                    types.add_component(ComponentDescriptor {
+                        qualified_name: component.qualified_name.clone(),
+                        component_id: component.component_id,
+                        fields,
+                        events: component.events.iter().map(EventDescriptor::from).collect(),
+                        commands: component
+                            .commands
+                            .iter()
+                            .map(CommandDescriptor::from)
+                            .collect(),
+                    });
+                }
+            }
+
+            Ok(types)
+        }
+    }
+
+    impl From<&EnumDefinition> for EnumDescriptor {
+        fn from(enum_def: &EnumDefinition) -> Self {
+            EnumDescriptor {
+                qualified_name: enum_def.qualified_name.clone(),
+                values: enum_def
+                    .values
+                    .iter()
+                    .map(|value| EnumValueDescriptor {
+                        name: value.name.clone(),
+                        value: value.value,
+                    })
+                    .collect(),
+            }
+        }
+    }
+
+    impl From<&ComponentDefinition_EventDefinition> for EventDescriptor {
+        fn from(event: &ComponentDefinition_EventDefinition) -> Self {
+            EventDescriptor {
+                name: event.name.clone(),
+                event_index: event.event_index,
+                type_name: event.type_reference.clone(),
+            }
+        }
+    }
+
+    impl From<&ComponentDefinition_CommandDefinition> for CommandDescriptor {
+        fn from(command: &ComponentDefinition_CommandDefinition) -> Self {
+            CommandDescriptor {
+                name: command.name.clone(),
+                command_index: command.command_index,
+                request_type: command.request_type.clone(),
+                response_type: command.response_type.clone(),
+            }
+        }
+    }
+
+    impl TryFrom<&TypeDefinition> for TypeDescriptor {
+        type Error = String;
+
+        fn try_from(type_def: &TypeDefinition) -> Result<Self, Self::Error> {
+            let fields = type_def
+                .fields
+                .iter()
+                .map(FieldDescriptor::try_from)
+                .collect::<Result<_, _>>()
+                .map_err(|e| format!("{} of type {}", e, type_def.qualified_name))?;
+
+            Ok(TypeDescriptor {
+                qualified_name: type_def.qualified_name.clone(),
+                fields,
+            })
+        }
+    }
+
+    impl TryFrom<&FieldDefinition> for FieldDescriptor {
+        type Error = String;
+
+        fn try_from(field: &FieldDefinition) -> Result<Self, Self::Error> {
+            let value_type = |type_reference: &TypeReference| {
+                ValueType::try_from(type_reference)
+                    .map_err(|e| format!("{} in field '{}'", e, field.name))
+            };
+
+            let field_type = match &field.field_type {
+                FieldDefinition_FieldType::Singular { type_reference } => {
+                    FieldType::Singular(value_type(type_reference)?)
+                }
+                FieldDefinition_FieldType::Option { inner_type } => {
+                    FieldType::Option(value_type(inner_type)?)
+                }
+                FieldDefinition_FieldType::List { inner_type } => {
+                    FieldType::List(value_type(inner_type)?)
+                }
+                FieldDefinition_FieldType::Map {
+                    key_type,
+                    value_type: map_value_type,
+                } => FieldType::Map {
+                    key: value_type(key_type)?,
+                    value: value_type(map_value_type)?,
+                },
+            };
+
+            Ok(FieldDescriptor {
+                name: field.name.clone(),
+                field_id: field.field_id,
+                field_type,
+            })
+        }
+    }
+
+    impl TryFrom<&TypeReference> for ValueType {
+        type Error = String;
+
+        fn try_from(type_reference: &TypeReference) -> Result<Self, Self::Error> {
+            Ok(match type_reference {
+                TypeReference::Primitive(primitive) => {
+                    ValueType::Primitive(PrimitiveType::try_from(primitive)?)
+                }
+                TypeReference::Enum(name) => ValueType::Enum(name.clone()),
+                TypeReference::Type(name) => ValueType::Type(name.clone()),
+            })
+        }
+    }
+
+    impl TryFrom<&schema_bundle::PrimitiveType> for PrimitiveType {
+        type Error = String;
+
+        fn try_from(primitive: &schema_bundle::PrimitiveType) -> Result<Self, Self::Error> {
+            use schema_bundle::PrimitiveType as Bundle;
+
+            Ok(match primitive {
+                Bundle::Invalid => return Err("Encountered invalid primitive".to_string()),
+                Bundle::Int32 => PrimitiveType::Int32,
+                Bundle::Int64 => PrimitiveType::Int64,
+                Bundle::Uint32 => PrimitiveType::Uint32,
+                Bundle::Uint64 => PrimitiveType::Uint64,
+                Bundle::Sint32 => PrimitiveType::Sint32,
+                Bundle::Sint64 => PrimitiveType::Sint64,
+                Bundle::Fixed32 => PrimitiveType::Fixed32,
+                Bundle::Fixed64 => PrimitiveType::Fixed64,
+                Bundle::Sfixed32 => PrimitiveType::Sfixed32,
+                Bundle::Sfixed64 => PrimitiveType::Sfixed64,
+                Bundle::Bool => PrimitiveType::Bool,
+                Bundle::Float => PrimitiveType::Float,
+                Bundle::Double => PrimitiveType::Double,
+                Bundle::String => PrimitiveType::String,
+                Bundle::EntityId => PrimitiveType::EntityId,
+                Bundle::Bytes => PrimitiveType::Bytes,
+                Bundle::Entity => PrimitiveType::Entity,
+            })
+        }
+    }
+}
+
+#[cfg(all(test, feature = "schema-bundle"))]
+mod test {
+    use super::*;
+    use spatialos_sdk_code_generator::schema_bundle::{
+        self, FieldDefinition_FieldType, PrimitiveType as BundlePrimitive, TypeReference,
+    };
+    use std::convert::TryFrom;
+
+    const TEST_BUNDLE: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../spatialos-sdk-code-generator/data/test.sb.json"
+    ));
+
+    #[test]
+    fn describes_components_from_schema_bundle() {
+        let bundle = schema_bundle::load_bundle(TEST_BUNDLE).unwrap();
+        let types = SchemaTypes::try_from(&bundle).unwrap();
+
+        let example = types.component_descriptor(1000).unwrap();
+        assert_eq!("example.Example", example.qualified_name);
+        assert_eq!(
+            Some(&FieldType::Singular(ValueType::Primitive(
+                PrimitiveType::Float
+            ))),
+            example.field("x").map(|field| &field.field_type)
+        );
+        assert_eq!(
+            "example.CommandData",
+            example.command(1).unwrap().request_type
+        );
+        assert!(types.type_descriptor("example.Example").is_some());
+
+        let test_enum = types.enum_descriptor("example.TestEnum").unwrap();
+        assert_eq!(Some("FIRST"), test_enum.value_name(0));
+        assert_eq!(
+            Some(2002),
+            types
+                .component_by_name("example.EnumTestComponent")
+                .map(|component| component.component_id)
+        );
+    }
+
+    #[test]
+    fn invalid_primitive_in_schema_bundle_is_an_error() {
+        let mut bundle = schema_bundle::load_bundle(TEST_BUNDLE).unwrap();
+        let field = bundle
+            .schema_files
+            .iter_mut()
+            .flat_map(|file| file.types.iter_mut())
+            .flat_map(|type_def| type_def.fields.iter_mut())
+            .next()
+            .expect("Test bundle has no type with fields");
+        field.field_type = FieldDefinition_FieldType::Singular {
+            type_reference: TypeReference::Primitive(BundlePrimitive::Invalid),
+        };
+
+        let error = SchemaTypes::try_from(&bundle).unwrap_err();
+        assert!(error.contains("invalid primitive"), "{}", error);
+    }
+
+    #[test]
+    fn unknown_component_data_type_is_an_error() {
+        let mut bundle = schema_bundle::load_bundle(TEST_BUNDLE).unwrap();
+        let component = bundle
+            .schema_files
+            .iter_mut()
+            .flat_map(|file| file.components.iter_mut())
+            .next()
+            .expect("Test bundle has no components");
+        component.data_definition = Some("example.DoesNotExist".to_owned());
+
+        let error = SchemaTypes::try_from(&bundle).unwrap_err();
+        assert!(error.contains("example.DoesNotExist"), "{}", error);
+    }
+
+    #[test]
+    fn loads_schema_types_from_bundle_json() {
+        let types = SchemaTypes::from_bundle_json(TEST_BUNDLE).unwrap();
+        assert!(types.component_descriptor(1000).is_some());
+
+        assert!(SchemaTypes::from_bundle_json("{}").is_err());
+    }
+}
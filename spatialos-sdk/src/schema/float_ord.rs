@@ -10,6 +10,11 @@ use core::ops::{Add, AddAssign, Deref, DerefMut, Div, DivAssign, Mul, MulAssign,
 /// A wrapper for floats, that implements total equality and ordering
 /// and hashing.
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
 pub struct FloatOrd<T>(pub T);
 
 macro_rules! float_ord_impl {
@@ -1,4 +1,4 @@
-use crate::schema::{DataPointer, Field, FieldId, IndexedField, Result};
+use crate::schema::{BorrowedField, DataPointer, Field, FieldId, IndexedField, Result};
 use crate::utils::cstr_to_string;
 use spatialos_sdk_sys::worker::*;
 use std::marker::PhantomData;
@@ -11,6 +11,15 @@ impl SchemaObject {
         T::get(self, field)
     }
 
+    /// Reads `field` without copying its data out of the object.
+    ///
+    /// See [`BorrowedField`] for more details.
+    ///
+    /// [`BorrowedField`]: trait.BorrowedField.html
+    pub fn get_ref<'a, T: BorrowedField<'a>>(&'a self, field: FieldId) -> Result<T::RefType> {
+        T::get_ref(self, field)
+    }
+
     pub fn get_index<T: IndexedField>(&self, field: FieldId, index: usize) -> Result<T::RustType> {
         T::index(self, field, index)
     }
@@ -82,6 +91,16 @@ impl SchemaObject {
 
         Ok(())
     }
+
+    /// Returns the most recent error reported by the schema library for this object.
+    pub(crate) fn last_error(&self) -> String {
+        let error = unsafe { Schema_GetError(self.as_ptr()) };
+        if error.is_null() {
+            "Unknown schema error".to_string()
+        } else {
+            cstr_to_string(error)
+        }
+    }
 }
 
 unsafe impl DataPointer for SchemaObject {
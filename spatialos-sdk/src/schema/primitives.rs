@@ -106,6 +106,25 @@ macro_rules! impl_primitive_field {
 
                 Ok(result)
             }
+
+            fn copy_list(
+                object: &SchemaObject,
+                field: FieldId,
+                dest: &mut Vec<$rust_type>,
+            ) -> Result<()> {
+                let count = Self::count(object, field);
+                dest.clear();
+                dest.reserve(count);
+
+                // Copy the whole list into the spare capacity of `dest` in one call, and only
+                // then mark the elements as initialized.
+                unsafe {
+                    $schema_get_list(object.as_ptr(), field, dest.as_mut_ptr() as *mut _);
+                    dest.set_len(count);
+                }
+
+                Ok(())
+            }
         }
     };
 }
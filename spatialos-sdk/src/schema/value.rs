@@ -0,0 +1,701 @@
+use crate::{
+    component::ComponentId,
+    entity::Entity,
+    schema::{
+        Error, FieldId, FieldType, FloatOrd, IndexedField, PrimitiveType, Result, SchemaBool,
+        SchemaBytes, SchemaComponentData, SchemaDouble, SchemaEntity, SchemaEntityId, SchemaEnum,
+        SchemaFixed32, SchemaFixed64, SchemaFloat, SchemaInt32, SchemaInt64, SchemaObject,
+        SchemaSfixed32, SchemaSfixed64, SchemaSint32, SchemaSint64, SchemaString, SchemaUint32,
+        SchemaUint64, TypeRegistry, ValueType,
+    },
+    EntityId,
+};
+use spatialos_sdk_sys::worker::{SCHEMA_MAP_KEY_FIELD_ID, SCHEMA_MAP_VALUE_FIELD_ID};
+use std::{
+    collections::{btree_map, BTreeMap},
+    fmt::{self, Display, Formatter},
+};
+
+/// A dynamically typed schema value.
+///
+/// Each primitive type has its own variant, so that a value is written back using the
+/// same encoding that it was read with. `option`, `list` and `map` fields are
+/// represented by the `Option`, `List` and `Map` variants, and the values of schema
+/// types by `Object`. Enum values are stored as their serialized `u32` representation.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SchemaValue {
+    Int32(i32),
+    Int64(i64),
+    Uint32(u32),
+    Uint64(u64),
+    Sint32(i32),
+    Sint64(i64),
+    Fixed32(u32),
+    Fixed64(u64),
+    Sfixed32(i32),
+    Sfixed64(i64),
+    Bool(bool),
+    Float(FloatOrd<f32>),
+    Double(FloatOrd<f64>),
+    String(String),
+    EntityId(EntityId),
+    Bytes(Vec<u8>),
+    Entity(Entity),
+    Enum(u32),
+    Object(SchemaObjectValue),
+    Option(Option<Box<SchemaValue>>),
+    List(Vec<SchemaValue>),
+    Map(BTreeMap<SchemaValue, SchemaValue>),
+}
+
+impl SchemaValue {
+    /// Writes the value to `field` of `object`.
+    ///
+    /// `None` options, empty lists and empty maps don't add anything to `object`.
+    pub fn write(&self, object: &mut SchemaObject, field: FieldId) {
+        match self {
+            SchemaValue::Int32(value) => object.add::<SchemaInt32>(field, value),
+            SchemaValue::Int64(value) => object.add::<SchemaInt64>(field, value),
+            SchemaValue::Uint32(value) => object.add::<SchemaUint32>(field, value),
+            SchemaValue::Uint64(value) => object.add::<SchemaUint64>(field, value),
+            SchemaValue::Sint32(value) => object.add::<SchemaSint32>(field, value),
+            SchemaValue::Sint64(value) => object.add::<SchemaSint64>(field, value),
+            SchemaValue::Fixed32(value) => object.add::<SchemaFixed32>(field, value),
+            SchemaValue::Fixed64(value) => object.add::<SchemaFixed64>(field, value),
+            SchemaValue::Sfixed32(value) => object.add::<SchemaSfixed32>(field, value),
+            SchemaValue::Sfixed64(value) => object.add::<SchemaSfixed64>(field, value),
+            SchemaValue::Bool(value) => object.add::<SchemaBool>(field, value),
+            SchemaValue::Float(value) => object.add::<SchemaFloat>(field, value),
+            SchemaValue::Double(value) => object.add::<SchemaDouble>(field, value),
+            SchemaValue::String(value) => object.add::<SchemaString>(field, value),
+            SchemaValue::EntityId(value) => object.add::<SchemaEntityId>(field, value),
+            SchemaValue::Bytes(value) => object.add::<SchemaBytes>(field, value),
+            SchemaValue::Entity(value) => object.add::<SchemaEntity>(field, value),
+            SchemaValue::Enum(value) => object.add::<SchemaEnum>(field, value),
+            SchemaValue::Object(value) => value.write(object.add_object(field)),
+
+            SchemaValue::Option(value) => {
+                if let Some(value) = value {
+                    value.write(object, field);
+                }
+            }
+
+            SchemaValue::List(values) => {
+                for value in values {
+                    value.write(object, field);
+                }
+            }
+
+            // Map fields are represented in schema as a list of pairs of key and value.
+            // See the `Map` field implementation for more details.
+            SchemaValue::Map(entries) => {
+                for (key, value) in entries {
+                    let pair = object.add_object(field);
+                    key.write(pair, SCHEMA_MAP_KEY_FIELD_ID);
+                    value.write(pair, SCHEMA_MAP_VALUE_FIELD_ID);
+                }
+            }
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&SchemaObjectValue> {
+        match self {
+            SchemaValue::Object(object) => Some(object),
+            _ => None,
+        }
+    }
+
+    pub fn as_object_mut(&mut self) -> Option<&mut SchemaObjectValue> {
+        match self {
+            SchemaValue::Object(object) => Some(object),
+            _ => None,
+        }
+    }
+
+    fn read_field<R>(
+        types: &R,
+        field_type: &FieldType,
+        object: &SchemaObject,
+        field: FieldId,
+    ) -> Result<Self>
+    where
+        R: TypeRegistry + ?Sized,
+    {
+        match field_type {
+            FieldType::Singular(value_type) => Self::read(types, value_type, object, field, None),
+
+            FieldType::Option(value_type) => {
+                if Self::count(value_type, object, field) > 0 {
+                    let value = Self::read(types, value_type, object, field, None)?;
+                    Ok(SchemaValue::Option(Some(Box::new(value))))
+                } else {
+                    Ok(SchemaValue::Option(None))
+                }
+            }
+
+            FieldType::List(value_type) => {
+                let count = Self::count(value_type, object, field);
+                (0..count)
+                    .map(|index| {
+                        Self::read(types, value_type, object, field, Some(index))
+                            .map_err(Error::at_index::<Self>(field, index))
+                    })
+                    .collect::<Result<_>>()
+                    .map(SchemaValue::List)
+            }
+
+            FieldType::Map { key, value } => {
+                let mut entries = BTreeMap::new();
+                for index in 0..object.object_count(field) {
+                    let pair = object.index_object(field, index);
+                    let key = Self::read(types, key, pair, SCHEMA_MAP_KEY_FIELD_ID, None)
+                        .map_err(Error::at_index::<Self>(field, index))?;
+                    let value = Self::read(types, value, pair, SCHEMA_MAP_VALUE_FIELD_ID, None)
+                        .map_err(Error::at_index::<Self>(field, index))?;
+                    entries.insert(key, value);
+                }
+
+                Ok(SchemaValue::Map(entries))
+            }
+        }
+    }
+
+    /// Reads a single value of type `value_type` from `field`. If `index` is `None`,
+    /// the value is read in the same way as a singular field.
+    fn read<R>(
+        types: &R,
+        value_type: &ValueType,
+        object: &SchemaObject,
+        field: FieldId,
+        index: Option<usize>,
+    ) -> Result<Self>
+    where
+        R: TypeRegistry + ?Sized,
+    {
+        let primitive = match value_type {
+            ValueType::Primitive(primitive) => primitive,
+
+            ValueType::Enum(_) => {
+                return get::<SchemaEnum>(object, field, index).map(SchemaValue::Enum)
+            }
+
+            ValueType::Type(qualified_name) => {
+                let object = match index {
+                    Some(index) => object.index_object(field, index),
+                    None => object.get_object(field),
+                };
+
+                return SchemaObjectValue::read(types, qualified_name, object)
+                    .map(SchemaValue::Object);
+            }
+        };
+
+        match primitive {
+            PrimitiveType::Int32 => {
+                get::<SchemaInt32>(object, field, index).map(SchemaValue::Int32)
+            }
+            PrimitiveType::Int64 => {
+                get::<SchemaInt64>(object, field, index).map(SchemaValue::Int64)
+            }
+            PrimitiveType::Uint32 => {
+                get::<SchemaUint32>(object, field, index).map(SchemaValue::Uint32)
+            }
+            PrimitiveType::Uint64 => {
+                get::<SchemaUint64>(object, field, index).map(SchemaValue::Uint64)
+            }
+            PrimitiveType::Sint32 => {
+                get::<SchemaSint32>(object, field, index).map(SchemaValue::Sint32)
+            }
+            PrimitiveType::Sint64 => {
+                get::<SchemaSint64>(object, field, index).map(SchemaValue::Sint64)
+            }
+            PrimitiveType::Fixed32 => {
+                get::<SchemaFixed32>(object, field, index).map(SchemaValue::Fixed32)
+            }
+            PrimitiveType::Fixed64 => {
+                get::<SchemaFixed64>(object, field, index).map(SchemaValue::Fixed64)
+            }
+            PrimitiveType::Sfixed32 => {
+                get::<SchemaSfixed32>(object, field, index).map(SchemaValue::Sfixed32)
+            }
+            PrimitiveType::Sfixed64 => {
+                get::<SchemaSfixed64>(object, field, index).map(SchemaValue::Sfixed64)
+            }
+            PrimitiveType::Bool => get::<SchemaBool>(object, field, index).map(SchemaValue::Bool),
+            PrimitiveType::Float => {
+                get::<SchemaFloat>(object, field, index).map(SchemaValue::Float)
+            }
+            PrimitiveType::Double => {
+                get::<SchemaDouble>(object, field, index).map(SchemaValue::Double)
+            }
+            PrimitiveType::String => {
+                get::<SchemaString>(object, field, index).map(SchemaValue::String)
+            }
+            PrimitiveType::EntityId => {
+                get::<SchemaEntityId>(object, field, index).map(SchemaValue::EntityId)
+            }
+            PrimitiveType::Bytes => {
+                get::<SchemaBytes>(object, field, index).map(SchemaValue::Bytes)
+            }
+            PrimitiveType::Entity => {
+                get::<SchemaEntity>(object, field, index).map(SchemaValue::Entity)
+            }
+        }
+    }
+
+    fn count(value_type: &ValueType, object: &SchemaObject, field: FieldId) -> usize {
+        let primitive = match value_type {
+            ValueType::Primitive(primitive) => primitive,
+            ValueType::Enum(_) => return object.count::<SchemaEnum>(field),
+            ValueType::Type(_) => return object.object_count(field),
+        };
+
+        match primitive {
+            PrimitiveType::Int32 => object.count::<SchemaInt32>(field),
+            PrimitiveType::Int64 => object.count::<SchemaInt64>(field),
+            PrimitiveType::Uint32 => object.count::<SchemaUint32>(field),
+            PrimitiveType::Uint64 => object.count::<SchemaUint64>(field),
+            PrimitiveType::Sint32 => object.count::<SchemaSint32>(field),
+            PrimitiveType::Sint64 => object.count::<SchemaSint64>(field),
+            PrimitiveType::Fixed32 => object.count::<SchemaFixed32>(field),
+            PrimitiveType::Fixed64 => object.count::<SchemaFixed64>(field),
+            PrimitiveType::Sfixed32 => object.count::<SchemaSfixed32>(field),
+            PrimitiveType::Sfixed64 => object.count::<SchemaSfixed64>(field),
+            PrimitiveType::Bool => object.count::<SchemaBool>(field),
+            PrimitiveType::Float => object.count::<SchemaFloat>(field),
+            PrimitiveType::Double => object.count::<SchemaDouble>(field),
+            PrimitiveType::String => object.count::<SchemaString>(field),
+            PrimitiveType::EntityId => object.count::<SchemaEntityId>(field),
+            PrimitiveType::Bytes => object.count::<SchemaBytes>(field),
+            PrimitiveType::Entity => object.count::<SchemaEntity>(field),
+        }
+    }
+}
+
+fn get<T: IndexedField>(
+    object: &SchemaObject,
+    field: FieldId,
+    index: Option<usize>,
+) -> Result<T::RustType> {
+    match index {
+        Some(index) => object.get_index::<T>(field, index),
+        None => object.get::<T>(field),
+    }
+}
+
+/// The dynamically typed value of a schema type, with a [`SchemaValue`] for each field.
+///
+/// A `SchemaObjectValue` is read from a [`SchemaObject`] using the [`TypeDescriptor`]s
+/// in a [`TypeRegistry`], and can then be inspected, edited and written back to a
+/// `SchemaObject` without any generated code.
+///
+/// # Examples
+///
+/// ```no_run
+/// use spatialos_sdk::schema::*;
+///
+/// # let types: SchemaTypes = unimplemented!();
+/// # let data: Owned<SchemaComponentData> = unimplemented!();
+/// let mut position = SchemaObjectValue::read(&types, "improbable.Position", data.fields())?;
+/// if let Some(coords) = position.get_mut(1).and_then(SchemaValue::as_object_mut) {
+///     coords.set(2, SchemaValue::Double(FloatOrd(10.0)));
+/// }
+///
+/// let mut edited = SchemaComponentData::new();
+/// position.write(edited.fields_mut());
+/// # Ok::<(), Error>(())
+/// ```
+///
+/// [`SchemaValue`]: enum.SchemaValue.html
+/// [`SchemaObject`]: struct.SchemaObject.html
+/// [`TypeDescriptor`]: struct.TypeDescriptor.html
+/// [`TypeRegistry`]: trait.TypeRegistry.html
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SchemaObjectValue {
+    qualified_name: String,
+    fields: BTreeMap<FieldId, SchemaValue>,
+}
+
+impl SchemaObjectValue {
+    /// Creates a value of the type `qualified_name` with no fields set.
+    pub fn new<T: Into<String>>(qualified_name: T) -> Self {
+        SchemaObjectValue {
+            qualified_name: qualified_name.into(),
+            fields: BTreeMap::new(),
+        }
+    }
+
+    /// Reads every field of the type `qualified_name` from `object`.
+    ///
+    /// Returns an error if `qualified_name`, or any type referenced by its fields,
+    /// isn't in `types`.
+    pub fn read<R>(types: &R, qualified_name: &str, object: &SchemaObject) -> Result<Self>
+    where
+        R: TypeRegistry + ?Sized,
+    {
+        let descriptor = types.type_descriptor(qualified_name).ok_or_else(|| {
+            Error::schema_error::<Self>(format!("Unknown schema type {}", qualified_name))
+        })?;
+
+        let mut value = SchemaObjectValue::new(qualified_name);
+        for field in &descriptor.fields {
+            let field_value =
+                SchemaValue::read_field(types, &field.field_type, object, field.field_id)
+                    .map_err(Error::at_field::<Self>(field.field_id))?;
+            value.fields.insert(field.field_id, field_value);
+        }
+
+        Ok(value)
+    }
+
+    /// Reads the data of the component with the ID `component_id`.
+    ///
+    /// Returns an error if the component, or any type referenced by its fields, isn't
+    /// in `types`.
+    pub fn read_component<R>(
+        types: &R,
+        component_id: ComponentId,
+        data: &SchemaComponentData,
+    ) -> Result<Self>
+    where
+        R: TypeRegistry + ?Sized,
+    {
+        let component = types.component_descriptor(component_id).ok_or_else(|| {
+            Error::schema_error::<Self>(format!("Unknown component ID {}", component_id))
+        })?;
+
+        Self::read(types, &component.qualified_name, data.fields())
+    }
+
+    /// Returns an object that formats the value using the field and enum value names
+    /// in `types`.
+    ///
+    /// Fields that `types` doesn't describe are displayed by their field ID, and enum
+    /// values that it doesn't describe by their serialized value.
+    pub fn display<'a, R>(&'a self, types: &'a R) -> DisplayObject<'a, R>
+    where
+        R: TypeRegistry + ?Sized,
+    {
+        DisplayObject { value: self, types }
+    }
+
+    /// Writes every field to `object`.
+    pub fn write(&self, object: &mut SchemaObject) {
+        for (field, value) in &self.fields {
+            value.write(object, *field);
+        }
+    }
+
+    pub fn qualified_name(&self) -> &str {
+        &self.qualified_name
+    }
+
+    pub fn get(&self, field: FieldId) -> Option<&SchemaValue> {
+        self.fields.get(&field)
+    }
+
+    pub fn get_mut(&mut self, field: FieldId) -> Option<&mut SchemaValue> {
+        self.fields.get_mut(&field)
+    }
+
+    /// Sets the value of `field`, returning the previous value if there was one.
+    pub fn set(&mut self, field: FieldId, value: SchemaValue) -> Option<SchemaValue> {
+        self.fields.insert(field, value)
+    }
+
+    pub fn remove(&mut self, field: FieldId) -> Option<SchemaValue> {
+        self.fields.remove(&field)
+    }
+
+    /// Returns an iterator over the field IDs and values of the object, in field ID order.
+    pub fn fields(&self) -> btree_map::Iter<'_, FieldId, SchemaValue> {
+        self.fields.iter()
+    }
+}
+
+/// Formats a [`SchemaObjectValue`] with field names, as returned by
+/// [`SchemaObjectValue::display`].
+///
+/// [`SchemaObjectValue`]: struct.SchemaObjectValue.html
+/// [`SchemaObjectValue::display`]: struct.SchemaObjectValue.html#method.display
+pub struct DisplayObject<'a, R: ?Sized> {
+    value: &'a SchemaObjectValue,
+    types: &'a R,
+}
+
+impl<'a, R> Display for DisplayObject<'a, R>
+where
+    R: TypeRegistry + ?Sized,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fmt_object(f, self.types, self.value)
+    }
+}
+
+fn fmt_object<R>(f: &mut Formatter<'_>, types: &R, object: &SchemaObjectValue) -> fmt::Result
+where
+    R: TypeRegistry + ?Sized,
+{
+    let descriptor = types.type_descriptor(&object.qualified_name);
+
+    write!(f, "{} {{", object.qualified_name)?;
+    for (index, (field_id, value)) in object.fields.iter().enumerate() {
+        if index > 0 {
+            write!(f, ",")?;
+        }
+
+        match descriptor.and_then(|descriptor| descriptor.field_by_id(*field_id)) {
+            Some(field) => {
+                write!(f, " {}: ", field.name)?;
+                fmt_field(f, types, Some(&field.field_type), value)?;
+            }
+            None => {
+                write!(f, " {}: ", field_id)?;
+                fmt_field(f, types, None, value)?;
+            }
+        }
+    }
+
+    if object.fields.is_empty() {
+        write!(f, "}}")
+    } else {
+        write!(f, " }}")
+    }
+}
+
+fn fmt_field<R>(
+    f: &mut Formatter<'_>,
+    types: &R,
+    field_type: Option<&FieldType>,
+    value: &SchemaValue,
+) -> fmt::Result
+where
+    R: TypeRegistry + ?Sized,
+{
+    let (element_type, key_type) = match field_type {
+        Some(FieldType::Singular(value_type))
+        | Some(FieldType::Option(value_type))
+        | Some(FieldType::List(value_type)) => (Some(value_type), None),
+        Some(FieldType::Map { key, value }) => (Some(value), Some(key)),
+        None => (None, None),
+    };
+
+    match value {
+        SchemaValue::Option(None) => write!(f, "None"),
+        SchemaValue::Option(Some(inner)) => fmt_value(f, types, element_type, inner),
+        SchemaValue::List(elements) => {
+            write!(f, "[")?;
+            for (index, element) in elements.iter().enumerate() {
+                if index > 0 {
+                    write!(f, ", ")?;
+                }
+                fmt_value(f, types, element_type, element)?;
+            }
+            write!(f, "]")
+        }
+        SchemaValue::Map(entries) => {
+            write!(f, "{{")?;
+            for (index, (key, value)) in entries.iter().enumerate() {
+                if index > 0 {
+                    write!(f, ", ")?;
+                }
+                fmt_value(f, types, key_type, key)?;
+                write!(f, ": ")?;
+                fmt_value(f, types, element_type, value)?;
+            }
+            write!(f, "}}")
+        }
+        _ => fmt_value(f, types, element_type, value),
+    }
+}
+
+fn fmt_value<R>(
+    f: &mut Formatter<'_>,
+    types: &R,
+    value_type: Option<&ValueType>,
+    value: &SchemaValue,
+) -> fmt::Result
+where
+    R: TypeRegistry + ?Sized,
+{
+    match value {
+        SchemaValue::Int32(value) | SchemaValue::Sint32(value) | SchemaValue::Sfixed32(value) => {
+            write!(f, "{}", value)
+        }
+        SchemaValue::Int64(value) | SchemaValue::Sint64(value) | SchemaValue::Sfixed64(value) => {
+            write!(f, "{}", value)
+        }
+        SchemaValue::Uint32(value) | SchemaValue::Fixed32(value) => write!(f, "{}", value),
+        SchemaValue::Uint64(value) | SchemaValue::Fixed64(value) => write!(f, "{}", value),
+        SchemaValue::Bool(value) => write!(f, "{}", value),
+        SchemaValue::Float(value) => write!(f, "{}", value.0),
+        SchemaValue::Double(value) => write!(f, "{}", value.0),
+        SchemaValue::String(value) => write!(f, "{:?}", value),
+        SchemaValue::EntityId(value) => write!(f, "{}", value.id),
+        SchemaValue::Bytes(value) => write!(f, "{:?}", value),
+        SchemaValue::Entity(value) => write!(f, "{:?}", value),
+        SchemaValue::Enum(value) => {
+            let name = match value_type {
+                Some(ValueType::Enum(enum_name)) => types
+                    .enum_descriptor(enum_name)
+                    .and_then(|descriptor| descriptor.value_name(*value)),
+                _ => None,
+            };
+
+            match name {
+                Some(name) => write!(f, "{}", name),
+                None => write!(f, "{}", value),
+            }
+        }
+        SchemaValue::Object(object) => fmt_object(f, types, object),
+
+        // Nested collections can't be declared in schemalang, but can still be built by
+        // hand, so they're displayed without any type information.
+        SchemaValue::Option(_) | SchemaValue::List(_) | SchemaValue::Map(_) => {
+            fmt_field(f, types, None, value)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::schema::{
+        ComponentDescriptor, EnumDescriptor, EnumValueDescriptor, FieldDescriptor, SchemaTypes,
+        TypeDescriptor,
+    };
+
+    fn field(name: &str, field_id: FieldId, field_type: FieldType) -> FieldDescriptor {
+        FieldDescriptor {
+            name: name.to_owned(),
+            field_id,
+            field_type,
+        }
+    }
+
+    fn types() -> SchemaTypes {
+        let mut types = SchemaTypes::new();
+        types.add_type(TypeDescriptor {
+            qualified_name: "example.Inner".to_owned(),
+            fields: vec![field(
+                "value",
+                1,
+                FieldType::Singular(ValueType::Primitive(PrimitiveType::Sint32)),
+            )],
+        });
+        types.add_type(TypeDescriptor {
+            qualified_name: "example.Outer".to_owned(),
+            fields: vec![
+                field(
+                    "name",
+                    1,
+                    FieldType::Singular(ValueType::Primitive(PrimitiveType::String)),
+                ),
+                field(
+                    "inner",
+                    2,
+                    FieldType::Option(ValueType::Type("example.Inner".to_owned())),
+                ),
+                field(
+                    "ids",
+                    3,
+                    FieldType::List(ValueType::Primitive(PrimitiveType::EntityId)),
+                ),
+                field(
+                    "lookup",
+                    4,
+                    FieldType::Map {
+                        key: ValueType::Primitive(PrimitiveType::Uint32),
+                        value: ValueType::Enum("example.Color".to_owned()),
+                    },
+                ),
+            ],
+        });
+        types.add_enum(EnumDescriptor {
+            qualified_name: "example.Color".to_owned(),
+            values: vec![EnumValueDescriptor {
+                name: "RED".to_owned(),
+                value: 2,
+            }],
+        });
+        types.add_component(ComponentDescriptor {
+            qualified_name: "example.Holder".to_owned(),
+            component_id: 1000,
+            fields: vec![field(
+                "outer",
+                1,
+                FieldType::Singular(ValueType::Type("example.Outer".to_owned())),
+            )],
+            events: Vec::new(),
+            commands: Vec::new(),
+        });
+        types
+    }
+
+    #[test]
+    fn reads_and_writes_nested_values() {
+        let mut data = SchemaComponentData::new();
+        let fields = data.fields_mut();
+        fields.add::<SchemaString>(1, &"outer".to_owned());
+        fields.add_object(2).add::<SchemaSint32>(1, &-5);
+        fields.add_list::<SchemaEntityId>(3, &[EntityId::new(1), EntityId::new(2)]);
+        let pair = fields.add_object(4);
+        pair.add::<SchemaUint32>(SCHEMA_MAP_KEY_FIELD_ID, &7);
+        pair.add::<SchemaEnum>(SCHEMA_MAP_VALUE_FIELD_ID, &2);
+
+        let types = types();
+        let mut value = SchemaObjectValue::read(&types, "example.Outer", data.fields()).unwrap();
+        assert_eq!(Some(&SchemaValue::String("outer".to_owned())), value.get(1));
+
+        match value.get_mut(2) {
+            Some(SchemaValue::Option(Some(inner))) => {
+                let inner = inner.as_object_mut().unwrap();
+                assert_eq!(Some(&SchemaValue::Sint32(-5)), inner.get(1));
+                inner.set(1, SchemaValue::Sint32(10));
+            }
+            _ => panic!("Expected a nested object"),
+        }
+
+        let mut written = SchemaComponentData::new();
+        value.write(written.fields_mut());
+
+        let fields = written.fields();
+        assert_eq!("outer", fields.get::<SchemaString>(1).unwrap());
+        assert_eq!(10, fields.get_object(2).get::<SchemaSint32>(1).unwrap());
+        assert_eq!(
+            vec![EntityId::new(1), EntityId::new(2)],
+            fields.get_list::<SchemaEntityId>(3).unwrap()
+        );
+        assert_eq!(
+            value,
+            SchemaObjectValue::read(&types, "example.Outer", fields).unwrap()
+        );
+    }
+
+    #[test]
+    fn unknown_types_are_rejected() {
+        let data = SchemaComponentData::new();
+        assert!(SchemaObjectValue::read(&types(), "example.Missing", data.fields()).is_err());
+    }
+
+    #[test]
+    fn displays_components_with_field_names() {
+        let mut data = SchemaComponentData::new();
+        let outer = data.fields_mut().add_object(1);
+        outer.add::<SchemaString>(1, &"outer".to_owned());
+        outer.add_list::<SchemaEntityId>(3, &[EntityId::new(5)]);
+        let pair = outer.add_object(4);
+        pair.add::<SchemaUint32>(SCHEMA_MAP_KEY_FIELD_ID, &7);
+        pair.add::<SchemaEnum>(SCHEMA_MAP_VALUE_FIELD_ID, &2);
+
+        let types = types();
+        let value = SchemaObjectValue::read_component(&types, 1000, &data).unwrap();
+        assert_eq!(
+            "example.Holder { outer: example.Outer { name: \"outer\", inner: None, \
+             ids: [5], lookup: {7: RED} } }",
+            value.display(&types).to_string()
+        );
+
+        assert!(SchemaObjectValue::read_component(&types, 1001, &data).is_err());
+    }
+}
@@ -0,0 +1,597 @@
+//! Utilities for testing worker logic without connecting to SpatialOS.
+//!
+//! This module is only available when the `testing` feature is enabled.
+
+use crate::{
+    commands::*,
+    component::*,
+    connection::{Connection, ConnectionStatus},
+    logging::LogLevel,
+    metrics::Metrics,
+    op::{CommandResponseError, OpList, OpListBuilder},
+    schema::{Owned, SchemaComponentData},
+    {Authority, EntityId, RequestId},
+};
+use std::{collections::HashMap, ffi::CString, ffi::NulError, mem};
+
+/// A message sent by a worker through a [`MockConnection`].
+///
+/// [`MockConnection`]: struct.MockConnection.html
+pub enum SentMessage {
+    LogMessage {
+        level: LogLevel,
+        logger_name: String,
+        message: String,
+        entity_id: Option<EntityId>,
+    },
+    Metrics(Metrics),
+    ReserveEntityIdsRequest {
+        request_id: RequestId,
+        payload: ReserveEntityIdsRequest,
+        timeout_millis: Option<u32>,
+    },
+    CreateEntityRequest {
+        request_id: RequestId,
+        payload: CreateEntityRequest,
+        timeout_millis: Option<u32>,
+    },
+    DeleteEntityRequest {
+        request_id: RequestId,
+        payload: DeleteEntityRequest,
+        timeout_millis: Option<u32>,
+    },
+    EntityQueryRequest {
+        request_id: RequestId,
+        payload: EntityQueryRequest,
+        timeout_millis: Option<u32>,
+    },
+    CommandRequest {
+        request_id: RequestId,
+        entity_id: EntityId,
+        request: CommandRequest,
+        timeout_millis: Option<u32>,
+        params: CommandParameters,
+    },
+    CommandResponse {
+        request_id: RequestId,
+        response: CommandResponse,
+    },
+    CommandFailure {
+        request_id: RequestId,
+        message: String,
+    },
+    ComponentUpdate {
+        entity_id: EntityId,
+        update: ComponentUpdate,
+        parameters: UpdateParameters,
+    },
+    AddComponent {
+        entity_id: EntityId,
+        component_id: ComponentId,
+        data: Owned<SchemaComponentData>,
+        parameters: UpdateParameters,
+    },
+    RemoveComponent {
+        entity_id: EntityId,
+        component_id: ComponentId,
+        parameters: UpdateParameters,
+    },
+    AuthorityLossImminentAcknowledgement {
+        entity_id: EntityId,
+        component_id: ComponentId,
+    },
+    ComponentInterest {
+        entity_id: EntityId,
+        interest: ComponentInterest,
+    },
+    Flush,
+}
+
+/// An in-process implementation of [`Connection`] for use in tests.
+///
+/// Every message sent through the connection is recorded, in order, and can be
+/// inspected with [`sent`]. Ops are scripted with the `push_*` methods, and are
+/// returned by the next call to [`get_op_list`].
+///
+/// # Examples
+///
+/// ```
+/// use spatialos_sdk::{connection::Connection, op::WorkerOp, testing::MockConnection, EntityId};
+///
+/// let mut connection = MockConnection::new("test_worker");
+/// connection.push_add_entity(EntityId::new(1));
+///
+/// let ops = connection.get_op_list(0);
+/// for op in &ops {
+///     if let WorkerOp::AddEntity(add_entity) = op {
+///         connection.send_log_message(
+///             spatialos_sdk::logging::LogLevel::Info,
+///             "test",
+///             "Entity added",
+///             Some(add_entity.entity_id),
+///         );
+///     }
+/// }
+///
+/// assert_eq!(1, connection.sent().len());
+/// ```
+///
+/// [`Connection`]: ../connection/trait.Connection.html
+/// [`sent`]: #method.sent
+/// [`get_op_list`]: ../connection/trait.Connection.html#tymethod.get_op_list
+pub struct MockConnection {
+    worker_id: String,
+    worker_attributes: Vec<String>,
+    connection_status: ConnectionStatus,
+    worker_flags: HashMap<String, String>,
+    logging_enabled: bool,
+    next_request_id: i64,
+    sent: Vec<SentMessage>,
+    pending_ops: OpListBuilder,
+}
+
+impl MockConnection {
+    pub fn new<S: Into<String>>(worker_id: S) -> Self {
+        MockConnection {
+            worker_id: worker_id.into(),
+            worker_attributes: Vec::new(),
+            connection_status: Ok(()),
+            worker_flags: HashMap::new(),
+            logging_enabled: false,
+            next_request_id: 1,
+            sent: Vec::new(),
+            pending_ops: OpListBuilder::new(),
+        }
+    }
+
+    pub fn with_worker_attributes(mut self, attributes: Vec<String>) -> Self {
+        self.worker_attributes = attributes;
+        self
+    }
+
+    /// Sets the status returned by `get_connection_status`.
+    pub fn set_connection_status(&mut self, status: ConnectionStatus) {
+        self.connection_status = status;
+    }
+
+    /// Sets the value returned by `get_worker_flag` for the given flag.
+    pub fn set_worker_flag<S: Into<String>, T: Into<String>>(&mut self, name: S, value: T) {
+        self.worker_flags.insert(name.into(), value.into());
+    }
+
+    /// Returns whether protocol logging is currently enabled.
+    pub fn is_logging_enabled(&self) -> bool {
+        self.logging_enabled
+    }
+
+    /// Returns all messages sent through the connection, in the order they were sent.
+    pub fn sent(&self) -> &[SentMessage] {
+        &self.sent
+    }
+
+    /// Removes and returns all messages sent through the connection so far.
+    pub fn take_sent(&mut self) -> Vec<SentMessage> {
+        mem::replace(&mut self.sent, Vec::new())
+    }
+
+    /// Returns the number of ops that will be returned by the next call to
+    /// `get_op_list`.
+    pub fn pending_op_count(&self) -> usize {
+        self.pending_ops.len()
+    }
+
+    /// Returns the builder for the ops that will be returned by the next call to
+    /// `get_op_list`, which can be used to queue any kind of op.
+    pub fn pending_ops_mut(&mut self) -> &mut OpListBuilder {
+        &mut self.pending_ops
+    }
+
+    pub fn push_add_entity(&mut self, entity_id: EntityId) {
+        self.pending_ops.add_entity(entity_id);
+    }
+
+    pub fn push_remove_entity(&mut self, entity_id: EntityId) {
+        self.pending_ops.remove_entity(entity_id);
+    }
+
+    pub fn push_add_component<C: Component>(&mut self, entity_id: EntityId, component: &C) {
+        self.pending_ops.add_component(entity_id, component);
+    }
+
+    pub fn push_remove_component(&mut self, entity_id: EntityId, component_id: ComponentId) {
+        self.pending_ops.remove_component(entity_id, component_id);
+    }
+
+    pub fn push_authority_change(
+        &mut self,
+        entity_id: EntityId,
+        component_id: ComponentId,
+        authority: Authority,
+    ) {
+        self.pending_ops
+            .authority_change(entity_id, component_id, authority);
+    }
+
+    pub fn push_component_update<T: Into<ComponentUpdate>>(
+        &mut self,
+        entity_id: EntityId,
+        update: T,
+    ) {
+        self.pending_ops.component_update(entity_id, update);
+    }
+
+    /// Queues a command request from another worker.
+    pub fn push_command_request<T: Into<CommandRequest>>(
+        &mut self,
+        request_id: RequestId,
+        entity_id: EntityId,
+        caller_worker_id: &str,
+        request: T,
+    ) {
+        self.pending_ops
+            .command_request(request_id, entity_id, caller_worker_id, request);
+    }
+
+    /// Queues a successful response to a command request sent by the worker.
+    pub fn push_command_response<T: Into<CommandResponse>>(
+        &mut self,
+        request_id: RequestId,
+        entity_id: EntityId,
+        response: T,
+    ) {
+        self.pending_ops
+            .command_response(request_id, entity_id, response);
+    }
+
+    /// Queues a failed response to a command request sent by the worker.
+    pub fn push_command_failure(
+        &mut self,
+        request_id: RequestId,
+        entity_id: EntityId,
+        component_id: ComponentId,
+        command_index: CommandIndex,
+        error: CommandResponseError,
+    ) {
+        self.pending_ops
+            .command_failure(request_id, entity_id, component_id, command_index, error);
+    }
+
+    pub fn push_reserve_entity_ids_response(
+        &mut self,
+        request_id: RequestId,
+        response: Result<(EntityId, u32), CommandResponseError>,
+    ) {
+        self.pending_ops
+            .reserve_entity_ids_response(request_id, response);
+    }
+
+    pub fn push_create_entity_response(
+        &mut self,
+        request_id: RequestId,
+        response: Result<EntityId, CommandResponseError>,
+    ) {
+        self.pending_ops
+            .create_entity_response(request_id, response);
+    }
+
+    pub fn push_delete_entity_response(
+        &mut self,
+        request_id: RequestId,
+        entity_id: EntityId,
+        response: Result<(), CommandResponseError>,
+    ) {
+        self.pending_ops
+            .delete_entity_response(request_id, entity_id, response);
+    }
+
+    fn next_request_id(&mut self) -> RequestId {
+        let request_id = RequestId::new(self.next_request_id);
+        self.next_request_id += 1;
+        request_id
+    }
+}
+
+impl Connection for MockConnection {
+    fn send_log_message(
+        &mut self,
+        level: LogLevel,
+        logger_name: &str,
+        message: &str,
+        entity_id: Option<EntityId>,
+    ) {
+        self.sent.push(SentMessage::LogMessage {
+            level,
+            logger_name: logger_name.to_owned(),
+            message: message.to_owned(),
+            entity_id,
+        });
+    }
+
+    fn send_metrics(&mut self, metrics: &Metrics) {
+        self.sent.push(SentMessage::Metrics(metrics.clone()));
+    }
+
+    fn send_reserve_entity_ids_request(
+        &mut self,
+        payload: ReserveEntityIdsRequest,
+        timeout_millis: Option<u32>,
+    ) -> RequestId {
+        let request_id = self.next_request_id();
+        self.sent.push(SentMessage::ReserveEntityIdsRequest {
+            request_id,
+            payload,
+            timeout_millis,
+        });
+        request_id
+    }
+
+    fn send_create_entity_request(
+        &mut self,
+        payload: CreateEntityRequest,
+        timeout_millis: Option<u32>,
+    ) -> RequestId {
+        let request_id = self.next_request_id();
+        self.sent.push(SentMessage::CreateEntityRequest {
+            request_id,
+            payload,
+            timeout_millis,
+        });
+        request_id
+    }
+
+    fn send_delete_entity_request(
+        &mut self,
+        payload: DeleteEntityRequest,
+        timeout_millis: Option<u32>,
+    ) -> RequestId {
+        let request_id = self.next_request_id();
+        self.sent.push(SentMessage::DeleteEntityRequest {
+            request_id,
+            payload,
+            timeout_millis,
+        });
+        request_id
+    }
+
+    fn send_entity_query_request(
+        &mut self,
+        payload: EntityQueryRequest,
+        timeout_millis: Option<u32>,
+    ) -> RequestId {
+        let request_id = self.next_request_id();
+        self.sent.push(SentMessage::EntityQueryRequest {
+            request_id,
+            payload,
+            timeout_millis,
+        });
+        request_id
+    }
+
+    fn send_command_request<T: Into<CommandRequest>>(
+        &mut self,
+        entity_id: EntityId,
+        request: T,
+        timeout_millis: Option<u32>,
+        params: CommandParameters,
+    ) -> RequestId {
+        let request_id = self.next_request_id();
+        self.sent.push(SentMessage::CommandRequest {
+            request_id,
+            entity_id,
+            request: request.into(),
+            timeout_millis,
+            params,
+        });
+        request_id
+    }
+
+    fn send_command_response<T: Into<CommandResponse>>(
+        &mut self,
+        request_id: RequestId,
+        response: T,
+    ) {
+        self.sent.push(SentMessage::CommandResponse {
+            request_id,
+            response: response.into(),
+        });
+    }
+
+    fn send_command_failure(
+        &mut self,
+        request_id: RequestId,
+        message: &str,
+    ) -> Result<(), NulError> {
+        // Match the validation performed by `WorkerConnection`.
+        CString::new(message)?;
+
+        self.sent.push(SentMessage::CommandFailure {
+            request_id,
+            message: message.to_owned(),
+        });
+        Ok(())
+    }
+
+    fn send_component_update<T: Into<ComponentUpdate>>(
+        &mut self,
+        entity_id: EntityId,
+        update: T,
+        parameters: UpdateParameters,
+    ) {
+        self.sent.push(SentMessage::ComponentUpdate {
+            entity_id,
+            update: update.into(),
+            parameters,
+        });
+    }
+
+    fn send_add_component<C: Component>(
+        &mut self,
+        entity_id: EntityId,
+        component: &C,
+        parameters: UpdateParameters,
+    ) {
+        self.sent.push(SentMessage::AddComponent {
+            entity_id,
+            component_id: C::ID,
+            data: SchemaComponentData::from_component(component),
+            parameters,
+        });
+    }
+
+    fn send_remove_component<C: Component>(
+        &mut self,
+        entity_id: EntityId,
+        parameters: UpdateParameters,
+    ) {
+        self.sent.push(SentMessage::RemoveComponent {
+            entity_id,
+            component_id: C::ID,
+            parameters,
+        });
+    }
+
+    fn send_authority_loss_imminent_acknowledgement(
+        &mut self,
+        entity_id: EntityId,
+        component_id: ComponentId,
+    ) {
+        self.sent
+            .push(SentMessage::AuthorityLossImminentAcknowledgement {
+                entity_id,
+                component_id,
+            });
+    }
+
+    fn send_component_interest(&mut self, entity_id: EntityId, interest: &ComponentInterest) {
+        self.sent.push(SentMessage::ComponentInterest {
+            entity_id,
+            interest: interest.clone(),
+        });
+    }
+
+    fn flush(&mut self) {
+        self.sent.push(SentMessage::Flush);
+    }
+
+    fn enable_logging(&mut self) {
+        self.logging_enabled = true;
+    }
+
+    fn disable_logging(&mut self) {
+        self.logging_enabled = false;
+    }
+
+    fn get_connection_status(&mut self) -> ConnectionStatus {
+        self.connection_status.clone()
+    }
+
+    fn get_worker_flag(&mut self, name: &str) -> Option<String> {
+        self.worker_flags.get(name).cloned()
+    }
+
+    fn get_op_list(&mut self, _timeout_millis: u32) -> OpList {
+        self.pending_ops.build()
+    }
+
+    fn get_worker_id(&self) -> &str {
+        &self.worker_id
+    }
+
+    fn get_worker_attributes(&self) -> &[String] {
+        &self.worker_attributes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::op::{CommandStatusCode, WorkerOp};
+
+    #[test]
+    fn request_ids_are_unique_and_recorded() {
+        let mut connection = MockConnection::new("worker");
+
+        let first = connection.send_reserve_entity_ids_request(ReserveEntityIdsRequest(5), None);
+        let second =
+            connection.send_delete_entity_request(DeleteEntityRequest(EntityId::new(3)), Some(100));
+
+        assert_ne!(first, second);
+        assert_eq!(2, connection.sent().len());
+
+        match &connection.sent()[1] {
+            SentMessage::DeleteEntityRequest {
+                request_id,
+                payload,
+                timeout_millis,
+            } => {
+                assert_eq!(second, *request_id);
+                assert_eq!(EntityId::new(3), payload.0);
+                assert_eq!(Some(100), *timeout_millis);
+            }
+            _ => panic!("Expected a delete entity request"),
+        }
+    }
+
+    #[test]
+    fn get_op_list_returns_scripted_ops_once() {
+        let mut connection = MockConnection::new("worker");
+        connection.push_add_entity(EntityId::new(1));
+        connection.push_authority_change(EntityId::new(1), 54, Authority::Authoritative);
+        connection.push_create_entity_response(
+            RequestId::new(7),
+            Err(CommandResponseError {
+                code: CommandStatusCode::Timeout,
+                detail: "Timed out".to_owned(),
+            }),
+        );
+        assert_eq!(3, connection.pending_op_count());
+
+        let ops = connection.get_op_list(0);
+        let ops = ops.iter().collect::<Vec<_>>();
+        assert_eq!(3, ops.len());
+
+        match &ops[0] {
+            WorkerOp::AddEntity(op) => assert_eq!(EntityId::new(1), op.entity_id),
+            _ => panic!("Expected an add entity op"),
+        }
+
+        match &ops[1] {
+            WorkerOp::AuthorityChange(op) => {
+                assert_eq!(54, op.component_id);
+                assert_eq!(Authority::Authoritative, op.authority);
+            }
+            _ => panic!("Expected an authority change op"),
+        }
+
+        match &ops[2] {
+            WorkerOp::CreateEntityResponse(op) => {
+                assert_eq!(RequestId::new(7), op.request_id);
+                let error = op.response.as_ref().unwrap_err();
+                assert_eq!("Timed out", error.detail);
+            }
+            _ => panic!("Expected a create entity response op"),
+        }
+
+        assert_eq!(0, connection.get_op_list(0).iter().count());
+    }
+
+    #[test]
+    fn worker_flags_and_status_are_configurable() {
+        let mut connection = MockConnection::new("worker");
+        connection.set_worker_flag("speed", "10");
+
+        assert_eq!(Some("10".to_owned()), connection.get_worker_flag("speed"));
+        assert_eq!(None, connection.get_worker_flag("missing"));
+        assert!(connection.get_connection_status().is_ok());
+    }
+
+    #[test]
+    fn send_command_failure_rejects_null_bytes() {
+        let mut connection = MockConnection::new("worker");
+
+        assert!(connection
+            .send_command_failure(RequestId::new(1), "bad\0message")
+            .is_err());
+        assert!(connection.sent().is_empty());
+    }
+}
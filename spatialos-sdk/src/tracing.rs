@@ -0,0 +1,470 @@
+//! Safe wrappers around the SpatialOS event tracing API.
+//!
+//! An [`EventTracer`] records *spans* and *events* that describe the causal
+//! relationships between the messages a worker sends and receives. Once attached
+//! to a connection via [`ConnectionParameters::with_event_tracer`], the tracer's
+//! active span ID is attached to any component updates and command requests sent
+//! from the same thread, allowing the trace to continue across workers.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use spatialos_sdk::{parameters::ConnectionParameters, tracing::*};
+//! use std::sync::Arc;
+//!
+//! let tracer = Arc::new(EventTracer::new(|item: TraceItem| println!("{:?}", item)));
+//! tracer.enable();
+//!
+//! let params = ConnectionParameters::new("RustWorker").with_event_tracer(tracer.clone());
+//!
+//! // Any updates sent after this point on the current thread are caused by `span`.
+//! let span = tracer.add_span(&[]);
+//! tracer.set_active_span_id(span);
+//! ```
+//!
+//! [`EventTracer`]: struct.EventTracer.html
+//! [`ConnectionParameters::with_event_tracer`]: ../parameters/struct.ConnectionParameters.html#method.with_event_tracer
+
+use spatialos_sdk_sys::worker::*;
+use std::{
+    collections::HashMap,
+    ffi::{CStr, CString},
+    fmt::{self, Debug, Formatter},
+    hash::{Hash, Hasher},
+    os::raw::{c_char, c_void},
+    ptr::{self, NonNull},
+    slice,
+};
+
+/// An identifier for a span which is expected to be unique across a deployment.
+#[derive(Copy, Clone)]
+pub struct SpanId(Trace_SpanId);
+
+impl SpanId {
+    /// Returns the null span ID, used to indicate that a span should not be traced.
+    pub fn null() -> Self {
+        SpanId(unsafe { Trace_SpanId_Null() })
+    }
+
+    /// Returns a randomly generated span ID. This should only be used for testing.
+    pub fn generate_test_span_id() -> Self {
+        SpanId(unsafe { Trace_SpanId_GenerateTestSpanId() })
+    }
+
+    pub fn is_null(self) -> bool {
+        self == SpanId::null()
+    }
+
+    /// Returns the raw bytes of the span ID.
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        &self.0.data
+    }
+}
+
+impl Default for SpanId {
+    fn default() -> Self {
+        SpanId::null()
+    }
+}
+
+impl PartialEq for SpanId {
+    fn eq(&self, other: &Self) -> bool {
+        unsafe { Trace_SpanId_Equal(self.0, other.0) != 0 }
+    }
+}
+
+impl Eq for SpanId {}
+
+impl Hash for SpanId {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        unsafe { Trace_SpanId_Hash(self.0) }.hash(state);
+    }
+}
+
+impl Debug for SpanId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "SpanId(")?;
+        for byte in self.as_bytes() {
+            write!(f, "{:02x}", byte)?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// A set of string key-value pairs attached to a traced event.
+pub struct EventData {
+    ptr: NonNull<Trace_EventData>,
+}
+
+impl EventData {
+    pub fn new() -> Self {
+        let ptr = unsafe { Trace_EventData_Create() };
+        EventData {
+            ptr: NonNull::new(ptr).expect("Received null pointer from Trace_EventData_Create"),
+        }
+    }
+
+    /// Adds a single field to the event data.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if `key` or `value` contain a 0 byte.
+    pub fn add_field<K: AsRef<str>, V: AsRef<str>>(&mut self, key: K, value: V) {
+        self.add_fields(&[(key, value)]);
+    }
+
+    /// Adds a set of fields to the event data.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if any key or value contains a 0 byte.
+    pub fn add_fields<K: AsRef<str>, V: AsRef<str>>(&mut self, fields: &[(K, V)]) {
+        let keys = fields
+            .iter()
+            .map(|(key, _)| CString::new(key.as_ref()).expect("`key` contained a null byte"))
+            .collect::<Vec<_>>();
+        let values = fields
+            .iter()
+            .map(|(_, value)| CString::new(value.as_ref()).expect("`value` contained a null byte"))
+            .collect::<Vec<_>>();
+
+        let mut key_ptrs = keys.iter().map(|key| key.as_ptr()).collect::<Vec<_>>();
+        let mut value_ptrs = values
+            .iter()
+            .map(|value| value.as_ptr())
+            .collect::<Vec<_>>();
+
+        unsafe {
+            Trace_EventData_AddStringFields(
+                self.ptr.as_ptr(),
+                fields.len() as u32,
+                key_ptrs.as_mut_ptr(),
+                value_ptrs.as_mut_ptr(),
+            );
+        }
+    }
+
+    pub fn field_count(&self) -> usize {
+        unsafe { Trace_EventData_GetFieldCount(self.ptr.as_ptr()) as usize }
+    }
+
+    /// Returns the value of the field named `key`, if present.
+    pub fn get<K: AsRef<str>>(&self, key: K) -> Option<String> {
+        let key = CString::new(key.as_ref()).ok()?;
+        let value = unsafe { Trace_EventData_GetFieldValue(self.ptr.as_ptr(), key.as_ptr()) };
+        cstr_to_option_string(value)
+    }
+
+    /// Returns all fields in the event data. The ordering of the fields is arbitrary.
+    pub fn fields(&self) -> HashMap<String, String> {
+        unsafe { event_data_fields(self.ptr.as_ptr()) }
+    }
+}
+
+impl Default for EventData {
+    fn default() -> Self {
+        EventData::new()
+    }
+}
+
+impl Debug for EventData {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.fields()).finish()
+    }
+}
+
+impl Drop for EventData {
+    fn drop(&mut self) {
+        unsafe { Trace_EventData_Destroy(self.ptr.as_ptr()) }
+    }
+}
+
+// SAFETY: Event data has no thread affinity, and is only mutated through `&mut self`.
+unsafe impl Send for EventData {}
+
+/// An event to be added to an [`EventTracer`].
+///
+/// [`EventTracer`]: struct.EventTracer.html
+#[derive(Debug)]
+pub struct Event<'a> {
+    pub span_id: SpanId,
+    pub message: &'a str,
+    pub event_type: &'a str,
+    pub data: Option<&'a EventData>,
+}
+
+impl<'a> Event<'a> {
+    pub fn new(span_id: SpanId, message: &'a str, event_type: &'a str) -> Self {
+        Event {
+            span_id,
+            message,
+            event_type,
+            data: None,
+        }
+    }
+
+    pub fn with_data(mut self, data: &'a EventData) -> Self {
+        self.data = Some(data);
+        self
+    }
+}
+
+/// A span or event recorded by an [`EventTracer`], as passed to its callback.
+///
+/// [`EventTracer`]: struct.EventTracer.html
+#[derive(Debug, Clone)]
+pub enum TraceItem {
+    Span {
+        id: SpanId,
+        causes: Vec<SpanId>,
+    },
+    Event {
+        span_id: SpanId,
+        unix_timestamp_millis: u64,
+        message: String,
+        event_type: String,
+        data: HashMap<String, String>,
+    },
+}
+
+impl TraceItem {
+    unsafe fn from_worker_sdk(item: &Trace_Item) -> Option<Self> {
+        match i32::from(item.item_type) {
+            Trace_ItemType_TRACE_ITEM_TYPE_SPAN => {
+                let span = item.item.span;
+                let causes = if span.causes.is_null() {
+                    Vec::new()
+                } else {
+                    slice::from_raw_parts(span.causes, span.cause_count as usize)
+                        .iter()
+                        .map(|cause| SpanId(*cause))
+                        .collect()
+                };
+
+                Some(TraceItem::Span {
+                    id: SpanId(span.id),
+                    causes,
+                })
+            }
+            Trace_ItemType_TRACE_ITEM_TYPE_EVENT => {
+                let event = item.item.event;
+                let data = if event.data.is_null() {
+                    HashMap::new()
+                } else {
+                    event_data_fields(event.data)
+                };
+
+                Some(TraceItem::Event {
+                    span_id: SpanId(event.span_id),
+                    unix_timestamp_millis: event.unix_timestamp_millis,
+                    message: cstr_to_option_string(event.message).unwrap_or_default(),
+                    event_type: cstr_to_option_string(event.type_).unwrap_or_default(),
+                    data,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+type TraceCallback = Box<dyn Fn(TraceItem) + Send + Sync>;
+
+/// Records spans and events, and forwards them to a user-provided callback.
+///
+/// The tracer is initially disabled. While disabled, [`add_span`] returns a null
+/// span ID and the callback is not invoked. Use [`enable`] and [`disable`] to
+/// control whether new spans are sampled.
+///
+/// An `EventTracer` can be shared between threads. Note that the active span ID is
+/// tracked per thread.
+///
+/// [`add_span`]: #method.add_span
+/// [`enable`]: #method.enable
+/// [`disable`]: #method.disable
+pub struct EventTracer {
+    ptr: NonNull<Trace_EventTracer>,
+
+    // The boxed callback is referenced by the C API as user data, so it must live as
+    // long as the tracer itself.
+    _callback: Box<TraceCallback>,
+}
+
+impl EventTracer {
+    pub fn new<F: Fn(TraceItem) + Send + Sync + 'static>(callback: F) -> Self {
+        let mut callback: Box<TraceCallback> = Box::new(Box::new(callback));
+
+        let params = Trace_EventTracer_Parameters {
+            callback: Some(trace_callback),
+            user_data: &mut *callback as *mut TraceCallback as *mut c_void,
+        };
+
+        let ptr = unsafe { Trace_EventTracer_Create(&params) };
+        EventTracer {
+            ptr: NonNull::new(ptr).expect("Received null pointer from Trace_EventTracer_Create"),
+            _callback: callback,
+        }
+    }
+
+    pub fn enable(&self) {
+        unsafe { Trace_EventTracer_Enable(self.ptr.as_ptr()) }
+    }
+
+    pub fn disable(&self) {
+        unsafe { Trace_EventTracer_Disable(self.ptr.as_ptr()) }
+    }
+
+    /// Sets the active span ID for the current thread.
+    ///
+    /// Messages sent through a connection using this tracer on the current thread
+    /// will be attached to this span until it is cleared or replaced.
+    pub fn set_active_span_id(&self, span_id: SpanId) {
+        unsafe { Trace_EventTracer_SetActiveSpanId(self.ptr.as_ptr(), span_id.0) }
+    }
+
+    /// Clears the active span ID for the current thread.
+    pub fn clear_active_span_id(&self) {
+        unsafe { Trace_EventTracer_ClearActiveSpanId(self.ptr.as_ptr()) }
+    }
+
+    pub fn active_span_id(&self) -> SpanId {
+        SpanId(unsafe { Trace_EventTracer_GetActiveSpanId(self.ptr.as_ptr()) })
+    }
+
+    /// Adds a new span caused by `causes`, returning its ID.
+    ///
+    /// Returns a null span ID if the tracer is disabled.
+    pub fn add_span(&self, causes: &[SpanId]) -> SpanId {
+        let causes = causes.iter().map(|cause| cause.0).collect::<Vec<_>>();
+        let causes_ptr = if causes.is_empty() {
+            ptr::null()
+        } else {
+            causes.as_ptr()
+        };
+
+        SpanId(unsafe {
+            Trace_EventTracer_AddSpan(self.ptr.as_ptr(), causes_ptr, causes.len() as u32)
+        })
+    }
+
+    /// Adds an event to the tracer.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if the event's message or type contain a 0 byte.
+    pub fn add_event(&self, event: &Event<'_>) {
+        let message = CString::new(event.message).expect("`message` contained a null byte");
+        let event_type =
+            CString::new(event.event_type).expect("`event_type` contained a null byte");
+
+        let raw_event = Trace_Event {
+            span_id: event.span_id.0,
+            unix_timestamp_millis: 0,
+            message: message.as_ptr(),
+            type_: event_type.as_ptr(),
+            data: event
+                .data
+                .map(|data| data.ptr.as_ptr() as *const _)
+                .unwrap_or_else(ptr::null),
+        };
+
+        unsafe { Trace_EventTracer_AddEvent(self.ptr.as_ptr(), &raw_event) }
+    }
+
+    /// Returns `true` if an event on `span_id` would be sampled.
+    ///
+    /// This can be used to avoid building expensive event messages or data for
+    /// events that would be discarded.
+    pub fn should_sample_event(&self, span_id: SpanId) -> bool {
+        let event = Trace_Event {
+            span_id: span_id.0,
+            ..Default::default()
+        };
+
+        unsafe { Trace_EventTracer_ShouldSampleEvent(self.ptr.as_ptr(), &event) != 0 }
+    }
+
+    pub(crate) fn as_ptr(&self) -> *const Trace_EventTracer {
+        self.ptr.as_ptr()
+    }
+}
+
+impl Debug for EventTracer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventTracer")
+            .field("ptr", &self.ptr)
+            .finish()
+    }
+}
+
+impl Drop for EventTracer {
+    fn drop(&mut self) {
+        unsafe { Trace_EventTracer_Destroy(self.ptr.as_ptr()) }
+    }
+}
+
+// SAFETY: The event tracer is designed to be used concurrently by the worker connection's
+// internal threads and user code, and the callback is required to be `Send + Sync`.
+unsafe impl Send for EventTracer {}
+unsafe impl Sync for EventTracer {}
+
+unsafe extern "C" fn trace_callback(user_data: *mut c_void, item: *const Trace_Item) {
+    assert!(!item.is_null());
+    let callback = &*(user_data as *const TraceCallback);
+    if let Some(item) = TraceItem::from_worker_sdk(&*item) {
+        callback(item);
+    }
+}
+
+unsafe fn event_data_fields(data: *const Trace_EventData) -> HashMap<String, String> {
+    let count = Trace_EventData_GetFieldCount(data) as usize;
+    let mut keys: Vec<*const c_char> = vec![ptr::null(); count];
+    let mut values: Vec<*const c_char> = vec![ptr::null(); count];
+    Trace_EventData_GetStringFields(data, keys.as_mut_ptr(), values.as_mut_ptr());
+
+    keys.into_iter()
+        .zip(values)
+        .filter_map(|(key, value)| {
+            Some((cstr_to_option_string(key)?, cstr_to_option_string(value)?))
+        })
+        .collect()
+}
+
+fn cstr_to_option_string(ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+
+    Some(unsafe { CStr::from_ptr(ptr) }.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn null_span_id_is_null() {
+        assert!(SpanId::null().is_null());
+        assert!(SpanId::default().is_null());
+    }
+
+    #[test]
+    fn generated_span_ids_are_distinct() {
+        let first = SpanId::generate_test_span_id();
+        let second = SpanId::generate_test_span_id();
+
+        assert!(!first.is_null());
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn event_data_returns_added_fields() {
+        let mut data = EventData::new();
+        data.add_field("entity_id", "10");
+        data.add_fields(&[("component_id", "54"), ("reason", "update")]);
+
+        assert_eq!(3, data.field_count());
+        assert_eq!(Some("10".to_owned()), data.get("entity_id"));
+        assert_eq!(None, data.get("missing"));
+        assert_eq!(Some(&"update".to_owned()), data.fields().get("reason"));
+    }
+}
@@ -0,0 +1,371 @@
+//! A local cache of the entities and components checked out by a worker.
+//!
+//! A [`View`] consumes the ops received from SpatialOS and keeps track of which
+//! entities are checked out, the latest data for each of their components, and the
+//! worker's authority over each component.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use spatialos_sdk::{
+//!     connection::{Connection, WorkerConnection},
+//!     view::View,
+//! };
+//!
+//! # let mut connection: WorkerConnection = unimplemented!();
+//! let mut view = View::new();
+//! loop {
+//!     let ops = connection.get_op_list(0);
+//!     view.process_op_list(&ops).expect("Failed to apply ops to the view");
+//!
+//!     for entity_id in view.entity_ids() {
+//!         // Perform per-entity logic based on the current state of the view.
+//!     }
+//! }
+//! ```
+//!
+//! [`View`]: struct.View.html
+
+use crate::{
+    component::{Component, ComponentId},
+    op::{OpList, WorkerOp},
+    schema::{self, Owned, SchemaComponentData},
+    Authority, EntityId,
+};
+use std::collections::{hash_map, HashMap};
+
+mod writer;
+
+pub use self::writer::*;
+
+/// The locally cached state of the entities checked out by a worker.
+///
+/// The view is updated by passing it every op list received from the connection via
+/// [`process_op_list`]. Component data is stored in its schema representation, and is
+/// only deserialized into a generated component type when it is accessed via [`get`].
+///
+/// [`process_op_list`]: #method.process_op_list
+/// [`get`]: #method.get
+#[derive(Debug, Default)]
+pub struct View {
+    entities: HashMap<EntityId, ViewEntity>,
+}
+
+impl View {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Applies every op in `ops` to the view.
+    ///
+    /// All ops are applied even if one of them fails, so that the view stays in sync
+    /// with the rest of the op list. The first error encountered is returned.
+    pub fn process_op_list(&mut self, ops: &OpList) -> schema::Result<()> {
+        let mut result = Ok(());
+        for op in ops {
+            let op_result = self.process_op(&op);
+            if result.is_ok() {
+                result = op_result;
+            }
+        }
+
+        result
+    }
+
+    /// Applies a single op to the view.
+    ///
+    /// Ops that don't affect entity or component state are ignored. An error is only
+    /// returned if a component update could not be applied to the cached data.
+    pub fn process_op(&mut self, op: &WorkerOp<'_>) -> schema::Result<()> {
+        match op {
+            WorkerOp::AddEntity(op) => {
+                self.entities.entry(op.entity_id).or_default();
+            }
+
+            WorkerOp::RemoveEntity(op) => {
+                self.entities.remove(&op.entity_id);
+            }
+
+            WorkerOp::AddComponent(op) => {
+                self.entities
+                    .entry(op.entity_id)
+                    .or_default()
+                    .components
+                    .insert(op.component_id, op.component_data.schema_type.to_owned());
+            }
+
+            WorkerOp::RemoveComponent(op) => {
+                if let Some(entity) = self.entities.get_mut(&op.entity_id) {
+                    entity.components.remove(&op.component_id);
+                    entity.authority.remove(&op.component_id);
+                }
+            }
+
+            WorkerOp::AuthorityChange(op) => {
+                self.entities
+                    .entry(op.entity_id)
+                    .or_default()
+                    .authority
+                    .insert(op.component_id, op.authority);
+            }
+
+            WorkerOp::ComponentUpdate(op) => {
+                let data = self
+                    .entities
+                    .get_mut(&op.entity_id)
+                    .and_then(|entity| entity.components.get_mut(&op.component_id));
+
+                // Updates for components that aren't checked out carry no useful state,
+                // since they only contain the fields that changed.
+                if let Some(data) = data {
+                    data.apply_update(op.component_update.schema_type)?;
+
+                    // Applying an update grows the data's internal storage, so replace it
+                    // with a compacted copy to keep memory usage bounded.
+                    *data = Owned::from(&**data);
+                }
+            }
+
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Returns `true` if the entity is currently checked out.
+    pub fn contains_entity(&self, entity_id: EntityId) -> bool {
+        self.entities.contains_key(&entity_id)
+    }
+
+    /// Returns an iterator over the IDs of all checked out entities.
+    pub fn entity_ids(&self) -> impl Iterator<Item = EntityId> + '_ {
+        self.entities.keys().copied()
+    }
+
+    /// Returns an iterator over all checked out entities.
+    pub fn entities(&self) -> Entities<'_> {
+        Entities {
+            inner: self.entities.iter(),
+        }
+    }
+
+    pub fn entity(&self, entity_id: EntityId) -> Option<&ViewEntity> {
+        self.entities.get(&entity_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    /// Deserializes the current value of component `C` on an entity.
+    ///
+    /// Returns `None` if the entity isn't checked out or doesn't have the component.
+    pub fn get<C: Component>(&self, entity_id: EntityId) -> Option<schema::Result<C>> {
+        self.entity(entity_id)?.get::<C>()
+    }
+
+    /// Returns the schema data for a component on an entity.
+    pub fn get_data(
+        &self,
+        entity_id: EntityId,
+        component_id: ComponentId,
+    ) -> Option<&SchemaComponentData> {
+        self.entity(entity_id)?.get_data(component_id)
+    }
+
+    /// Returns the worker's authority over component `C` on an entity.
+    pub fn authority<C: Component>(&self, entity_id: EntityId) -> Option<Authority> {
+        self.authority_by_id(entity_id, C::ID)
+    }
+
+    pub fn authority_by_id(
+        &self,
+        entity_id: EntityId,
+        component_id: ComponentId,
+    ) -> Option<Authority> {
+        self.entity(entity_id)?.authority_by_id(component_id)
+    }
+
+    /// Returns `true` if the worker is authoritative over component `C` on an entity.
+    ///
+    /// Authority is considered to be held while in the `AuthorityLossImminent` state.
+    pub fn has_authority<C: Component>(&self, entity_id: EntityId) -> bool {
+        self.authority::<C>(entity_id)
+            .map_or(false, Authority::has_authority)
+    }
+
+    /// Removes all entities from the view.
+    pub fn clear(&mut self) {
+        self.entities.clear();
+    }
+}
+
+/// The cached state of a single entity in a [`View`].
+///
+/// [`View`]: struct.View.html
+#[derive(Debug, Default)]
+pub struct ViewEntity {
+    components: HashMap<ComponentId, Owned<SchemaComponentData>>,
+    authority: HashMap<ComponentId, Authority>,
+}
+
+impl ViewEntity {
+    /// Deserializes the current value of component `C`.
+    pub fn get<C: Component>(&self) -> Option<schema::Result<C>> {
+        self.get_data(C::ID).map(SchemaComponentData::deserialize)
+    }
+
+    pub fn get_data(&self, component_id: ComponentId) -> Option<&SchemaComponentData> {
+        self.components.get(&component_id).map(|data| &**data)
+    }
+
+    pub fn has_component<C: Component>(&self) -> bool {
+        self.components.contains_key(&C::ID)
+    }
+
+    /// Returns an iterator over the IDs of the components on the entity.
+    pub fn component_ids(&self) -> impl Iterator<Item = ComponentId> + '_ {
+        self.components.keys().copied()
+    }
+
+    pub fn authority<C: Component>(&self) -> Option<Authority> {
+        self.authority_by_id(C::ID)
+    }
+
+    pub fn authority_by_id(&self, component_id: ComponentId) -> Option<Authority> {
+        self.authority.get(&component_id).copied()
+    }
+
+    pub fn has_authority<C: Component>(&self) -> bool {
+        self.authority::<C>()
+            .map_or(false, Authority::has_authority)
+    }
+}
+
+/// An iterator over the entities in a [`View`].
+///
+/// [`View`]: struct.View.html
+#[derive(Debug)]
+pub struct Entities<'a> {
+    inner: hash_map::Iter<'a, EntityId, ViewEntity>,
+}
+
+impl<'a> Iterator for Entities<'a> {
+    type Item = (EntityId, &'a ViewEntity);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(&id, entity)| (id, entity))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        op::*,
+        schema::{SchemaComponentUpdate, SchemaInt32},
+    };
+
+    const COMPONENT_ID: ComponentId = 1000;
+
+    fn add_component(builder: &mut OpListBuilder, entity_id: EntityId, value: i32) {
+        let mut data = SchemaComponentData::new();
+        data.fields_mut().add::<SchemaInt32>(1, &value);
+
+        builder.op(OwnedWorkerOp::AddComponent(OwnedAddComponentOp {
+            entity_id,
+            component_id: COMPONENT_ID,
+            component_data: data,
+        }));
+    }
+
+    fn update_component(builder: &mut OpListBuilder, entity_id: EntityId, value: i32) {
+        let mut update = SchemaComponentUpdate::new();
+        update.fields_mut().add::<SchemaInt32>(1, &value);
+
+        builder.op(OwnedWorkerOp::ComponentUpdate(OwnedComponentUpdateOp {
+            entity_id,
+            component_id: COMPONENT_ID,
+            component_update: update,
+        }));
+    }
+
+    fn value(view: &View, entity_id: EntityId) -> Option<i32> {
+        view.get_data(entity_id, COMPONENT_ID)
+            .map(|data| data.fields().get::<SchemaInt32>(1).unwrap())
+    }
+
+    #[test]
+    fn tracks_added_and_removed_entities() {
+        let mut builder = OpListBuilder::new();
+        builder
+            .add_entity(EntityId::new(1))
+            .add_entity(EntityId::new(2));
+        add_component(&mut builder, EntityId::new(1), 5);
+
+        let mut view = View::new();
+        view.process_op_list(&builder.build()).unwrap();
+        assert_eq!(2, view.len());
+        assert_eq!(Some(5), value(&view, EntityId::new(1)));
+
+        let ops = OpListBuilder::new().remove_entity(EntityId::new(1)).build();
+        view.process_op_list(&ops).unwrap();
+        assert!(!view.contains_entity(EntityId::new(1)));
+        assert!(view.contains_entity(EntityId::new(2)));
+        assert_eq!(None, value(&view, EntityId::new(1)));
+    }
+
+    #[test]
+    fn applies_component_updates() {
+        let mut builder = OpListBuilder::new();
+        builder.add_entity(EntityId::new(1));
+        add_component(&mut builder, EntityId::new(1), 5);
+        update_component(&mut builder, EntityId::new(1), 6);
+        update_component(&mut builder, EntityId::new(1), 7);
+
+        let mut view = View::new();
+        view.process_op_list(&builder.build()).unwrap();
+        assert_eq!(Some(7), value(&view, EntityId::new(1)));
+    }
+
+    #[test]
+    fn ignores_updates_for_missing_components() {
+        let mut builder = OpListBuilder::new();
+        builder.add_entity(EntityId::new(1));
+        update_component(&mut builder, EntityId::new(1), 6);
+
+        let mut view = View::new();
+        view.process_op_list(&builder.build()).unwrap();
+        assert_eq!(None, value(&view, EntityId::new(1)));
+    }
+
+    #[test]
+    fn tracks_authority_per_component() {
+        let mut builder = OpListBuilder::new();
+        builder.add_entity(EntityId::new(1));
+        add_component(&mut builder, EntityId::new(1), 5);
+        builder.authority_change(EntityId::new(1), COMPONENT_ID, Authority::Authoritative);
+
+        let mut view = View::new();
+        view.process_op_list(&builder.build()).unwrap();
+        assert_eq!(
+            Some(Authority::Authoritative),
+            view.authority_by_id(EntityId::new(1), COMPONENT_ID)
+        );
+
+        let ops = OpListBuilder::new()
+            .remove_component(EntityId::new(1), COMPONENT_ID)
+            .build();
+        view.process_op_list(&ops).unwrap();
+        assert_eq!(None, view.authority_by_id(EntityId::new(1), COMPONENT_ID));
+        assert_eq!(None, value(&view, EntityId::new(1)));
+    }
+}
@@ -0,0 +1,418 @@
+use crate::{
+    component::{ComponentId, ComponentUpdate, UpdateParameters},
+    connection::Connection,
+    logging::LogLevel,
+    view::View,
+    Authority, EntityId,
+};
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+const LOGGER_NAME: &str = "ComponentWriter";
+
+/// What a [`ComponentWriter`] does with an update for a component that the worker
+/// isn't authoritative over.
+///
+/// [`ComponentWriter`]: struct.ComponentWriter.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnauthorizedUpdates {
+    /// Return a [`NotAuthoritativeError`] without sending the update.
+    ///
+    /// [`NotAuthoritativeError`]: struct.NotAuthoritativeError.html
+    Reject,
+
+    /// Hold on to the update until the worker gains authority over the component.
+    Queue,
+}
+
+/// The result of a successful call to [`ComponentWriter::send_component_update`].
+///
+/// [`ComponentWriter::send_component_update`]: struct.ComponentWriter.html#method.send_component_update
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteStatus {
+    Sent,
+    Queued,
+}
+
+/// The error returned when sending an update for a component that the worker isn't
+/// authoritative over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotAuthoritativeError {
+    pub entity_id: EntityId,
+    pub component_id: ComponentId,
+}
+
+impl Display for NotAuthoritativeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Not authoritative over component {} on {}",
+            self.component_id, self.entity_id
+        )
+    }
+}
+
+impl Error for NotAuthoritativeError {}
+
+struct QueuedUpdate {
+    entity_id: EntityId,
+    update: ComponentUpdate,
+    parameters: UpdateParameters,
+
+    /// Whether the entity has been in the view since the update was queued.
+    seen: bool,
+}
+
+/// Sends component updates only for components that the worker is authoritative over.
+///
+/// SpatialOS silently drops updates sent for components that the worker isn't
+/// authoritative over. A `ComponentWriter` checks the worker's authority in a [`View`]
+/// before sending each update, and either rejects the update or queues it until
+/// authority is gained, depending on the configured [`UnauthorizedUpdates`] behaviour.
+///
+/// Updates sent while in the `AuthorityLossImminent` state are still sent, since the
+/// worker is still authoritative, but a warning can optionally be logged through the
+/// connection.
+///
+/// # Examples
+///
+/// ```no_run
+/// use spatialos_sdk::{
+///     component::{ComponentUpdate, UpdateParameters},
+///     connection::{Connection, WorkerConnection},
+///     view::{ComponentWriter, UnauthorizedUpdates, View},
+///     EntityId,
+/// };
+///
+/// # let mut connection: WorkerConnection = unimplemented!();
+/// # let update: ComponentUpdate = unimplemented!();
+/// let mut view = View::new();
+/// let mut writer = ComponentWriter::new()
+///     .with_unauthorized_updates(UnauthorizedUpdates::Queue)
+///     .with_authority_loss_imminent_warnings(true);
+///
+/// writer
+///     .send_component_update(&mut connection, &view, EntityId::new(1), update, UpdateParameters::new())
+///     .expect("Queued updates are never rejected");
+///
+/// loop {
+///     let ops = connection.get_op_list(0);
+///     view.process_op_list(&ops).expect("Failed to apply ops to the view");
+///     writer.send_queued_updates(&mut connection, &view);
+/// }
+/// ```
+///
+/// [`View`]: struct.View.html
+/// [`UnauthorizedUpdates`]: enum.UnauthorizedUpdates.html
+pub struct ComponentWriter {
+    unauthorized_updates: UnauthorizedUpdates,
+    warn_on_authority_loss_imminent: bool,
+    queued: Vec<QueuedUpdate>,
+}
+
+impl ComponentWriter {
+    /// Creates a writer that rejects unauthorized updates and doesn't log warnings.
+    pub fn new() -> Self {
+        ComponentWriter {
+            unauthorized_updates: UnauthorizedUpdates::Reject,
+            warn_on_authority_loss_imminent: false,
+            queued: Vec::new(),
+        }
+    }
+
+    pub fn with_unauthorized_updates(mut self, unauthorized_updates: UnauthorizedUpdates) -> Self {
+        self.unauthorized_updates = unauthorized_updates;
+        self
+    }
+
+    /// Sets whether a warning is logged through the connection when an update is
+    /// sent while in the `AuthorityLossImminent` state.
+    pub fn with_authority_loss_imminent_warnings(mut self, enabled: bool) -> Self {
+        self.warn_on_authority_loss_imminent = enabled;
+        self
+    }
+
+    /// Returns the number of updates waiting for authority.
+    pub fn queued_count(&self) -> usize {
+        self.queued.len()
+    }
+
+    /// Discards every queued update.
+    pub fn clear_queued(&mut self) {
+        self.queued.clear();
+    }
+
+    /// Sends `update` if the worker is authoritative over the component according to
+    /// `view`.
+    ///
+    /// Otherwise, the update is either queued or rejected with a
+    /// [`NotAuthoritativeError`], depending on the configured [`UnauthorizedUpdates`]
+    /// behaviour.
+    ///
+    /// [`NotAuthoritativeError`]: struct.NotAuthoritativeError.html
+    /// [`UnauthorizedUpdates`]: enum.UnauthorizedUpdates.html
+    pub fn send_component_update<C, T>(
+        &mut self,
+        connection: &mut C,
+        view: &View,
+        entity_id: EntityId,
+        update: T,
+        parameters: UpdateParameters,
+    ) -> Result<WriteStatus, NotAuthoritativeError>
+    where
+        C: Connection,
+        T: Into<ComponentUpdate>,
+    {
+        let update = update.into();
+        let authority = view.authority_by_id(entity_id, update.component_id);
+        if authority.map_or(false, Authority::has_authority) {
+            self.send(connection, authority, entity_id, update, parameters);
+            return Ok(WriteStatus::Sent);
+        }
+
+        match self.unauthorized_updates {
+            UnauthorizedUpdates::Reject => Err(NotAuthoritativeError {
+                entity_id,
+                component_id: update.component_id,
+            }),
+            UnauthorizedUpdates::Queue => {
+                self.queued.push(QueuedUpdate {
+                    entity_id,
+                    update,
+                    parameters,
+                    seen: view.contains_entity(entity_id),
+                });
+                Ok(WriteStatus::Queued)
+            }
+        }
+    }
+
+    /// Sends every queued update for which the worker has since gained authority,
+    /// returning the number of updates sent.
+    ///
+    /// Queued updates are sent in the order they were queued. Updates for entities that
+    /// haven't been checked out yet, such as entities the worker has just created, stay
+    /// queued until the entity is added to `view`. Once an entity has been in `view`,
+    /// updates for it are discarded if it's removed again, since the worker can't gain
+    /// authority over it without checking the entity out again.
+    pub fn send_queued_updates<C: Connection>(&mut self, connection: &mut C, view: &View) -> usize {
+        let mut sent = 0;
+        for mut queued in std::mem::take(&mut self.queued) {
+            if !view.contains_entity(queued.entity_id) {
+                if !queued.seen {
+                    self.queued.push(queued);
+                }
+                continue;
+            }
+
+            queued.seen = true;
+
+            let authority = view.authority_by_id(queued.entity_id, queued.update.component_id);
+            if authority.map_or(false, Authority::has_authority) {
+                self.send(
+                    connection,
+                    authority,
+                    queued.entity_id,
+                    queued.update,
+                    queued.parameters,
+                );
+                sent += 1;
+            } else {
+                self.queued.push(queued);
+            }
+        }
+
+        sent
+    }
+
+    fn send<C: Connection>(
+        &self,
+        connection: &mut C,
+        authority: Option<Authority>,
+        entity_id: EntityId,
+        update: ComponentUpdate,
+        parameters: UpdateParameters,
+    ) {
+        if self.warn_on_authority_loss_imminent
+            && authority == Some(Authority::AuthorityLossImminent)
+        {
+            let message = format!(
+                "Sending an update for component {} while authority loss is imminent",
+                update.component_id
+            );
+            connection.send_log_message(LogLevel::Warn, LOGGER_NAME, &message, Some(entity_id));
+        }
+
+        connection.send_component_update(entity_id, update, parameters);
+    }
+}
+
+impl Default for ComponentWriter {
+    fn default() -> Self {
+        ComponentWriter::new()
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod test {
+    use super::*;
+    use crate::{
+        op::OpListBuilder,
+        schema::SchemaComponentUpdate,
+        testing::{MockConnection, SentMessage},
+    };
+
+    const COMPONENT_ID: ComponentId = 1000;
+
+    fn update() -> ComponentUpdate {
+        ComponentUpdate {
+            schema_data: SchemaComponentUpdate::new(),
+            component_id: COMPONENT_ID,
+        }
+    }
+
+    fn view_with_authority(authority: Authority) -> View {
+        let mut view = View::new();
+        let ops = OpListBuilder::new()
+            .add_entity(EntityId::new(1))
+            .authority_change(EntityId::new(1), COMPONENT_ID, authority)
+            .build();
+        view.process_op_list(&ops).unwrap();
+        view
+    }
+
+    #[test]
+    fn rejects_updates_without_authority() {
+        let mut connection = MockConnection::new("test_worker");
+        let view = view_with_authority(Authority::NotAuthoritative);
+        let mut writer = ComponentWriter::new();
+
+        let result = writer.send_component_update(
+            &mut connection,
+            &view,
+            EntityId::new(1),
+            update(),
+            UpdateParameters::new(),
+        );
+
+        assert_eq!(
+            Err(NotAuthoritativeError {
+                entity_id: EntityId::new(1),
+                component_id: COMPONENT_ID,
+            }),
+            result
+        );
+        assert!(connection.sent().is_empty());
+    }
+
+    #[test]
+    fn queued_updates_are_sent_once_authority_is_gained() {
+        let mut connection = MockConnection::new("test_worker");
+        let mut view = view_with_authority(Authority::NotAuthoritative);
+        let mut writer =
+            ComponentWriter::new().with_unauthorized_updates(UnauthorizedUpdates::Queue);
+
+        let status = writer.send_component_update(
+            &mut connection,
+            &view,
+            EntityId::new(1),
+            update(),
+            UpdateParameters::new(),
+        );
+        assert_eq!(Ok(WriteStatus::Queued), status);
+        assert_eq!(0, writer.send_queued_updates(&mut connection, &view));
+        assert_eq!(1, writer.queued_count());
+
+        let ops = OpListBuilder::new()
+            .authority_change(EntityId::new(1), COMPONENT_ID, Authority::Authoritative)
+            .build();
+        view.process_op_list(&ops).unwrap();
+
+        assert_eq!(1, writer.send_queued_updates(&mut connection, &view));
+        assert_eq!(0, writer.queued_count());
+        match connection.sent() {
+            [SentMessage::ComponentUpdate { entity_id, .. }] => {
+                assert_eq!(EntityId::new(1), *entity_id)
+            }
+            _ => panic!("Expected a single component update"),
+        }
+    }
+
+    #[test]
+    fn warns_when_authority_loss_is_imminent() {
+        let mut connection = MockConnection::new("test_worker");
+        let view = view_with_authority(Authority::AuthorityLossImminent);
+        let mut writer = ComponentWriter::new().with_authority_loss_imminent_warnings(true);
+
+        let status = writer.send_component_update(
+            &mut connection,
+            &view,
+            EntityId::new(1),
+            update(),
+            UpdateParameters::new(),
+        );
+
+        assert_eq!(Ok(WriteStatus::Sent), status);
+        match connection.sent() {
+            [SentMessage::LogMessage { level, .. }, SentMessage::ComponentUpdate { .. }] => {
+                assert_eq!(LogLevel::Warn, *level)
+            }
+            _ => panic!("Expected a warning followed by the update"),
+        }
+    }
+
+    #[test]
+    fn updates_for_entities_not_yet_in_view_stay_queued() {
+        let mut connection = MockConnection::new("test_worker");
+        let mut view = View::new();
+        let mut writer =
+            ComponentWriter::new().with_unauthorized_updates(UnauthorizedUpdates::Queue);
+
+        let status = writer.send_component_update(
+            &mut connection,
+            &view,
+            EntityId::new(1),
+            update(),
+            UpdateParameters::new(),
+        );
+        assert_eq!(Ok(WriteStatus::Queued), status);
+        assert_eq!(0, writer.send_queued_updates(&mut connection, &view));
+        assert_eq!(1, writer.queued_count());
+
+        let ops = OpListBuilder::new()
+            .add_entity(EntityId::new(1))
+            .authority_change(EntityId::new(1), COMPONENT_ID, Authority::Authoritative)
+            .build();
+        view.process_op_list(&ops).unwrap();
+
+        assert_eq!(1, writer.send_queued_updates(&mut connection, &view));
+        assert_eq!(0, writer.queued_count());
+    }
+
+    #[test]
+    fn updates_for_entities_that_leave_the_view_are_discarded() {
+        let mut connection = MockConnection::new("test_worker");
+        let mut view = view_with_authority(Authority::NotAuthoritative);
+        let mut writer =
+            ComponentWriter::new().with_unauthorized_updates(UnauthorizedUpdates::Queue);
+
+        writer
+            .send_component_update(
+                &mut connection,
+                &view,
+                EntityId::new(1),
+                update(),
+                UpdateParameters::new(),
+            )
+            .unwrap();
+
+        let ops = OpListBuilder::new().remove_entity(EntityId::new(1)).build();
+        view.process_op_list(&ops).unwrap();
+
+        assert_eq!(0, writer.send_queued_updates(&mut connection, &view));
+        assert_eq!(0, writer.queued_count());
+        assert!(connection.sent().is_empty());
+    }
+}
@@ -0,0 +1,293 @@
+//! A typed cache of the worker flags set for a deployment.
+//!
+//! Worker flags are received as strings, either from [`Connection::get_worker_flag`] or
+//! from `FlagUpdate` ops when a flag changes at runtime. [`WorkerFlags`] keeps the
+//! current value of every flag it has seen, parses flags into typed values on demand,
+//! and notifies subscribers when a flag changes, which makes it straightforward to tune
+//! a running deployment without handling strings throughout a worker.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use spatialos_sdk::{
+//!     connection::{Connection, WorkerConnection},
+//!     worker_flags::{FlagDuration, WorkerFlags},
+//! };
+//!
+//! # let mut connection: WorkerConnection = unimplemented!();
+//! let mut flags = WorkerFlags::new();
+//! flags.fetch(&mut connection, "max_speed");
+//! flags.on_change_as::<f64, _>("max_speed", |speed| match speed {
+//!     Ok(speed) => println!("Max speed is now {}", speed),
+//!     Err(e) => eprintln!("Invalid max speed: {}", e),
+//! });
+//!
+//! loop {
+//!     let ops = connection.get_op_list(0);
+//!     flags.process_op_list(&ops);
+//!
+//!     let max_speed = flags.get_or("max_speed", 10.0);
+//!     let tick = flags.get_or("tick_interval", FlagDuration::from_millis(50));
+//! }
+//! ```
+//!
+//! [`Connection::get_worker_flag`]: ../connection/trait.Connection.html#tymethod.get_worker_flag
+//! [`WorkerFlags`]: struct.WorkerFlags.html
+
+use crate::{
+    connection::Connection,
+    op::{OpList, WorkerOp},
+};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{self, Display, Formatter},
+    ops::Deref,
+    str::FromStr,
+    time::Duration,
+};
+
+type Subscribers<'a> = HashMap<String, Vec<Box<dyn FnMut(&str) + 'a>>>;
+
+/// The current values of the worker flags, with subscribers that are notified when a
+/// flag changes.
+///
+/// Subscribers may borrow from their environment for the lifetime `'a` of the
+/// registry, in the same way as the callbacks registered with a [`Dispatcher`].
+///
+/// See the [module documentation] for more details.
+///
+/// [`Dispatcher`]: ../dispatcher/struct.Dispatcher.html
+/// [module documentation]: index.html
+#[derive(Default)]
+pub struct WorkerFlags<'a> {
+    values: HashMap<String, String>,
+    subscribers: Subscribers<'a>,
+}
+
+impl<'a> WorkerFlags<'a> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Updates the cached flags from every `FlagUpdate` op in `ops`.
+    pub fn process_op_list(&mut self, ops: &OpList) {
+        for op in ops {
+            self.process_op(&op);
+        }
+    }
+
+    pub fn process_op(&mut self, op: &WorkerOp<'_>) {
+        if let WorkerOp::FlagUpdate(op) = op {
+            self.set(&op.name, &op.value);
+        }
+    }
+
+    /// Reads the current value of the flag `name` from `connection` into the cache.
+    ///
+    /// Flags that are set when a worker connects aren't necessarily received as
+    /// `FlagUpdate` ops, so this should be called once for each flag the worker uses.
+    /// Subscribers are notified if the value differs from the cached value.
+    pub fn fetch<C: Connection>(&mut self, connection: &mut C, name: &str) -> Option<&str> {
+        if let Some(value) = connection.get_worker_flag(name) {
+            self.set(name, &value);
+        }
+
+        self.get(name)
+    }
+
+    /// Returns the raw value of the flag `name`, or `None` if it hasn't been set.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    /// Parses the value of the flag `name` into a `T`.
+    ///
+    /// Returns `None` if the flag hasn't been set.
+    pub fn parse<T: FromStr>(&self, name: &str) -> Option<Result<T, T::Err>> {
+        self.get(name).map(str::parse)
+    }
+
+    /// Parses the value of the flag `name` into a `T`, falling back to `default` if the
+    /// flag hasn't been set or can't be parsed.
+    pub fn get_or<T: FromStr>(&self, name: &str, default: T) -> T {
+        match self.parse(name) {
+            Some(Ok(value)) => value,
+            _ => default,
+        }
+    }
+
+    /// Returns an iterator over the names and values of every cached flag.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> + '_ {
+        self.values
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// Registers a callback that is invoked with the new raw value whenever the flag
+    /// `name` changes.
+    pub fn on_change<F>(&mut self, name: &str, callback: F) -> &mut Self
+    where
+        F: FnMut(&str) + 'a,
+    {
+        self.subscribers
+            .entry(name.to_owned())
+            .or_default()
+            .push(Box::new(callback));
+        self
+    }
+
+    /// Registers a callback that is invoked with the new value, parsed into a `T`,
+    /// whenever the flag `name` changes.
+    pub fn on_change_as<T, F>(&mut self, name: &str, mut callback: F) -> &mut Self
+    where
+        T: FromStr,
+        F: FnMut(Result<T, T::Err>) + 'a,
+    {
+        self.on_change(name, move |value| callback(value.parse()))
+    }
+
+    fn set(&mut self, name: &str, value: &str) {
+        if self.get(name) == Some(value) {
+            return;
+        }
+
+        self.values.insert(name.to_owned(), value.to_owned());
+        if let Some(subscribers) = self.subscribers.get_mut(name) {
+            for subscriber in subscribers {
+                subscriber(value);
+            }
+        }
+    }
+}
+
+/// A duration that can be parsed from a worker flag.
+///
+/// Durations are written as a number followed by a unit, which is one of `ms`, `s`, `m`
+/// or `h`, such as `"250ms"` or `"1.5s"`. A number without a unit is interpreted as a
+/// number of milliseconds, consistent with the timeouts used throughout the SDK.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FlagDuration(pub Duration);
+
+impl FlagDuration {
+    pub fn from_millis(millis: u64) -> Self {
+        FlagDuration(Duration::from_millis(millis))
+    }
+}
+
+impl Deref for FlagDuration {
+    type Target = Duration;
+
+    fn deref(&self) -> &Duration {
+        &self.0
+    }
+}
+
+impl From<FlagDuration> for Duration {
+    fn from(duration: FlagDuration) -> Self {
+        duration.0
+    }
+}
+
+impl FromStr for FlagDuration {
+    type Err = ParseFlagDurationError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        let split = value
+            .find(|c: char| c.is_ascii_alphabetic())
+            .unwrap_or(value.len());
+        let (number, unit) = value.split_at(split);
+
+        let millis_per_unit = match unit {
+            "" | "ms" => 1.0,
+            "s" => 1000.0,
+            "m" => 60_000.0,
+            "h" => 3_600_000.0,
+            _ => return Err(ParseFlagDurationError(value.to_owned())),
+        };
+
+        match number.trim().parse::<f64>() {
+            Ok(number) if number >= 0.0 && number.is_finite() => {
+                let nanos = (number * millis_per_unit * 1_000_000.0).round();
+                Ok(FlagDuration(Duration::from_nanos(nanos as u64)))
+            }
+            _ => Err(ParseFlagDurationError(value.to_owned())),
+        }
+    }
+}
+
+/// The error returned when a [`FlagDuration`] can't be parsed.
+///
+/// [`FlagDuration`]: struct.FlagDuration.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseFlagDurationError(String);
+
+impl Display for ParseFlagDurationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid duration: {:?}", self.0)
+    }
+}
+
+impl Error for ParseFlagDurationError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::op::OpListBuilder;
+    use std::cell::RefCell;
+
+    #[test]
+    fn parses_cached_flags() {
+        let mut flags = WorkerFlags::new();
+        let ops = OpListBuilder::new()
+            .flag_update("count", "3")
+            .flag_update("ratio", "0.5")
+            .flag_update("enabled", "true")
+            .flag_update("interval", "1.5s")
+            .build();
+        flags.process_op_list(&ops);
+
+        assert_eq!(Some(Ok(3)), flags.parse::<u32>("count"));
+        assert_eq!(0.5, flags.get_or("ratio", 1.0));
+        assert!(flags.get_or("enabled", false));
+        assert_eq!(
+            Duration::from_millis(1500),
+            *flags.get_or("interval", FlagDuration::from_millis(0))
+        );
+        assert_eq!(7, flags.get_or("missing", 7));
+        assert!(flags.parse::<u32>("ratio").unwrap().is_err());
+    }
+
+    #[test]
+    fn subscribers_are_notified_when_flags_change() {
+        let changes = RefCell::new(Vec::new());
+
+        let mut flags = WorkerFlags::new();
+        flags.on_change_as::<i32, _>("count", |value| changes.borrow_mut().push(value.ok()));
+
+        let ops = OpListBuilder::new()
+            .flag_update("count", "1")
+            .flag_update("count", "1")
+            .flag_update("other", "2")
+            .flag_update("count", "invalid")
+            .build();
+        flags.process_op_list(&ops);
+        drop(flags);
+
+        assert_eq!(vec![Some(1), None], changes.into_inner());
+    }
+
+    #[test]
+    fn parses_durations() {
+        let parse = |value: &str| value.parse::<FlagDuration>().map(Duration::from);
+
+        assert_eq!(Ok(Duration::from_millis(250)), parse("250ms"));
+        assert_eq!(Ok(Duration::from_millis(250)), parse("250"));
+        assert_eq!(Ok(Duration::from_secs(120)), parse("2m"));
+        assert_eq!(Ok(Duration::from_secs(3600)), parse(" 1h "));
+        assert!(parse("5 days").is_err());
+        assert!(parse("-1s").is_err());
+        assert!(parse("s").is_err());
+    }
+}
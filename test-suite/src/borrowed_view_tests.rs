@@ -0,0 +1,119 @@
+use crate::generated::improbable::*;
+use spatialos_sdk::{
+    component::Component,
+    op::{OpListBuilder, WorkerOp},
+    schema::FloatOrd,
+    EntityId,
+};
+use std::collections::BTreeMap;
+
+#[test]
+fn component_ref_can_be_read_from_add_component_op() {
+    let mut component_write_acl = BTreeMap::new();
+    component_write_acl.insert(
+        Position::ID,
+        WorkerRequirementSet {
+            attribute_set: vec![WorkerAttributeSet {
+                attribute: vec!["position_worker".to_owned()],
+            }],
+        },
+    );
+
+    let ops = OpListBuilder::new()
+        .add_component(
+            EntityId::new(1),
+            &Position {
+                coords: Coordinates {
+                    x: FloatOrd(1.0),
+                    y: FloatOrd(2.0),
+                    z: FloatOrd(3.0),
+                },
+            },
+        )
+        .add_component(
+            EntityId::new(1),
+            &EntityAcl {
+                read_acl: WorkerRequirementSet::default(),
+                component_write_acl,
+            },
+        )
+        .build();
+
+    let mut positions = Vec::new();
+    let mut write_acls = Vec::new();
+    for op in &ops {
+        if let WorkerOp::AddComponent(add_component) = op {
+            if let Some(position) = add_component.get_ref::<Position>() {
+                let position = position.expect("Failed to read `PositionRef`");
+                positions.push(position.coords.y);
+            }
+
+            if let Some(acl) = add_component.get_ref::<EntityAcl>() {
+                let acl = acl.expect("Failed to read `EntityAclRef`");
+                assert!(acl.read_acl.attribute_set.is_empty());
+
+                let (component_id, requirement) = acl
+                    .component_write_acl
+                    .get(0)
+                    .expect("Write ACL is empty")
+                    .expect("Failed to read write ACL entry");
+                let attribute_set = requirement
+                    .attribute_set
+                    .get(0)
+                    .expect("Requirement set is empty")
+                    .expect("Failed to read attribute set");
+                let attribute = attribute_set
+                    .attribute
+                    .get(0)
+                    .expect("Attribute set is empty")
+                    .expect("Failed to read attribute");
+                write_acls.push((component_id, attribute));
+            }
+        }
+    }
+
+    assert_eq!(vec![FloatOrd(2.0)], positions);
+    assert_eq!(vec![(Position::ID, "position_worker")], write_acls);
+}
+
+#[test]
+fn update_ref_can_be_read_from_component_update_op() {
+    let ops = OpListBuilder::new()
+        .component_update(
+            EntityId::new(1),
+            &PositionUpdate {
+                coords: Some(Coordinates {
+                    x: FloatOrd(4.0),
+                    y: FloatOrd(5.0),
+                    z: FloatOrd(6.0),
+                }),
+            },
+        )
+        .component_update(EntityId::new(1), &PositionUpdate { coords: None })
+        .component_update(
+            EntityId::new(1),
+            &MetadataUpdate {
+                entity_type: Some("updated".to_owned()),
+            },
+        )
+        .build();
+
+    let mut coords = Vec::new();
+    let mut entity_types = Vec::new();
+    for op in &ops {
+        if let WorkerOp::ComponentUpdate(update) = op {
+            if let Some(position) = update.get_ref::<Position>() {
+                let position = position.expect("Failed to read `PositionUpdateRef`");
+                coords.push(position.coords.map(|coords| coords.z));
+            }
+
+            if let Some(metadata) = update.get_ref::<Metadata>() {
+                let metadata = metadata.expect("Failed to read `MetadataUpdateRef`");
+                entity_types.push(metadata.entity_type);
+            }
+        }
+    }
+
+    assert_eq!(vec![Some(FloatOrd(6.0)), None], coords);
+    assert_eq!(vec![Some("updated")], entity_types);
+}
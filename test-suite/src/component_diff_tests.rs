@@ -0,0 +1,51 @@
+use crate::generated::improbable::*;
+use spatialos_sdk::component::{Component, ComponentUpdate};
+use spatialos_sdk::schema::FloatOrd;
+use std::collections::BTreeMap;
+
+fn entity_acl() -> EntityAcl {
+    let mut component_write_acl = BTreeMap::new();
+    component_write_acl.insert(Position::ID, WorkerRequirementSet::default());
+
+    EntityAcl {
+        read_acl: WorkerRequirementSet::default(),
+        component_write_acl,
+    }
+}
+
+#[test]
+fn diff_only_contains_changed_fields() {
+    let old = Position {
+        coords: Coordinates {
+            x: FloatOrd(1.0),
+            y: FloatOrd(2.0),
+            z: FloatOrd(3.0),
+        },
+    };
+
+    let update = Position::diff(&old, &old);
+    assert!(update.coords.is_none());
+
+    let mut new = old.clone();
+    new.coords.y = FloatOrd(5.0);
+
+    let update = Position::diff(&old, &new);
+    assert_eq!(Some(new.coords.clone()), update.coords);
+
+    let mut merged = old;
+    merged.merge_update(update);
+    assert_eq!(new.coords, merged.coords);
+}
+
+#[test]
+fn diff_clears_fields_that_became_empty() {
+    let old = entity_acl();
+    let mut new = old.clone();
+    new.component_write_acl.clear();
+
+    let update = EntityAcl::diff(&old, &new);
+    assert!(update.read_acl.is_none());
+
+    let update = ComponentUpdate::from(&update);
+    assert!(update.schema_data.is_field_cleared(2));
+}
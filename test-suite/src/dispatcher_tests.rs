@@ -0,0 +1,84 @@
+use crate::generated::improbable::*;
+use spatialos_sdk::{
+    component::Component, dispatcher::Dispatcher, op::OpListBuilder, Authority, EntityId,
+};
+use std::cell::RefCell;
+
+#[test]
+fn component_callbacks_receive_deserialized_values() {
+    let added = RefCell::new(Vec::new());
+    let updated = RefCell::new(Vec::new());
+
+    let mut dispatcher = Dispatcher::new();
+    dispatcher
+        .on_add_component::<Metadata, _>(|entity_id, metadata| {
+            let metadata = metadata.expect("Failed to deserialize `Metadata`");
+            added.borrow_mut().push((entity_id, metadata.entity_type));
+        })
+        .on_component_update::<Metadata, _>(|entity_id, update| {
+            let update = update.expect("Failed to deserialize `MetadataUpdate`");
+            updated.borrow_mut().push((entity_id, update.entity_type));
+        });
+
+    let ops = OpListBuilder::new()
+        .add_entity(EntityId::new(1))
+        .add_component(
+            EntityId::new(1),
+            &Metadata {
+                entity_type: "test".to_owned(),
+            },
+        )
+        .component_update(
+            EntityId::new(1),
+            &MetadataUpdate {
+                entity_type: Some("updated".to_owned()),
+            },
+        )
+        .build();
+    dispatcher.process_op_list(&ops);
+    drop(dispatcher);
+
+    assert_eq!(
+        vec![(EntityId::new(1), "test".to_owned())],
+        added.into_inner()
+    );
+    assert_eq!(
+        vec![(EntityId::new(1), Some("updated".to_owned()))],
+        updated.into_inner()
+    );
+}
+
+#[test]
+fn component_callbacks_are_only_invoked_for_their_component() {
+    let authority = RefCell::new(Vec::new());
+
+    let mut dispatcher = Dispatcher::new();
+    dispatcher
+        .on_add_component::<Metadata, _>(|_, _| panic!("Unexpected `Metadata` component"))
+        .on_authority_change::<Position, _>(|entity_id, auth| {
+            authority.borrow_mut().push((entity_id, auth))
+        });
+
+    let ops = OpListBuilder::new()
+        .add_entity(EntityId::new(1))
+        .add_component(
+            EntityId::new(1),
+            &Position {
+                coords: Coordinates {
+                    x: 0.0.into(),
+                    y: 0.0.into(),
+                    z: 0.0.into(),
+                },
+            },
+        )
+        .authority_change(EntityId::new(1), Metadata::ID, Authority::Authoritative)
+        .authority_change(EntityId::new(1), Position::ID, Authority::Authoritative)
+        .build();
+    dispatcher.process_op_list(&ops);
+    drop(dispatcher);
+
+    assert_eq!(
+        vec![(EntityId::new(1), Authority::Authoritative)],
+        authority.into_inner()
+    );
+}
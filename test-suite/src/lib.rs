@@ -1,7 +1,17 @@
 #[rustfmt::skip]
 pub mod generated;
 
+#[cfg(test)]
+pub mod borrowed_view_tests;
+#[cfg(test)]
+pub mod component_diff_tests;
+#[cfg(test)]
+pub mod dispatcher_tests;
 #[cfg(test)]
 pub mod entity_builder_tests;
 #[cfg(test)]
+pub mod mock_connection_tests;
+#[cfg(test)]
+pub mod serde_tests;
+#[cfg(test)]
 pub mod snapshot_integration_tests;
@@ -0,0 +1,109 @@
+use crate::generated::improbable::*;
+use spatialos_sdk::{
+    component::{Component, UpdateParameters},
+    connection::{AuthorityHandoff, Connection},
+    op::WorkerOp,
+    testing::{MockConnection, SentMessage},
+    Authority, EntityId,
+};
+
+#[test]
+fn scripted_add_component_op_can_be_deserialized() {
+    let mut connection = MockConnection::new("test_worker");
+    connection.push_add_entity(EntityId::new(1));
+    connection.push_add_component(
+        EntityId::new(1),
+        &Metadata {
+            entity_type: "test".to_owned(),
+        },
+    );
+
+    let ops = connection.get_op_list(0);
+    let metadata = ops
+        .iter()
+        .find_map(|op| match op {
+            WorkerOp::AddComponent(add_component) => Some(
+                add_component
+                    .get::<Metadata>()
+                    .expect("Component ID did not match `Metadata`")
+                    .expect("Failed to deserialize `Metadata`"),
+            ),
+            _ => None,
+        })
+        .expect("No add component op found");
+
+    assert_eq!("test", metadata.entity_type);
+}
+
+#[test]
+fn sent_component_updates_are_recorded() {
+    let mut connection = MockConnection::new("test_worker");
+    let update = MetadataUpdate {
+        entity_type: Some("updated".to_owned()),
+    };
+    connection.send_component_update(EntityId::new(5), &update, UpdateParameters::new());
+
+    match &connection.sent()[0] {
+        SentMessage::ComponentUpdate {
+            entity_id, update, ..
+        } => {
+            assert_eq!(EntityId::new(5), *entity_id);
+            assert_eq!(Metadata::ID, update.component_id);
+
+            let update = update
+                .schema_data
+                .deserialize::<MetadataUpdate>()
+                .expect("Failed to deserialize `MetadataUpdate`");
+            assert_eq!(Some("updated".to_owned()), update.entity_type);
+        }
+        _ => panic!("Expected a component update"),
+    }
+}
+
+#[test]
+fn authority_handoff_acknowledges_after_final_state() {
+    let mut connection = MockConnection::new("test_worker");
+    connection.push_authority_change(
+        EntityId::new(1),
+        Metadata::ID,
+        Authority::AuthorityLossImminent,
+    );
+
+    let mut handoff = AuthorityHandoff::new(
+        |connection: &mut MockConnection, entity_id, _component_id| {
+            connection.send_component_update(
+                entity_id,
+                &MetadataUpdate {
+                    entity_type: Some("final".to_owned()),
+                },
+                UpdateParameters::new(),
+            );
+        },
+    );
+
+    let ops = connection.get_op_list(0);
+    for op in &ops {
+        if let WorkerOp::AuthorityChange(authority_change) = op {
+            assert!(handoff.handle(&mut connection, &authority_change));
+        }
+    }
+
+    let sent = connection.sent();
+    assert_eq!(2, sent.len());
+
+    match &sent[0] {
+        SentMessage::ComponentUpdate { .. } => {}
+        _ => panic!("Expected the final state to be sent first"),
+    }
+
+    match &sent[1] {
+        SentMessage::AuthorityLossImminentAcknowledgement {
+            entity_id,
+            component_id,
+        } => {
+            assert_eq!(EntityId::new(1), *entity_id);
+            assert_eq!(Metadata::ID, *component_id);
+        }
+        _ => panic!("Expected an authority loss imminent acknowledgement"),
+    }
+}
@@ -0,0 +1,67 @@
+use crate::generated::improbable::*;
+use spatialos_sdk::component::Component;
+use spatialos_sdk::schema::FloatOrd;
+use std::collections::BTreeMap;
+
+#[test]
+fn component_round_trips_through_json() {
+    let position = Position {
+        coords: Coordinates {
+            x: FloatOrd(1.0),
+            y: FloatOrd(2.0),
+            z: FloatOrd(3.0),
+        },
+    };
+
+    let json = serde_json::to_value(&position).expect("Failed to serialize Position");
+    assert_eq!(
+        serde_json::json!({ "coords": { "x": 1.0, "y": 2.0, "z": 3.0 } }),
+        json
+    );
+
+    let deserialized: Position =
+        serde_json::from_value(json).expect("Failed to deserialize Position");
+    assert_eq!(position.coords, deserialized.coords);
+}
+
+#[test]
+fn component_with_collections_round_trips_through_json() {
+    let mut component_write_acl = BTreeMap::new();
+    component_write_acl.insert(
+        Position::ID,
+        WorkerRequirementSet {
+            attribute_set: vec![WorkerAttributeSet {
+                attribute: vec!["position_worker".to_string()],
+            }],
+        },
+    );
+
+    let acl = EntityAcl {
+        read_acl: WorkerRequirementSet::default(),
+        component_write_acl,
+    };
+
+    let json = serde_json::to_string(&acl).expect("Failed to serialize EntityAcl");
+    let deserialized: EntityAcl =
+        serde_json::from_str(&json).expect("Failed to deserialize EntityAcl");
+
+    assert_eq!(acl.read_acl, deserialized.read_acl);
+    assert_eq!(acl.component_write_acl, deserialized.component_write_acl);
+}
+
+#[test]
+fn update_round_trips_through_json() {
+    let update = PositionUpdate {
+        coords: Some(Coordinates {
+            x: FloatOrd(4.0),
+            y: FloatOrd(5.0),
+            z: FloatOrd(6.0),
+        }),
+    };
+
+    let json = serde_json::to_string(&update).expect("Failed to serialize PositionUpdate");
+    let deserialized: PositionUpdate =
+        serde_json::from_str(&json).expect("Failed to deserialize PositionUpdate");
+
+    assert_eq!(update.coords, deserialized.coords);
+}